@@ -178,9 +178,9 @@ const commands: CommandsConfig = [
       if (name === '') {
         return;
       }
-      const kind = api.getVirtualDirKindFromExt(name);
-      if (kind) {
-        api.changeVirtualDir(name, kind);
+      // アーカイブ形式はサーバーが内容から判別するため、kind は渡さない。
+      if (api.getVirtualDirKindFromExt(name)) {
+        api.changeVirtualDir(name);
         return;
       }
       if (api.isFile(name)) {
@@ -717,7 +717,8 @@ tar cvfz %d -- %s`,
       if (name === '') {
         return;
       }
-      if (api.isDir(name)) {
+      // アーカイブ内のアーカイブにも入れる。
+      if (api.isDir(name) || api.getVirtualDirKindFromExt(name)) {
         api.changeVirtualDir();
       }
     },
@@ -737,6 +738,27 @@ tar cvfz %d -- %s`,
     },
   },
 
+  // search-result モード系
+  {
+    name: 'SearchEntries',
+    async action(api, combo) {
+      if (!api.ensureNotVirtualDir('src', 'search')) {
+        return;
+      }
+      const input = await api.showPromptModal(messages[17], '*', 'all');
+      if (input === '') {
+        return;
+      }
+      api.searchEntries({ name: input, ignoreCase: true });
+    },
+  },
+  {
+    name: 'ExitSearchResult',
+    action(api, combo) {
+      api.exitSearchResult();
+    },
+  },
+
   // gallery モード系
   {
     name: 'EnterGalleryMode',
@@ -18,6 +18,9 @@ const messages: MessagesConfig = [
   'Could not determine archive type',
   'Already exists',
   'Not supported for virtual directories',
+  'Enter the archive password',
+  'Wrong password',
+  'Enter a file name pattern to search for',
 ];
 
 export { messages };
@@ -9,6 +9,7 @@ const settings: SettingsConfig = {
   maxLogCount: 1000,
   previewScrollAmount: 40,
   progressTaskLogInterval: 3000,
+  thumbnailSize: 256,
   virtualDirExcludePatterns: [],
 };
 
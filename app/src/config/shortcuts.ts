@@ -333,6 +333,18 @@ const shortcuts: ShortcutsConfig = {
         modes: ['!virtual-dir'],
       },
     ],
+    'shift+ctrl+f': [
+      {
+        cmd: 'SearchEntries',
+        modes: ['!virtual-dir'],
+      },
+    ],
+    'shift+esc': [
+      {
+        cmd: 'ExitSearchResult',
+        modes: ['search-result'],
+      },
+    ],
     'ctrl+0': [
       {
         cmd: 'ClearSort',
@@ -18,8 +18,10 @@ const LISTENER_STATUS = {
   progress: 'PROGRESS',
   progressError: 'PROGRESS_ERROR',
   progressEnd: 'PROGRESS_END',
+  progressData: 'PROGRESS_DATA',
   progressAbort: 'PROGRESS_ABORT',
   dirUpdate: 'DIR_UPDATE',
+  dirDiff: 'DIR_DIFF',
   watchError: 'WATCH_ERROR',
 } as const;
 
@@ -1,6 +1,12 @@
 import type { LISTENER_STATUS } from '@libs/ws';
 import type { EntryModel } from '@modules/DataFrame/models';
-import type { Bookmark, DirData, Mode } from '@modules/DataFrame/types';
+import type {
+  Bookmark,
+  DirData,
+  DirDiffData,
+  Entry,
+  Mode,
+} from '@modules/DataFrame/types';
 import type * as appApi from '@modules/App/api';
 import type * as dataFrameApi from '@modules/DataFrame/api';
 import type * as logApi from '@modules/LogFrame/api';
@@ -184,24 +190,6 @@ export type WsBookmarkResponse = {
   status: 'SUCCESS';
 };
 
-/**
- * vcp コマンドでスキップが発生した場合のレスポンス。
- */
-export type WsVcpSkippedResponse = {
-  /**
-   * コマンド ID。
-   */
-  cid: string;
-  /**
-   * スキップされたエントリのパス一覧。
-   */
-  data: string[];
-  /**
-   * ステータス。
-   */
-  status: 'SKIPPED';
-};
-
 /**
  * コマンド系のレスポンス群。
  * こちらから何かを送信して、それに対するレスポンスとして送信されてくるもの。
@@ -213,8 +201,7 @@ export type WsCommandResponse =
   | WsDataResponse
   | WsErrorResponse
   | WsProgressTaskResponse
-  | WsSuccessResponse
-  | WsVcpSkippedResponse;
+  | WsSuccessResponse;
 
 /**
  * 進捗状況を表すレスポンス。
@@ -229,6 +216,16 @@ export type WsProgressResponse = {
    * 進捗状況の詳細データ。
    */
   data: {
+    /**
+     * 進捗の詳細。
+     * 処理済みのバイト数やエントリ数など。詳細がない場合は null。
+     */
+    detail: {
+      bytes: number;
+      files: number;
+      totalBytes: number;
+      totalFiles: number;
+    } | null;
     /**
      * プロセス ID。
      */
@@ -289,6 +286,37 @@ export type WsProgressEndResponse = {
      * プロセス ID。
      */
     pid: string;
+    /**
+     * 処理結果。
+     * 結果を返さないタスクの場合は null。
+     */
+    result: {
+      /**
+       * 削除に失敗したエントリの一覧。
+       */
+      failures?: {
+        /**
+         * エントリのパス。
+         */
+        path: string;
+        /**
+         * 失敗した理由。
+         */
+        msg: string;
+      }[];
+      /**
+       * 検索にマッチしたエントリまたは行の数。
+       */
+      count?: number;
+      /**
+       * grep タスクでマッチした行を含むファイルの数。
+       */
+      files?: number;
+      /**
+       * vcp タスクで、すでに存在するためスキップされたエントリの一覧。
+       */
+      skipped?: string[];
+    } | null;
   };
   /**
    * ステータス。
@@ -296,6 +324,56 @@ export type WsProgressEndResponse = {
   status: (typeof LISTENER_STATUS)['progressEnd'];
 };
 
+/**
+ * grep タスクでマッチした行。
+ */
+export type GrepMatch = {
+  /**
+   * 行番号 (1 始まり)。
+   */
+  line: number;
+  /**
+   * ファイルのパス。
+   * カレントディレクトリからの相対パス。
+   */
+  path: string;
+  /**
+   * 行の内容。
+   * 長い行はマッチした位置の周辺だけに切り詰められる。
+   */
+  text: string;
+};
+
+/**
+ * 進捗の途中経過のデータを表すレスポンス。
+ */
+export type WsProgressDataResponse = {
+  /**
+   * コマンド ID。
+   */
+  cid: string;
+  /**
+   * 途中経過の詳細データ。
+   */
+  data: {
+    /**
+     * プロセス ID。
+     */
+    pid: string;
+    /**
+     * 前回の送信以降に得られたデータ。
+     * search タスクの場合、マッチしたエントリの一覧。
+     * エントリ名はカレントディレクトリからの相対パス。
+     * grep タスクの場合、マッチした行の一覧。
+     */
+    data: Entry[] | GrepMatch[];
+  };
+  /**
+   * ステータス。
+   */
+  status: (typeof LISTENER_STATUS)['progressData'];
+};
+
 /**
  * 進捗中止を表すレスポンス。
  * ProgressTask の場合に送信されてくる。
@@ -340,6 +418,26 @@ export type WsDirUpdateResponse = {
   status: (typeof LISTENER_STATUS)['dirUpdate'];
 };
 
+/**
+ * ディレクトリ更新の差分を表すレスポンス。
+ * 更新があると自動的に送信されてくる。
+ */
+export type WsDirDiffResponse = {
+  /**
+   * コマンド ID。
+   * 自動送信されるため、空文字である。
+   */
+  cid: '';
+  /**
+   * ディレクトリの差分データ。
+   */
+  data: DirDiffData;
+  /**
+   * ステータス。
+   */
+  status: (typeof LISTENER_STATUS)['dirDiff'];
+};
+
 /**
  * 監視エラーを表すレスポンス。
  * 監視ディレクトリに問題が発生した場合に送信されてくる。
@@ -379,8 +477,10 @@ export type WsWatchErrorResponse = {
  * サーバーから自動的に送信されてくるもの。
  */
 export type WsListenerResponse =
+  | WsDirDiffResponse
   | WsDirUpdateResponse
   | WsProgressAbortResponse
+  | WsProgressDataResponse
   | WsProgressEndResponse
   | WsProgressErrorResponse
   | WsProgressResponse
@@ -532,6 +632,12 @@ export type SettingsConfig = {
    * ログフレームの画面外に流れて行ってしまわないよう一定間隔で最後尾に移動する。
    */
   progressTaskLogInterval: number;
+  /**
+   * gallery モードで表示するサムネイル画像の長辺のピクセル数。
+   * サーバーはこの大きさに縮小した画像を生成し、キャッシュする。
+   * 16 から 1024 の範囲に丸められる。
+   */
+  thumbnailSize: number;
   /**
    * 仮想ディレクトリの表示に含めたくないエントリの正規表現パターン一覧。
    * ノイズになるエントリを除外するために使用する。
@@ -4,6 +4,7 @@ import { $activeFrame, $config, $modal } from '@modules/App/state';
 import {
   changeVirtualDir,
   clearEntryFilter,
+  closeSearchResult,
   getActiveEntryName,
 } from '@modules/DataFrame/api';
 import {
@@ -16,6 +17,7 @@ import {
 import {
   $activeEntryName,
   $currentDir,
+  $dirRevision,
   $historyCopy,
   $historyIndex,
   $rawEntries,
@@ -63,14 +65,16 @@ function changeDir(
         opts.errorHandler(resp.data.msg);
         return;
       }
-      const { entries, path } = resp.data;
+      const { entries, path, revision } = resp.data;
       // foo/bar というツリーを考える。
       // 今 bar にいるとして、親 (foo) に上がった時、prevDirName は bar になる。
       // 親子関係がないところに移動した場合は null になる。
       const prevDirName = getPrevDirName(path, frame);
+      closeSearchResult(frame);
       writeState($virtualDirInfo(frame), RESET);
       writeState($currentDir(frame), path);
       writeState($rawEntries(frame), entries);
+      writeState($dirRevision(frame), revision ?? 0);
       writeState(
         $activeEntryName(frame),
         // 今までいたエントリ (ディレクトリ) をカレントエントリにする。
@@ -3,6 +3,7 @@ export * from './dialog';
 export * from './entryGrid';
 export * from './misc';
 export * from './preview';
+export * from './search';
 export * from './sort';
 export * from './utils';
 export * from './vcd';
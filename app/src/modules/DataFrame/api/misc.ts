@@ -244,6 +244,21 @@ function copySrcDirPathToClipboard(frame = readState($activeFrame)): void {
   copyTextToClipboard(curDir, messages[10], messages[11]);
 }
 
+/**
+ * カレントディレクトリの全エントリをサーバーから再取得する。
+ * 最新のエントリ一覧は DIR_UPDATE として送信されてくる。
+ *
+ * @param frame - 対象フレーム
+ */
+function resyncDir(frame = readState($activeFrame)): void {
+  wsSend<WsSuccessResponse>(
+    'resync',
+    {},
+    (resp) => handleWsSendError(resp, frame),
+    frame,
+  );
+}
+
 /**
  * ディレクトリが更新された時に実行するコールバック設定を登録する。
  *
@@ -281,6 +296,7 @@ export {
   open,
   copySrcPathsToClipboard,
   copySrcDirPathToClipboard,
+  resyncDir,
   subscribeDirUpdate,
   unsubscribeDirUpdate,
 };
@@ -0,0 +1,102 @@
+import { RESET } from 'jotai/utils';
+import { readState, writeState } from '@libs/utils';
+import { $activeFrame, $config } from '@modules/App/state';
+import {
+  abortProgressTask,
+  changeDir,
+  clearEntryFilter,
+} from '@modules/DataFrame/api';
+import { handleWsSendError, wsSend } from '@modules/DataFrame/libs';
+import {
+  $activeEntryName,
+  $currentDir,
+  $rawEntries,
+  $searchResultInfo,
+  $selectedEntryNames,
+  $virtualDirInfo,
+} from '@modules/DataFrame/state';
+import { writeLog } from '@modules/LogFrame/api';
+import { ProgressTaskLog } from '@modules/LogFrame/components';
+import { $progressTaskInfo } from '@modules/LogFrame/state';
+
+import type { WsProgressTaskResponse } from '@modules/App/types';
+import type { SearchQuery } from '@modules/DataFrame/types';
+
+/**
+ * 検索結果を閉じる。
+ * 検索がまだ続いていれば中止する。
+ * エントリ一覧は元に戻さないため、呼び出し元で更新すること。
+ *
+ * @param frame - 対象フレーム
+ */
+function closeSearchResult(frame = readState($activeFrame)): void {
+  const info = readState($searchResultInfo(frame));
+  if (!info) {
+    return;
+  }
+  writeState($searchResultInfo(frame), RESET);
+  if (readState($progressTaskInfo(info.pid)).status === 'progress') {
+    abortProgressTask(info.pid, frame);
+  }
+}
+
+/**
+ * カレントディレクトリの配下からエントリを検索する。
+ * 検索は ProgressTask として実行され、LogFrame から中止できる。
+ * エントリ一覧は検索結果に置き換わり、マッチするたびに追加されていく。
+ * エントリ名はカレントディレクトリからの相対パスなため、
+ * 検索結果に対して通常のエントリと同じように他のタスクを実行できる。
+ *
+ * @param query - 検索条件
+ * @param frame - 対象フレーム
+ */
+function searchEntries(
+  query: SearchQuery,
+  frame = readState($activeFrame),
+): void {
+  // 仮想ディレクトリ内では検索できない。
+  if (readState($virtualDirInfo(frame))) {
+    const { messages } = readState($config);
+    writeLog(`search: ${messages[14]}`, 'error');
+    return;
+  }
+
+  wsSend<WsProgressTaskResponse>(
+    'search',
+    query,
+    (resp) => {
+      if (handleWsSendError(resp, frame)) {
+        return;
+      }
+      // 前回の検索が続いていれば中止する。
+      closeSearchResult(frame);
+      const { pid } = resp.data;
+      writeState($searchResultInfo(frame), { pid });
+      writeState($rawEntries(frame), (prev) =>
+        prev.filter((e) => e.name === '..'),
+      );
+      writeState($activeEntryName(frame), RESET);
+      writeState($selectedEntryNames(frame), RESET);
+      const { settings } = readState($config);
+      if (settings.clearEntryFilterOnDirChange) {
+        clearEntryFilter(frame);
+      }
+      writeLog(<ProgressTaskLog label="search" pid={pid} />, 'progress');
+    },
+    frame,
+  );
+}
+
+/**
+ * 検索結果を閉じ、カレントディレクトリのエントリ一覧に戻る。
+ *
+ * @param frame - 対象フレーム
+ */
+function exitSearchResult(frame = readState($activeFrame)): void {
+  if (!readState($searchResultInfo(frame))) {
+    return;
+  }
+  changeDir(readState($currentDir(frame)), frame);
+}
+
+export { closeSearchResult, searchEntries, exitSearchResult };
@@ -4,6 +4,7 @@ import { $activeFrame, $config, $inactiveFrame } from '@modules/App/state';
 import {
   changeDir,
   clearEntryFilter,
+  closeSearchResult,
   getActiveEntryName,
   getTargetEntryNames,
 } from '@modules/DataFrame/api';
@@ -21,51 +22,100 @@ import {
   $virtualDirInfo,
 } from '@modules/DataFrame/state';
 import { writeLog } from '@modules/LogFrame/api';
+import { ProgressTaskLog } from '@modules/LogFrame/components';
+import { showPromptModal } from '@modules/Modal/api';
 
 import type {
+  Frame,
   WsCdResponse,
+  WsProgressTaskResponse,
   WsResponse,
-  WsVcpSkippedResponse,
 } from '@modules/App/types';
 import type { VirtualDirKind } from '@modules/DataFrame/types';
 
 /**
- * 仮想ディレクトリの外に出たか否かを返す。
- * 出た場合、エラーコード E006002 のレスポンスが WebSocket サーバーから返ってくる。
+ * アーカイブ形式を判別できなかったか否かを返す。
+ * 判別できない場合、エラーコード E006006 のレスポンスが WebSocket サーバーから返ってくる。
  *
  * @param resp - サーバーからのレスポンス
- * @returns 仮想ディレクトリの外に出たか否か
+ * @returns アーカイブ形式を判別できなかったか否か
  */
-function isOutsideRoot(resp: WsResponse): boolean {
-  return isErrorResp(resp) && resp.data.code === 'E006002';
+function isUnknownFormat(resp: WsResponse): boolean {
+  return isErrorResp(resp) && resp.data.code === 'E006006';
 }
 
 /**
- * 仮想エントリのコピーがスキップされたか否かを返す型ガード。
- * スキップされたエントリがある場合は、
- * ステータスコード SKIPPED のレスポンスが WebSocket サーバーから返ってくる。
+ * パスワードが必要か、または間違っているか否かを返す。
+ * 暗号化された zip のエントリを展開する際、パスワードがなければ E006007、
+ * 間違っていれば E006008 のレスポンスが WebSocket サーバーから返ってくる。
  *
  * @param resp - サーバーからのレスポンス
- * @returns コピーがスキップされたか否か
+ * @returns パスワードが必要か、または間違っているか否か
+ */
+function isPasswordError(resp: WsResponse): boolean {
+  return (
+    isErrorResp(resp) && ['E006007', 'E006008'].includes(resp.data.code)
+  );
+}
+
+/**
+ * パスワードの入力を求め、仮想ディレクトリの情報に保存する。
+ * 一度入力したパスワードは、仮想ディレクトリから出るまで使い回される。
+ *
+ * @param resp - パスワードエラーのレスポンス
+ * @param frame - 対象フレーム
+ * @returns 入力されたか否か (キャンセル時は false) の Promise
  */
-function isCopySkipped(resp: WsResponse): resp is WsVcpSkippedResponse {
-  return resp.status === 'SKIPPED';
+async function promptPassword(
+  resp: WsResponse,
+  frame: Frame,
+): Promise<boolean> {
+  const { messages } = readState($config);
+  const wrong = isErrorResp(resp) && resp.data.code === 'E006008';
+  const message = wrong ? `${messages[16]}. ${messages[15]}` : messages[15];
+  const password = await showPromptModal(message, '');
+  const vdInfo = readState($virtualDirInfo(frame));
+  if (password === '' || !vdInfo) {
+    return false;
+  }
+  writeState($virtualDirInfo(frame), { ...vdInfo, password });
+  return true;
+}
+
+/**
+ * 仮想ディレクトリの外に出たか否かを返す。
+ * 出た場合、エラーコード E006002 のレスポンスが WebSocket サーバーから返ってくる。
+ *
+ * @param resp - サーバーからのレスポンス
+ * @returns 仮想ディレクトリの外に出たか否か
+ */
+function isOutsideRoot(resp: WsResponse): boolean {
+  return isErrorResp(resp) && resp.data.code === 'E006002';
 }
 
 /**
  * 仮想ディレクトリの種類 (アーカイブ形式) を取得する。
- * 対応している形式は zip, tar, tgz (tar.gz) のみっつ。
+ * 対応している形式は zip, tar, tgz (tar.gz), txz (tar.xz),
+ * tbz (tar.bz2, tbz2), tzst (tar.zst), 7z, iso のやっつ。
  *
  * @param path - アーカイブのパス
  * @returns 仮想ディレクトリの種類。未対応形式の場合は undefined。
  */
 function getVirtualDirKindFromExt(path: string): VirtualDirKind | undefined {
-  const matches = path.match(/\.(zip|tar|tgz|tar\.gz)$/);
+  const matches = path.match(
+    /\.(zip|tar|tgz|tar\.gz|txz|tar\.xz|tbz2?|tar\.bz2|tzst|tar\.zst|7z|iso)$/,
+  );
   if (!matches) {
     return;
   }
-  const kind = matches[1] === 'tar.gz' ? 'tgz' : matches[1];
-  return kind as VirtualDirKind;
+  const aliases: Record<string, VirtualDirKind> = {
+    'tar.gz': 'tgz',
+    'tar.xz': 'txz',
+    'tar.bz2': 'tbz',
+    tbz2: 'tbz',
+    'tar.zst': 'tzst',
+  };
+  return aliases[matches[1]] ?? (matches[1] as VirtualDirKind);
 }
 
 /**
@@ -75,7 +125,9 @@ function getVirtualDirKindFromExt(path: string): VirtualDirKind | undefined {
  *   まだ仮想ディレクトリに入っていない場合は、
  *   初期化のため、アーカイブへのパスを指定する必要がある。
  *   すでに入っている場合は仮想ディレクトリ内のパスを指定できる。
+ *   アーカイブ内のアーカイブを指定すると、さらにその中に入る。
  * @param kind - 仮想ディレクトリの種類 (アーカイブ形式)
+ *   省略した場合は、サーバーがアーカイブの内容から判別する。
  * @param frame - 対象フレーム
  */
 function changeVirtualDir(
@@ -92,18 +144,15 @@ function changeVirtualDir(
   }
 
   let vdInfo = readState($virtualDirInfo(frame));
+  const initializing = !vdInfo;
 
   // $virtualDirInfo が null ということは、
   // これから仮想ディレクトリに入ろうとしているということだから、
   // $virtualDirInfo を初期化する。
+  // アーカイブ形式は拡張子ではなく、サーバーが内容から判別する。
   if (!vdInfo) {
     const curDir = readState($currentDir(frame));
     const archive = path.startsWith('/') ? path : `${curDir}/${path}`;
-    kind = kind ?? getVirtualDirKindFromExt(archive);
-    if (!kind) {
-      writeLog(`${frame}: ${messages[12]}`, 'info');
-      return;
-    }
     vdInfo = { archive, kind };
     writeState($virtualDirInfo(frame), vdInfo);
   }
@@ -115,6 +164,7 @@ function changeVirtualDir(
       archive: vdInfo.archive,
       path,
       filters: settings.virtualDirExcludePatterns,
+      password: vdInfo.password,
     },
     (resp) => {
       // path が仮想ディレクトリ外なら、通常の changeDir を呼び出す。
@@ -122,6 +172,26 @@ function changeVirtualDir(
         changeDir(path, frame);
         return;
       }
+      // 暗号化された zip 内のアーカイブに入るには、パスワードが必要となる。
+      // 入力されたらやり直し、キャンセルされたらその場に留まる。
+      if (isPasswordError(resp)) {
+        void promptPassword(resp, frame).then((ok) => {
+          if (ok) {
+            changeVirtualDir(path, kind, frame);
+          } else if (initializing) {
+            writeState($virtualDirInfo(frame), RESET);
+          }
+        });
+        return;
+      }
+      // アーカイブではなかった場合は、その場に留まる。
+      if (isUnknownFormat(resp)) {
+        writeLog(`${frame}: ${messages[12]}`, 'info');
+        if (initializing) {
+          writeState($virtualDirInfo(frame), RESET);
+        }
+        return;
+      }
       // エラー時はホームディレクトリに移動する。
       if (isErrorResp(resp) || isCommandErrorResp(resp)) {
         writeLog(resp.data.msg, 'error');
@@ -133,6 +203,7 @@ function changeVirtualDir(
       // 今 bar にいるとして、親 (foo) に上がった時、prevDirName は bar になる。
       // 親子関係がないところに移動した場合は null になる。
       const prevDirName = getPrevDirName(p, frame);
+      closeSearchResult(frame);
       writeState($currentDir(frame), p);
       writeState($rawEntries(frame), entries);
       writeState(
@@ -167,6 +238,7 @@ function goToParentVirtualDir(frame = readState($activeFrame)): void {
 /**
  * 選択した仮想エントリを実ディレクトリにコピーする。
  * まだ仮想ディレクトリに入っていない場合は何も起きない。
+ * コピーは ProgressTask として実行され、LogFrame から中止できる。
  *
  * @param paths - コピーする仮想エントリのパス配列
  * @param frame - 対象フレーム
@@ -191,20 +263,35 @@ function extractSelectedEntries(
   // コピー先のパス (非アクティブなフレームのカレントディレクトリ)
   const destination = readState($currentDir(readState($inactiveFrame)));
 
-  wsSend<WsCdResponse | WsVcpSkippedResponse>(
+  wsSend<WsProgressTaskResponse>(
     'vcp',
-    { kind: vdInfo.kind, archive: vdInfo.archive, sources: paths, destination },
+    {
+      kind: vdInfo.kind,
+      archive: vdInfo.archive,
+      sources: paths,
+      destination,
+      password: vdInfo.password,
+    },
     (resp) => {
-      // スキップされたエントリがある場合はログに出す。
-      if (isCopySkipped(resp)) {
-        const { messages } = readState($config);
-        writeLog(`${frame}: ${messages[13]}: ${resp.data.join(', ')}`, 'warn');
+      // 暗号化されたエントリがある場合は、パスワードを入力してやり直す。
+      if (isPasswordError(resp)) {
+        void promptPassword(resp, frame).then((ok) => {
+          if (ok) {
+            extractSelectedEntries(paths, frame);
+          }
+        });
         return;
       }
       if (isErrorResp(resp) || isCommandErrorResp(resp)) {
         writeLog(resp.data.msg, 'error');
         return;
       }
+      // 展開は ProgressTask として進むため、進捗をログに表示する。
+      // スキップされたエントリは、完了時に LogFrame がログに出す。
+      const log = (
+        <ProgressTaskLog label="extract entries" pid={resp.data.pid} />
+      );
+      writeLog(log, 'progress');
     },
     frame,
   );
@@ -14,6 +14,7 @@ import {
   useFocusFrame,
   useGridState,
   useGridViewport,
+  useSearchResult,
   useWatchError,
 } from '@modules/DataFrame/hooks';
 import { getSortDisplay } from '@modules/DataFrame/libs';
@@ -69,6 +70,7 @@ const DataFrameComponent: FC<Props> = ({
 
   useGridViewport(frame, gridRef);
   useDirUpdate(frame);
+  useSearchResult(frame);
   useWatchError(frame);
   useFocusFrame(frame, frameRef, initialFocus);
 
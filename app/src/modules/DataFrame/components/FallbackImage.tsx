@@ -25,7 +25,7 @@ type Props = {
 /**
  * gallery モードで表示するサムネイル画像コンポーネント。
  * 読込失敗時はフォールバックとして 1px 透過 PNG を表示する。
- * (virtual-dir モード中に gallery モードにした時など)
+ * (サーバーがデコードできない形式の画像など)
  * フォールバック時に一瞬プレースホルダが見えるのを防ぐため、
  * visibility を制御する。
  */
@@ -4,5 +4,6 @@ export * from './useFocusFrame';
 export * from './useGridState';
 export * from './useGridViewport';
 export * from './usePreview';
+export * from './useSearchResult';
 export * from './useThumbnail';
 export * from './useWatchError';
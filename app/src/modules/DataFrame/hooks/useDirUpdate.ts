@@ -3,19 +3,26 @@ import { useAtomCallback } from 'jotai/utils';
 import { useCallback, useEffect } from 'react';
 import { readState, writeState } from '@libs/utils';
 import { $ws } from '@modules/App/state';
-import { unsubscribeDirUpdate } from '@modules/DataFrame/api';
+import { resyncDir, unsubscribeDirUpdate } from '@modules/DataFrame/api';
 import { getOtherFrame } from '@modules/DataFrame/libs';
 import {
   $activeEntryName,
   $currentDir,
+  $dirRevision,
   $dirUpdateSubscriptionRecords,
   $filteredEntries,
   $rawEntries,
+  $searchResultInfo,
   $selectedEntryNames,
   $sortedEntries,
 } from '@modules/DataFrame/state';
 
-import type { Frame, WsDirUpdateResponse } from '@modules/App/types';
+import type { Getter, Setter } from 'jotai';
+import type {
+  Frame,
+  WsDirDiffResponse,
+  WsDirUpdateResponse,
+} from '@modules/App/types';
 import type { Entry } from '@modules/DataFrame/types';
 
 /**
@@ -93,6 +100,72 @@ function invokeDirUpdateCallbacks(path: string, frame: Frame): void {
   }
 }
 
+/**
+ * 新しいエントリ一覧を反映する。
+ * カレントエントリや選択行が削除された場合は、それらも更新する。
+ *
+ * @param get - jotai の getter
+ * @param set - jotai の setter
+ * @param path - 更新されたディレクトリのパス
+ * @param newRawEntries - 新しいエントリ一覧
+ * @param frame - 対象フレーム
+ */
+function updateEntries(
+  get: Getter,
+  set: Setter,
+  path: string,
+  newRawEntries: Entry[],
+  frame: Frame,
+): void {
+  const activeEntryName = get($activeEntryName(frame));
+
+  // 更新前のエントリ一覧を取得しておく。
+  const oldSortedEntries = get($sortedEntries(frame));
+
+  // エントリ一覧を更新する。
+  set($rawEntries(frame), newRawEntries);
+
+  // 更新後のエントリ一覧を取得する。
+  // コールバック引数の set, get は即時反映なため、
+  // $sortedEntries にはすでに newRawEntries が反映されている。
+  const newSortedEntries = get($sortedEntries(frame));
+
+  // コールバック引数の set, get は即時反映なため、
+  // $filteredEntries にはすでに newRawEntries が反映されている。
+  const filteredEntries = get($filteredEntries(frame));
+  const filteredEntryNames = new Set(filteredEntries.map((e) => e.name));
+  const selectedEntryNames = get($selectedEntryNames(frame));
+
+  // 選択行だったエントリが削除された場合を考慮した、新しい選択行リスト。
+  const entryNames = getFallbackSelectedEntryNames(
+    filteredEntryNames,
+    selectedEntryNames,
+  );
+
+  set($selectedEntryNames(frame), entryNames);
+
+  // カレントだったエントリが削除されたか否か。
+  const isDeleted =
+    oldSortedEntries.some((e) => e.name === activeEntryName) &&
+    !newSortedEntries.some((e) => e.name === activeEntryName);
+
+  // 削除されていないなら、カレントはそのままでよい。
+  if (!isDeleted) {
+    invokeDirUpdateCallbacks(path, frame);
+    return;
+  }
+
+  // カレントだったエントリが削除された場合の、新しいカレントエントリ。
+  const entryName = getFallbackActiveEntryName(
+    oldSortedEntries,
+    filteredEntryNames,
+    activeEntryName,
+  );
+
+  set($activeEntryName(frame), entryName);
+  invokeDirUpdateCallbacks(path, frame);
+}
+
 /**
  * ディレクトリが更新された時の処理を行う。
  * サーバーからイベントが飛んでくるので、エントリの更新などを行う。
@@ -106,60 +179,64 @@ export const useDirUpdate = (frame: Frame): void => {
     useCallback(
       (get, set, resp) => {
         const curDir = get($currentDir(frame));
-        const { entries: newRawEntries, path } = resp.data;
+        const { entries, path, revision } = resp.data;
 
         // 他フレームの更新イベントは無視する。
         if (path !== curDir) {
           return;
         }
 
-        const activeEntryName = get($activeEntryName(frame));
-
-        // 更新前のエントリ一覧を取得しておく。
-        const oldSortedEntries = get($sortedEntries(frame));
-
-        // エントリ一覧を更新する。
-        set($rawEntries(frame), newRawEntries);
+        set($dirRevision(frame), revision ?? 0);
 
-        // 更新後のエントリ一覧を取得する。
-        // コールバック引数の set, get は即時反映なため、
-        // $sortedEntries にはすでに newRawEntries が反映されている。
-        const newSortedEntries = get($sortedEntries(frame));
+        // 検索結果の表示中は、エントリ一覧を上書きしない。
+        if (get($searchResultInfo(frame))) {
+          return;
+        }
+        updateEntries(get, set, path, entries, frame);
+      },
+      [frame],
+    ),
+  );
 
-        // コールバック引数の set, get は即時反映なため、
-        // $filteredEntries にはすでに newRawEntries が反映されている。
-        const filteredEntries = get($filteredEntries(frame));
-        const filteredEntryNames = new Set(filteredEntries.map((e) => e.name));
-        const selectedEntryNames = get($selectedEntryNames(frame));
+  const handleDirDiff = useAtomCallback<void, [WsDirDiffResponse]>(
+    useCallback(
+      (get, set, resp) => {
+        const curDir = get($currentDir(frame));
+        const { added, modified, path, removed, revision } = resp.data;
 
-        // 選択行だったエントリが削除された場合を考慮した、新しい選択行リスト。
-        const entryNames = getFallbackSelectedEntryNames(
-          filteredEntryNames,
-          selectedEntryNames,
-        );
+        // 他フレームの更新イベントは無視する。
+        if (path !== curDir) {
+          return;
+        }
 
-        set($selectedEntryNames(frame), entryNames);
+        // 既に反映済みの差分は無視する。
+        const curRevision = get($dirRevision(frame));
+        if (revision <= curRevision) {
+          return;
+        }
 
-        // カレントだったエントリが削除されたか否か。
-        const isDeleted =
-          oldSortedEntries.some((e) => e.name === activeEntryName) &&
-          !newSortedEntries.some((e) => e.name === activeEntryName);
+        // 検索結果の表示中は、差分を適用できないため無視する。
+        // リビジョンだけは進めておく。
+        if (get($searchResultInfo(frame))) {
+          set($dirRevision(frame), revision);
+          return;
+        }
 
-        // 削除されていないなら、カレントはそのままでよい。
-        if (!isDeleted) {
-          invokeDirUpdateCallbacks(path, frame);
+        // 差分を取りこぼしている場合は、全エントリを再送してもらう。
+        if (revision !== curRevision + 1) {
+          resyncDir(frame);
           return;
         }
 
-        // カレントだったエントリが削除された場合の、新しいカレントエントリ。
-        const entryName = getFallbackActiveEntryName(
-          oldSortedEntries,
-          filteredEntryNames,
-          activeEntryName,
-        );
+        // 変更されたエントリは置き換え、削除されたエントリは取り除く。
+        const modifiedMap = new Map(modified.map((e) => [e.name, e]));
+        const removedNames = new Set(removed);
+        const newRawEntries = get($rawEntries(frame))
+          .filter((e) => !removedNames.has(e.name))
+          .map((e) => modifiedMap.get(e.name) ?? e);
 
-        set($activeEntryName(frame), entryName);
-        invokeDirUpdateCallbacks(path, frame);
+        set($dirRevision(frame), revision);
+        updateEntries(get, set, path, [...newRawEntries, ...added], frame);
       },
       [frame],
     ),
@@ -167,8 +244,10 @@ export const useDirUpdate = (frame: Frame): void => {
 
   useEffect(() => {
     ws.registerListener('DIR_UPDATE', handleDirUpdate);
+    ws.registerListener('DIR_DIFF', handleDirDiff);
     return () => {
       ws.removeListener('DIR_UPDATE', handleDirUpdate);
+      ws.removeListener('DIR_DIFF', handleDirDiff);
     };
-  }, [handleDirUpdate, ws]);
+  }, [handleDirDiff, handleDirUpdate, ws]);
 };
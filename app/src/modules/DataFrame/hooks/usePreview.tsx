@@ -30,6 +30,7 @@ function getMimeType(path: string): null | string {
 /**
  * エントリの種類に応じて、プレビュー用の情報を返す。
  * 画像 <img> | ビデオ・オーディオ <video> | その他 <iframe>
+ * ディレクトリは、サーバーが返す概要を <iframe> で表示する。
  *
  * @param entry - 対象エントリ
  * @param frame - 対象フレーム
@@ -46,15 +47,17 @@ export const usePreview = (
   const curDir = useAtomValue($currentDir(frame));
 
   const info = useMemo(() => {
-    if (entry === null || !entry.perm.startsWith('-')) {
+    if (entry === null || !/^[-d]/.test(entry.perm)) {
       return { node: null, ref: null };
     }
+    const isDir = entry.perm.startsWith('d');
 
     // /preview/* はサーバーが提供する特別なルーティングで、
     // ファイルシステム上のリソースをロードできる。
     // 例えば /preview/foo/bar/baz.jpg だと /foo/bar/baz.jpg をロードする。
+    // 仮想ディレクトリ内の場合は、アーカイブから展開されたものがロードされる。
     const path = `/preview${curDir}/${entry.name}`;
-    const type = getMimeType(path);
+    const type = isDir ? 'inode/directory' : getMimeType(path);
 
     if (type === null) {
       const node = <div className="preview_unavailable" />;
@@ -0,0 +1,45 @@
+import { useAtomValue } from 'jotai';
+import { useAtomCallback } from 'jotai/utils';
+import { useCallback, useEffect } from 'react';
+import { $ws } from '@modules/App/state';
+import { $rawEntries, $searchResultInfo } from '@modules/DataFrame/state';
+
+import type { Frame, WsProgressDataResponse } from '@modules/App/types';
+import type { Entry } from '@modules/DataFrame/types';
+
+/**
+ * 検索結果をエントリ一覧に反映する。
+ * search タスクのマッチは PROGRESS_DATA として逐次送信されてくるため、
+ * 自フレームの検索のものであれば、エントリ一覧に追加していく。
+ *
+ * @param frame - 対象フレーム
+ */
+export const useSearchResult = (frame: Frame): void => {
+  const ws = useAtomValue($ws);
+
+  const handleProgressData = useAtomCallback<void, [WsProgressDataResponse]>(
+    useCallback(
+      (get, set, resp) => {
+        const info = get($searchResultInfo(frame));
+        const { data, pid } = resp.data;
+
+        // 他フレームの検索や、grep などのデータは無視する。
+        if (info?.pid !== pid) {
+          return;
+        }
+
+        // pid が一致するなら search タスクのデータである。
+        const entries = data as Entry[];
+        set($rawEntries(frame), (prev) => [...prev, ...entries]);
+      },
+      [frame],
+    ),
+  );
+
+  useEffect(() => {
+    ws.registerListener('PROGRESS_DATA', handleProgressData);
+    return () => {
+      ws.removeListener('PROGRESS_DATA', handleProgressData);
+    };
+  }, [handleProgressData, ws]);
+};
@@ -1,6 +1,7 @@
 import { useAtomValue } from 'jotai';
 import mime from 'mime';
 import { useMemo } from 'react';
+import { $config } from '@modules/App/state';
 import { FallbackImage } from '@modules/DataFrame/components';
 import { $currentDir } from '@modules/DataFrame/state';
 
@@ -22,21 +23,26 @@ export const useThumbnail = (
   className?: string,
 ): ReactNode => {
   const curDir = useAtomValue($currentDir(frame));
+  const { settings } = useAtomValue($config);
 
   const node = useMemo(() => {
     if (!entry.perm.startsWith('-')) {
       return null;
     }
-    // /preview/* はサーバーが提供する特別なルーティングで、
-    // ファイルシステム上のリソースをロードできる。
-    // 例えば /preview/foo/bar/baz.jpg だと /foo/bar/baz.jpg をロードする。
-    const src = `/preview${curDir}/${entry.name}`;
-    const type = mime.getType(src);
+    // /thumb/* はサーバーが提供する特別なルーティングで、
+    // ファイルシステム上の画像を縮小したものをロードできる。
+    // 例えば /thumb/foo/bar/baz.jpg だと /foo/bar/baz.jpg の縮小版をロードする。
+    // 仮想ディレクトリ内の場合は、アーカイブから展開された画像が縮小される。
+    const path = `/thumb${curDir}/${entry.name}`;
+    const type = mime.getType(path);
     if (type !== null && type.startsWith('image/')) {
+      // ブラウザのキャッシュ対策として、更新日時も付与する。
+      const time = encodeURIComponent(entry.time);
+      const src = `${path}?size=${settings.thumbnailSize}&t=${time}`;
       return <FallbackImage className={className} src={src} />;
     }
     return null;
-  }, [className, curDir, entry]);
+  }, [className, curDir, entry, settings.thumbnailSize]);
 
   return node;
 };
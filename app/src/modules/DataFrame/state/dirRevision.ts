@@ -0,0 +1,10 @@
+import { atom } from 'jotai';
+import { atomFamily } from 'jotai-family';
+
+import type { Frame } from '@modules/App/types';
+
+/**
+ * エントリ一覧のリビジョン番号。
+ * サーバーから送られてくる差分の取りこぼしを検出するために使用する。
+ */
+export const $dirRevision = atomFamily((_frame: Frame) => atom(0));
@@ -1,6 +1,7 @@
 export * from './activeEntryIndex';
 export * from './activeEntryName';
 export * from './currentDir';
+export * from './dirRevision';
 export * from './dirUpdateSubscriptionRecords';
 export * from './filteredEntries';
 export * from './filterQuery';
@@ -19,6 +20,7 @@ export * from './prevSessionDir';
 export * from './rawEntries';
 export * from './renderedEntries';
 export * from './renderedRowHeight';
+export * from './searchResultInfo';
 export * from './selectedEntryIndices';
 export * from './selectedEntryNames';
 export * from './sort';
@@ -0,0 +1,45 @@
+import { atom } from 'jotai';
+import { atomFamily } from 'jotai-family';
+import { atomWithReset, RESET } from 'jotai/utils';
+import { shallowEqualObject } from '@libs/utils';
+import { $modes } from '@modules/DataFrame/state';
+
+import type { SetStateAction } from 'jotai';
+import type { Frame } from '@modules/App/types';
+import type { SearchResultInfo } from '@modules/DataFrame/types';
+
+const searchResultInfoAtom = atomFamily((_frame: Frame) =>
+  atomWithReset<SearchResultInfo | null>(null),
+);
+
+/**
+ * 検索結果の詳細情報。
+ * 値がある間は、エントリ一覧が検索結果に置き換わっている。
+ */
+export const $searchResultInfo = atomFamily((frame: Frame) =>
+  atom(
+    (get) => get(searchResultInfoAtom(frame)),
+    (
+      get,
+      set,
+      newVal: SetStateAction<SearchResultInfo | null> | typeof RESET,
+    ) => {
+      const curVal = get(searchResultInfoAtom(frame));
+      if (typeof newVal === 'function') {
+        newVal = newVal(curVal);
+      }
+      if (newVal === RESET || newVal === null) {
+        set(searchResultInfoAtom(frame), RESET);
+        set($modes(frame), (prev) =>
+          prev.filter((m) => m !== 'search-result'),
+        );
+        return;
+      }
+      if (curVal !== null && shallowEqualObject(newVal, curVal)) {
+        return;
+      }
+      set(searchResultInfoAtom(frame), newVal);
+      set($modes(frame), (prev) => [...prev, 'search-result']);
+    },
+  ),
+);
@@ -10,7 +10,13 @@ import type { EntryModel } from '@modules/DataFrame/models';
 /**
  * モード。
  */
-export type Mode = 'filter' | 'gallery' | 'history' | 'preview' | 'virtual-dir';
+export type Mode =
+  | 'filter'
+  | 'gallery'
+  | 'history'
+  | 'preview'
+  | 'search-result'
+  | 'virtual-dir';
 
 /**
  * 各フレームのカレントディレクトリを表すオブジェクト。
@@ -78,6 +84,37 @@ export type DirData = {
    * ディレクトリのパス。
    */
   path: string;
+  /**
+   * エントリ一覧のリビジョン番号。
+   * 仮想ディレクトリの場合は存在しない。
+   */
+  revision?: number;
+};
+
+/**
+ * ディレクトリの差分データ。
+ */
+export type DirDiffData = {
+  /**
+   * 追加されたエントリ一覧。
+   */
+  added: Entry[];
+  /**
+   * 変更されたエントリ一覧。
+   */
+  modified: Entry[];
+  /**
+   * ディレクトリのパス。
+   */
+  path: string;
+  /**
+   * 削除されたエントリの name 一覧。
+   */
+  removed: string[];
+  /**
+   * 差分適用後のリビジョン番号。
+   */
+  revision: number;
 };
 
 /**
@@ -120,7 +157,15 @@ export type CursorDirection = 'down' | 'left' | 'right' | 'up';
 /**
  * 仮想ディレクトリの種類。
  */
-export type VirtualDirKind = 'tar' | 'tgz' | 'zip';
+export type VirtualDirKind =
+  | '7z'
+  | 'iso'
+  | 'tar'
+  | 'tbz'
+  | 'tgz'
+  | 'txz'
+  | 'tzst'
+  | 'zip';
 
 /**
  * 仮想ディレクトリの詳細情報。
@@ -132,8 +177,76 @@ export type VirtualDirInfo = {
   archive: string;
   /**
    * 仮想ディレクトリの種類。
+   * 省略した場合は、サーバーがアーカイブの内容から判別する。
+   */
+  kind?: VirtualDirKind;
+  /**
+   * 暗号化された zip を展開する際のパスワード。
+   * 必要になった時点でユーザーに入力を求める。
+   */
+  password?: string;
+};
+
+/**
+ * 検索条件。
+ * search タスクの引数に対応している。
+ */
+export type SearchQuery = {
+  /**
+   * gitignore に従って除外するか否か。
+   */
+  gitignore?: boolean;
+  /**
+   * 大文字小文字を区別しないか否か。
+   */
+  ignoreCase?: boolean;
+  /**
+   * 潜る階層の深さの上限。
+   */
+  maxDepth?: number;
+  /**
+   * 更新日時の上限 (UNIX 時間)。
+   */
+  maxMtime?: number;
+  /**
+   * 容量の上限 (バイト)。
+   */
+  maxSize?: number;
+  /**
+   * 更新日時の下限 (UNIX 時間)。
+   */
+  minMtime?: number;
+  /**
+   * 容量の下限 (バイト)。
+   */
+  minSize?: number;
+  /**
+   * エントリ名の glob パターン。
+   */
+  name?: string;
+  /**
+   * パーミッションの 8 進数表記。
+   * 例： `755`
+   */
+  perm?: string;
+  /**
+   * エントリ名の正規表現パターン。
+   */
+  regex?: string;
+  /**
+   * 検索するエントリの種類。
+   */
+  type?: 'dir' | 'file' | 'symlink';
+};
+
+/**
+ * 検索結果の詳細情報。
+ */
+export type SearchResultInfo = {
+  /**
+   * search タスクのプロセス ID。
    */
-  kind: VirtualDirKind;
+  pid: string;
 };
 
 /**
@@ -1,7 +1,7 @@
 import { useAtom, useAtomValue, useSetAtom } from 'jotai';
 import { useAtomCallback } from 'jotai/utils';
 import { memo, useCallback, useEffect, useRef } from 'react';
-import { $scope, $ws } from '@modules/App/state';
+import { $config, $scope, $ws } from '@modules/App/state';
 import {
   $logData,
   $logFrameRef,
@@ -36,15 +36,28 @@ const LogFrameComponent: FC = () => {
   );
 
   // ProgressTask の終了処理をする。
+  // 処理できなかったエントリがあれば、エラーとしてログに出力する。
+  // スキップされたエントリがあれば、警告としてログに出力する。
   const handleProgressEnd = useAtomCallback<void, [WsProgressEndResponse]>(
-    useCallback((_get, set, resp) => {
-      const { pid } = resp.data;
-      set($progressTaskInfo(pid), (prev) => ({
-        ...prev,
-        status: 'end',
-        progress: 100,
-      }));
-    }, []),
+    useCallback(
+      (get, set, resp) => {
+        const { pid, result } = resp.data;
+        set($progressTaskInfo(pid), (prev) => ({
+          ...prev,
+          status: 'end',
+          progress: 100,
+        }));
+        for (const { msg, path } of result?.failures ?? []) {
+          setLogData({ log: `${path}: ${msg}`, level: 'error' });
+        }
+        if (result?.skipped?.length) {
+          const { messages } = get($config);
+          const log = `${messages[13]}: ${result.skipped.join(', ')}`;
+          setLogData({ log, level: 'warn' });
+        }
+      },
+      [setLogData],
+    ),
   );
 
   // progress error の処理をする。
@@ -1,13 +1,33 @@
-//! アーカイブファイル (zip, tar, tgz/tar.gz) を提供するモジュール。
+//! アーカイブファイル (zip, tar, tgz/tar.gz, txz/tar.xz, tbz/tar.bz2,
+//! tzst/tar.zst, 7z, iso) と、走査済みのエントリ一覧 (索引) を
+//! 提供するモジュール。
 
+mod indexed;
+mod indexed_entry;
+mod iso;
+mod iso_entry;
+mod seven_zip;
+mod seven_zip_entry;
 mod tar;
 mod tar_entry;
+mod tbz;
 mod tgz;
+mod txz;
+mod tzst;
 mod zip;
 mod zip_entry;
 
+pub use indexed::Indexed;
+pub use indexed_entry::IndexedEntry;
+pub use iso::{Iso, IsoRecord};
+pub use iso_entry::IsoEntry;
+pub use seven_zip::SevenZip;
+pub use seven_zip_entry::SevenZipEntry;
 pub use tar::Tar;
 pub use tar_entry::TarEntry;
+pub use tbz::Tbz;
 pub use tgz::Tgz;
+pub use txz::Txz;
+pub use tzst::Tzst;
 pub use zip::Zip;
 pub use zip_entry::ZipEntry;
@@ -0,0 +1,36 @@
+use crate::{
+    archives::IndexedEntry,
+    traits::{Archive, ArchiveEntry, ArchiveEntryIter},
+};
+
+use std::{io::Result, sync::Arc};
+
+/// 索引に保持されたエントリ一覧をアーカイブとして扱う構造体。
+///
+/// 一度走査したアーカイブを、展開し直さずに一覧するために使用する。
+///
+/// # Fields
+/// * `entries` - エントリ一覧
+pub struct Indexed {
+    entries: Arc<Vec<IndexedEntry>>,
+}
+
+impl Indexed {
+    /// 新しい Indexed インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `entries` - エントリ一覧
+    pub fn new(entries: Arc<Vec<IndexedEntry>>) -> Self {
+        Self { entries }
+    }
+}
+
+impl Archive for Indexed {
+    fn entries(&mut self) -> Result<ArchiveEntryIter<'_>> {
+        let iter = self
+            .entries
+            .iter()
+            .map(|e| Ok(Box::new(e.clone()) as Box<dyn ArchiveEntry>));
+        Ok(Box::new(iter))
+    }
+}
@@ -0,0 +1,65 @@
+use crate::traits::ArchiveEntry;
+
+use std::mem::size_of;
+
+/// 索引に保持されるエントリを表す構造体。
+///
+/// アーカイブを走査して得たエントリ情報の写しであり、
+/// アーカイブを開き直さずに一覧を作成するために使用する。
+///
+/// # Fields
+/// * `perm` - パーミッション文字列
+/// * `path` - エントリのパス
+/// * `size` - ファイルサイズ (バイト)
+/// * `time` - 最終更新日時
+#[derive(Clone)]
+pub struct IndexedEntry {
+    perm: String,
+    path: String,
+    size: u64,
+    time: String,
+}
+
+impl IndexedEntry {
+    /// 既存のエントリから新しい IndexedEntry インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `entry` - 写しを取るエントリ
+    pub fn new(entry: &dyn ArchiveEntry) -> Self {
+        Self {
+            perm: entry.perm(),
+            path: entry.path(),
+            size: entry.size(),
+            time: entry.time(),
+        }
+    }
+
+    /// このエントリが占有するおおよそのメモリ量を取得する。
+    ///
+    /// # Returns
+    /// メモリ量 (バイト)
+    pub fn mem_size(&self) -> usize {
+        size_of::<Self>()
+            + self.perm.capacity()
+            + self.path.capacity()
+            + self.time.capacity()
+    }
+}
+
+impl ArchiveEntry for IndexedEntry {
+    fn perm(&self) -> String {
+        self.perm.clone()
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn time(&self) -> String {
+        self.time.clone()
+    }
+}
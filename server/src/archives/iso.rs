@@ -0,0 +1,669 @@
+use crate::{
+    archives::IsoEntry,
+    errors::VirtualDirError,
+    traits::{Archive, ArchiveEntry, ArchiveEntryIter},
+};
+
+use anyhow::bail;
+use chrono::{FixedOffset, TimeZone as _};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs::File,
+    io::{self, Read, Result},
+    os::unix::fs::FileExt as _,
+};
+
+/// ボリューム記述子が格納されているセクタの開始位置。
+const DESCRIPTOR_START: u64 = 16;
+
+/// ボリューム記述子のセクタサイズ。
+const SECTOR_SIZE: u64 = 2048;
+
+/// 読み込むボリューム記述子の上限数。
+const MAX_DESCRIPTORS: u64 = 64;
+
+/// ディレクトリ階層の上限。
+///
+/// 壊れたイメージで無限に再帰しないようにする。
+const MAX_DEPTH: usize = 64;
+
+/// ディレクトリの大きさの上限 (ブロック数)。
+///
+/// 壊れたイメージで巨大なバッファを確保しないようにする。
+const MAX_DIR_BLOCKS: u64 = 32768;
+
+/// ディレクトリを表すフラグ。
+const FLAG_DIR: u8 = 0x02;
+
+/// 後続のレコードにデータが続くこと (マルチエクステント) を表すフラグ。
+const FLAG_MULTI_EXTENT: u8 = 0x80;
+
+/// ISO イメージ内のエントリ (ディレクトリレコード) の情報。
+///
+/// # Fields
+/// * `path` - エントリのパスのバイト列 (ディレクトリは末尾スラッシュ付き)
+/// * `is_dir` - ディレクトリか否か
+/// * `mode` - UNIX のモード (Rock Ridge の場合のみ)
+/// * `mtime` - 最終更新日時 (UNIX 時間)
+/// * `size` - ファイルサイズ (バイト)
+/// * `extents` - データの位置とサイズ (バイト) の配列
+pub struct IsoRecord {
+    pub path: Vec<u8>,
+    pub is_dir: bool,
+    pub mode: Option<u32>,
+    pub mtime: Option<i64>,
+    pub size: u64,
+    extents: Vec<(u64, u64)>,
+}
+
+/// ディレクトリレコードを解析した結果。
+///
+/// # Fields
+/// * `extent` - データの開始ブロック
+/// * `size` - データのサイズ (バイト)
+/// * `flags` - ファイルフラグ
+/// * `name` - ファイル識別子
+/// * `system_use` - システム使用領域 (Rock Ridge の情報が入る)
+/// * `mtime` - 記録日時 (UNIX 時間)
+struct RawRecord<'a> {
+    extent: u64,
+    size: u64,
+    flags: u8,
+    name: &'a [u8],
+    system_use: &'a [u8],
+    mtime: Option<i64>,
+}
+
+/// Rock Ridge 拡張から読み取った情報。
+///
+/// # Fields
+/// * `name` - 代替名 (NM)
+/// * `mode` - UNIX のモード (PX)
+/// * `child` - 再配置されたディレクトリの位置 (CL)
+/// * `relocated` - 再配置先のディレクトリであるか否か (RE)
+#[derive(Default)]
+struct RockRidge {
+    name: Option<Vec<u8>>,
+    mode: Option<u32>,
+    child: Option<u64>,
+    relocated: bool,
+}
+
+/// ファイル名の形式。
+///
+/// # Variants
+/// * `Plain` - ISO 9660 の識別子をそのまま使う
+/// * `Joliet` - UCS-2 で格納された Joliet の識別子を使う
+/// * `RockRidge` - Rock Ridge の代替名を使う
+#[derive(Clone, Copy, PartialEq)]
+enum NameKind {
+    Plain,
+    Joliet,
+    RockRidge,
+}
+
+/// ISO 9660 のディスクイメージを扱う構造体。
+///
+/// 一覧の取得にはディレクトリレコードのみを読み込む。
+/// ファイル名は Rock Ridge、Joliet、ISO 9660 の順に優先して使われる。
+///
+/// # Fields
+/// * `time_style` - 日時のフォーマット文字列
+/// * `file` - ISO イメージのファイル
+/// * `records` - イメージ内の全エントリ
+pub struct Iso {
+    time_style: String,
+    file: File,
+    records: Vec<IsoRecord>,
+}
+
+impl Iso {
+    /// 新しい Iso インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `path` - ISO ファイルのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// 初期化された Iso インスタンス
+    ///
+    /// # Errors
+    /// - `VirtualDirError::UnsupportedArchive`:
+    ///   ISO 9660 のイメージではない。
+    pub fn new(path: &str, time_style: &str) -> anyhow::Result<Self> {
+        let file = File::open(path)?;
+        let records = Reader::new(&file)?.records()?;
+        Ok(Self {
+            time_style: time_style.to_owned(),
+            file,
+            records,
+        })
+    }
+
+    /// イメージ内の全エントリを返す。
+    pub fn records(&self) -> &[IsoRecord] {
+        &self.records
+    }
+
+    /// エントリのデータを読み込むリーダーを返す。
+    ///
+    /// # Arguments
+    /// * `record` - 読み込むエントリ
+    pub fn open<'a>(&'a self, record: &IsoRecord) -> impl Read + 'a {
+        ExtentReader {
+            file: &self.file,
+            extents: record.extents.iter().copied().collect(),
+        }
+    }
+}
+
+impl Archive for Iso {
+    fn entries(&mut self) -> Result<ArchiveEntryIter<'_>> {
+        let iter = self.records.iter().map(|r| {
+            let mut item = IsoEntry::new(&self.time_style);
+            item.init(r);
+            Ok(Box::new(item) as Box<dyn ArchiveEntry>)
+        });
+        Ok(Box::new(iter))
+    }
+}
+
+/// エクステントを順に読み込むリーダー。
+///
+/// # Fields
+/// * `file` - ISO イメージのファイル
+/// * `extents` - 未読のデータの位置とサイズ (バイト)
+struct ExtentReader<'a> {
+    file: &'a File,
+    extents: VecDeque<(u64, u64)>,
+}
+
+impl Read for ExtentReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while let Some((pos, len)) = self.extents.front_mut() {
+            if *len == 0 {
+                self.extents.pop_front();
+                continue;
+            }
+            let max = buf.len().min(*len as usize);
+            let n = self.file.read_at(&mut buf[..max], *pos)?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            *pos += n as u64;
+            *len -= n as u64;
+            return Ok(n);
+        }
+        Ok(0)
+    }
+}
+
+/// ディレクトリツリーを読み込む構造体。
+///
+/// # Fields
+/// * `file` - ISO イメージのファイル
+/// * `len` - ISO イメージのファイルサイズ (バイト)
+/// * `block_size` - 論理ブロックサイズ
+/// * `root` - ルートディレクトリの位置とサイズ (バイト)
+/// * `kind` - ファイル名の形式
+/// * `skip` - システム使用領域の先頭で読み飛ばすバイト数 (SUSP の SP)
+struct Reader<'a> {
+    file: &'a File,
+    len: u64,
+    block_size: u64,
+    root: (u64, u64),
+    kind: NameKind,
+    skip: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// ボリューム記述子を読み込み、新しい Reader インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `file` - ISO イメージのファイル
+    fn new(file: &'a File) -> anyhow::Result<Self> {
+        let mut primary = None;
+        let mut joliet = None;
+        for i in DESCRIPTOR_START..DESCRIPTOR_START + MAX_DESCRIPTORS {
+            let mut buf = vec![0; SECTOR_SIZE as usize];
+            if file.read_exact_at(&mut buf, i * SECTOR_SIZE).is_err()
+                || &buf[1..6] != b"CD001"
+            {
+                break;
+            }
+            match buf[0] {
+                1 if primary.is_none() => primary = Some(buf),
+                // エスケープシーケンスが UCS-2 レベル 1-3 なら Joliet である。
+                2 if matches!(&buf[88..91], b"%/@" | b"%/C" | b"%/E") => {
+                    joliet = Some(buf);
+                }
+                255 => break,
+                _ => {}
+            }
+        }
+        let Some(primary) = primary else {
+            bail!(VirtualDirError::UnsupportedArchive);
+        };
+
+        let block_size = u16::from_le_bytes([primary[128], primary[129]]);
+        let Some(root) =
+            parse_record(&primary[156..190]).filter(|_| block_size > 0)
+        else {
+            bail!(VirtualDirError::UnsupportedArchive);
+        };
+        let block_size = block_size as u64;
+        let mut reader = Self {
+            file,
+            len: file.metadata()?.len(),
+            block_size,
+            root: (root.extent * block_size, root.size),
+            kind: NameKind::Plain,
+            skip: 0,
+        };
+
+        // ルートの "." レコードに SP があれば Rock Ridge が使われている。
+        if let Some(skip) = reader.susp_skip()? {
+            reader.kind = NameKind::RockRidge;
+            reader.skip = skip;
+        } else if let Some(svd) = joliet
+            && let Some(root) = parse_record(&svd[156..190])
+        {
+            reader.kind = NameKind::Joliet;
+            reader.root = (root.extent * block_size, root.size);
+        }
+        Ok(reader)
+    }
+
+    /// ルートディレクトリの SUSP の SP エントリを探す。
+    ///
+    /// # Returns
+    /// SP エントリがあれば読み飛ばすバイト数
+    fn susp_skip(&self) -> Result<Option<usize>> {
+        // "." レコードは先頭にあるため、最初のブロックだけ読めばよい。
+        let size = self.root.1.min(self.block_size);
+        let data = self.read_at(self.root.0, size)?;
+        let Some(dot) = parse_record(&data) else {
+            return Ok(None);
+        };
+        let su = dot.system_use;
+        let found = su.len() >= 7
+            && &su[..2] == b"SP"
+            && su[4] == 0xBE
+            && su[5] == 0xEF;
+        Ok(found.then(|| su[6] as usize))
+    }
+
+    /// 全エントリを深さ優先で取得する。
+    ///
+    /// ディレクトリは配下のエントリより前に格納される。
+    fn records(&self) -> Result<Vec<IsoRecord>> {
+        let mut records = Vec::new();
+        let mut visited = HashSet::new();
+        self.walk(self.root, b"", 0, &mut visited, &mut records)?;
+        Ok(records)
+    }
+
+    /// ディレクトリ内のエントリを再帰的に取得する。
+    ///
+    /// # Arguments
+    /// * `dir` - ディレクトリの位置とサイズ (バイト)
+    /// * `prefix` - ディレクトリのパス (末尾スラッシュ付き)
+    /// * `depth` - 現在の階層
+    /// * `visited` - 読み込み済みのディレクトリの位置
+    /// * `records` - 取得したエントリの格納先
+    fn walk(
+        &self,
+        dir: (u64, u64),
+        prefix: &[u8],
+        depth: usize,
+        visited: &mut HashSet<u64>,
+        records: &mut Vec<IsoRecord>,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH || !visited.insert(dir.0) {
+            return Ok(());
+        }
+        if dir.1 > MAX_DIR_BLOCKS * self.block_size {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        let data = self.read_at(dir.0, dir.1)?;
+        let mut children = Vec::new();
+        let mut continued = false;
+        let mut pos = 0;
+        while pos < data.len() {
+            // レコードはセクタをまたがないため、長さ 0 なら次のセクタへ進む。
+            let len = data[pos] as usize;
+            if len == 0 {
+                let bs = self.block_size as usize;
+                pos = (pos / bs + 1) * bs;
+                continue;
+            }
+            let Some(raw) = parse_record(&data[pos..]) else {
+                break;
+            };
+            pos += len;
+
+            // "." と ".." は除外する。
+            if matches!(raw.name, [0] | [1]) {
+                continue;
+            }
+            let rr = match self.kind {
+                NameKind::RockRidge => self.rock_ridge(raw.system_use)?,
+                _ => RockRidge::default(),
+            };
+            if rr.relocated {
+                continue;
+            }
+            let is_dir = raw.flags & FLAG_DIR != 0 || rr.child.is_some();
+            let mut name = match (self.kind, rr.name) {
+                (NameKind::RockRidge, Some(name)) => name,
+                (NameKind::Joliet, _) => decode_joliet(raw.name),
+                _ => raw.name.to_vec(),
+            };
+            if !is_dir && self.kind != NameKind::RockRidge {
+                strip_version(&mut name);
+            }
+            let multi = raw.flags & FLAG_MULTI_EXTENT != 0;
+            let extent = (raw.extent * self.block_size, raw.size);
+
+            // マルチエクステントの場合は直前のレコードにデータを連結する。
+            if continued
+                && let Some(last) = records.last_mut()
+                && last_name(last) == name.as_slice()
+            {
+                last.extents.push(extent);
+                last.size += raw.size;
+                continued = multi;
+                continue;
+            }
+            continued = multi;
+
+            let mut path = [prefix, &name].concat();
+            if is_dir {
+                path.push(b'/');
+            }
+            let extent = match rr.child {
+                // 再配置されたディレクトリは、その "." レコードから大きさを得る。
+                Some(child) => self.relocated_dir(child)?,
+                None => extent,
+            };
+            records.push(IsoRecord {
+                path,
+                is_dir,
+                mode: rr.mode,
+                mtime: raw.mtime,
+                size: if is_dir { 0 } else { raw.size },
+                extents: vec![extent],
+            });
+            if is_dir {
+                children.push(records.len() - 1);
+            }
+        }
+
+        // 配下のディレクトリを読み込む。
+        // ディレクトリエントリの直後に配下のエントリが並ぶわけではないが、
+        // ディレクトリは配下のエントリより前に格納される。
+        for i in children {
+            let dir = records[i].extents[0];
+            let path = records[i].path.clone();
+            self.walk(dir, &path, depth + 1, visited, records)?;
+        }
+        Ok(())
+    }
+
+    /// 再配置されたディレクトリの位置と大きさを取得する。
+    ///
+    /// 大きさはディレクトリの "." レコードから得る。
+    ///
+    /// # Arguments
+    /// * `extent` - ディレクトリの開始ブロック
+    fn relocated_dir(&self, extent: u64) -> Result<(u64, u64)> {
+        let pos = extent * self.block_size;
+        let data = self.read_at(pos, self.block_size)?;
+        let size = parse_record(&data).map_or(self.block_size, |r| r.size);
+        Ok((pos, size))
+    }
+
+    /// システム使用領域から Rock Ridge の情報を読み取る。
+    ///
+    /// 継続領域 (CE) がある場合は、それも続けて読み込む。
+    ///
+    /// # Arguments
+    /// * `su` - システム使用領域
+    fn rock_ridge(&self, su: &[u8]) -> Result<RockRidge> {
+        let mut rr = RockRidge::default();
+        let mut area = su.get(self.skip..).unwrap_or_default().to_vec();
+        // 壊れたイメージで無限に継続しないよう、回数を制限する。
+        for _ in 0..16 {
+            let mut next = None;
+            let mut pos = 0;
+            while pos + 4 <= area.len() {
+                let len = area[pos + 2] as usize;
+                if len < 4 || pos + len > area.len() {
+                    break;
+                }
+                let e = &area[pos..pos + len];
+                match &e[..2] {
+                    b"NM" if len >= 5 && e[4] & 0x06 == 0 => {
+                        rr.name.get_or_insert_default().extend(&e[5..]);
+                    }
+                    b"PX" if len >= 8 => rr.mode = Some(le_u32(&e[4..8])),
+                    b"CL" if len >= 8 => {
+                        rr.child = Some(le_u32(&e[4..8]) as u64)
+                    }
+                    b"RE" => rr.relocated = true,
+                    b"CE" if len >= 28 => {
+                        let block = le_u32(&e[4..8]) as u64;
+                        let offset = le_u32(&e[12..16]) as u64;
+                        let size = le_u32(&e[20..24]) as u64;
+                        // 継続領域はひとつのブロックに収まっている。
+                        if offset + size <= self.block_size {
+                            let pos = block * self.block_size + offset;
+                            next = Some((pos, size));
+                        }
+                    }
+                    b"ST" => break,
+                    _ => {}
+                }
+                pos += len;
+            }
+            let Some((pos, size)) = next else {
+                break;
+            };
+            area = self.read_at(pos, size)?;
+        }
+        Ok(rr)
+    }
+
+    /// 指定した位置のデータを読み込む。
+    ///
+    /// ファイルの範囲外を指している場合は、バッファを確保せずにエラーを返す。
+    ///
+    /// # Arguments
+    /// * `pos` - 開始位置 (バイト)
+    /// * `size` - サイズ (バイト)
+    fn read_at(&self, pos: u64, size: u64) -> Result<Vec<u8>> {
+        if pos.checked_add(size).is_none_or(|end| end > self.len) {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut buf = vec![0; size as usize];
+        self.file.read_exact_at(&mut buf, pos)?;
+        Ok(buf)
+    }
+}
+
+/// ディレクトリレコードを解析する。
+///
+/// # Arguments
+/// * `r` - ディレクトリレコードから始まるバイト列
+///
+/// # Returns
+/// 解析結果。不正なレコードの場合は None
+fn parse_record(r: &[u8]) -> Option<RawRecord<'_>> {
+    let len = *r.first()? as usize;
+    if len < 34 || len > r.len() {
+        return None;
+    }
+    let name_len = r[32] as usize;
+    let name = r.get(33..33 + name_len)?;
+    // 識別子の長さが偶数の場合、パディングが 1 バイト入る。
+    let su_start = (33 + name_len + (1 - name_len % 2)).min(len);
+    Some(RawRecord {
+        extent: le_u32(&r[2..6]) as u64,
+        size: le_u32(&r[10..14]) as u64,
+        flags: r[25],
+        name,
+        system_use: &r[su_start..len],
+        mtime: record_time(&r[18..25]),
+    })
+}
+
+/// ディレクトリレコードの記録日時を UNIX 時間に変換する。
+///
+/// # Arguments
+/// * `t` - 7 バイトの記録日時
+fn record_time(t: &[u8]) -> Option<i64> {
+    // 時差は 15 分単位の符号付き整数で格納されている。
+    let offset = FixedOffset::east_opt(t[6] as i8 as i32 * 15 * 60)?;
+    let dt = offset
+        .with_ymd_and_hms(
+            1900 + t[0] as i32,
+            t[1] as u32,
+            t[2] as u32,
+            t[3] as u32,
+            t[4] as u32,
+            t[5] as u32,
+        )
+        .single()?;
+    Some(dt.timestamp())
+}
+
+/// UCS-2 (ビッグエンディアン) の Joliet の識別子を UTF-8 に変換する。
+///
+/// # Arguments
+/// * `raw` - 識別子のバイト列
+fn decode_joliet(raw: &[u8]) -> Vec<u8> {
+    let units = raw
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units).into_bytes()
+}
+
+/// ファイル識別子からバージョン番号 (;1) と末尾のピリオドを取り除く。
+///
+/// # Arguments
+/// * `name` - ファイル識別子
+fn strip_version(name: &mut Vec<u8>) {
+    if let Some(i) = name.iter().rposition(|&b| b == b';') {
+        name.truncate(i);
+    }
+    if name.len() > 1 && name.ends_with(b".") {
+        name.pop();
+    }
+}
+
+/// レコードのパスから名前部分を取得する。
+///
+/// # Arguments
+/// * `record` - IsoRecord の参照
+fn last_name(record: &IsoRecord) -> &[u8] {
+    let path = record.path.strip_suffix(b"/").unwrap_or(&record.path);
+    match path.iter().rposition(|&b| b == b'/') {
+        Some(i) => &path[i + 1..],
+        None => path,
+    }
+}
+
+/// リトルエンディアンの u32 を読み取る。
+///
+/// # Arguments
+/// * `b` - 4 バイト以上のバイト列
+fn le_u32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use tempfile::tempdir;
+    use unicode_normalization::UnicodeNormalization as _;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    fn paths(iso: &mut Iso) -> anyhow::Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for entry in iso.entries()? {
+            paths.push(entry?.path());
+        }
+        Ok(paths)
+    }
+
+    #[test]
+    fn test_run_success() -> anyhow::Result<()> {
+        let time_style = "%y/%m/%d %H:%M:%S";
+        for name in ["dir-entries.iso", "dir-entries-joliet.iso"] {
+            let archive = format!("./tests/archives/{name}");
+            let mut iso = Iso::new(&archive, time_style)?;
+            let paths = paths(&mut iso)?;
+            assert_eq!(paths.len(), 30);
+            assert!(paths.contains(&nfc("1 ゲーム/")));
+            assert!(
+                paths.contains(&nfc("1 ゲーム/1 オープンワールド/2 画像.jpg"))
+            );
+            assert!(paths.contains(&"2 movies/._2 image.jpg".to_owned()));
+
+            // ディレクトリは配下のエントリより前にある。
+            let dir = paths.iter().position(|p| p == "2 movies/");
+            let file = paths.iter().position(|p| p == "2 movies/1 text.txt");
+            assert!(dir < file);
+
+            let record = iso
+                .records()
+                .iter()
+                .find(|r| r.path == b"1 text.txt")
+                .unwrap();
+            let mut text = String::new();
+            iso.open(record).read_to_string(&mut text)?;
+            assert_eq!(text.len(), 445);
+        }
+
+        // Rock Ridge の場合はパーミッションも取得できる。
+        let archive = "./tests/archives/dir-entries.iso";
+        let mut iso = Iso::new(archive, time_style)?;
+        let entry = iso.entries()?.next().unwrap()?;
+        assert_ne!(entry.perm(), entry.default_perm('d'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_error() -> Result<()> {
+        let archive = "./tests/archives/nonexistent.iso";
+        let time_style = "%y/%m/%d %H:%M:%S";
+        assert!(Iso::new(archive, time_style).is_err());
+        let archive = "./tests/archives/dir-entries.zip";
+        assert!(Iso::new(archive, time_style).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_corrupt() -> anyhow::Result<()> {
+        let src = "./tests/archives/dir-entries.iso";
+        let time_style = "%y/%m/%d %H:%M:%S";
+        let dir = tempdir()?;
+        let archive = dir.path().join("corrupt.iso");
+
+        // ルートディレクトリの大きさを巨大な値に書き換えても、
+        // バッファを確保しようとせずにエラーになる。
+        let mut data = fs::read(src)?;
+        let pos = (DESCRIPTOR_START * SECTOR_SIZE) as usize + 156 + 10;
+        data[pos..pos + 4].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+        fs::write(&archive, &data)?;
+        let archive = archive.to_string_lossy();
+        assert!(Iso::new(&archive, time_style).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{archives::IsoRecord, helpers::perm_string, traits::ArchiveEntry};
+
+use chrono::{Local, TimeZone as _};
+
+/// シンボリックリンクを表すファイル種別 (S_IFLNK)。
+const S_IFLNK: u32 = 0o120000;
+
+/// ファイル種別を取り出すためのマスク (S_IFMT)。
+const S_IFMT: u32 = 0o170000;
+
+/// ISO イメージのエントリを表す構造体。
+///
+/// # Fields
+/// * `time_style` - 日時のフォーマット文字列
+/// * `perm` - パーミッション文字列
+/// * `path` - エントリのパス
+/// * `size` - ファイルサイズ (バイト)
+/// * `time` - 最終更新日時
+pub struct IsoEntry {
+    time_style: String,
+    perm: String,
+    path: String,
+    size: u64,
+    time: String,
+}
+
+impl IsoEntry {
+    /// 新しい IsoEntry インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// デフォルト値で初期化された IsoEntry インスタンス
+    pub fn new(time_style: &str) -> Self {
+        let mut ins = Self {
+            time_style: time_style.to_owned(),
+            perm: String::new(),
+            path: String::new(),
+            size: 0,
+            time: String::new(),
+        };
+        ins.perm = ins.default_perm('-');
+        ins.size = ins.default_size();
+        ins.time = ins.default_time();
+        ins
+    }
+
+    /// IsoRecord を元にこのインスタンスを初期化する。
+    ///
+    /// # Arguments
+    /// * `record` - IsoRecord の参照
+    pub fn init(&mut self, record: &IsoRecord) {
+        self.perm = self.get_perm(record);
+        self.path = self.decode_path(&record.path);
+        self.size = record.size;
+        self.time = self.get_time(record);
+    }
+
+    /// エントリのパーミッション文字列を取得する。
+    ///
+    /// Rock Ridge 拡張がない場合はデフォルトのパーミッション文字列を返す。
+    ///
+    /// # Arguments
+    /// * `record` - IsoRecord の参照
+    ///
+    /// # Returns
+    /// `ls -l` 形式のパーミッション文字列
+    /// 例： `drwxr-xr-x`
+    fn get_perm(&self, record: &IsoRecord) -> String {
+        let first = if record.mode.is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            'l'
+        } else if record.is_dir {
+            'd'
+        } else {
+            '-'
+        };
+        let Some(mode) = record.mode else {
+            return self.default_perm(first);
+        };
+        let perm = perm_string(mode);
+        format!("{}{}", first, perm)
+    }
+
+    /// エントリの最終更新日時を取得する。
+    ///
+    /// `time_style` に基づいてフォーマットされる。
+    ///
+    /// # Arguments
+    /// * `record` - IsoRecord の参照
+    ///
+    /// # Returns
+    /// フォーマットされた日時文字列
+    fn get_time(&self, record: &IsoRecord) -> String {
+        let Some(dt) = record
+            .mtime
+            .and_then(|ts| Local.timestamp_opt(ts, 0).single())
+        else {
+            return self.default_time();
+        };
+        dt.format(&self.time_style).to_string()
+    }
+}
+
+impl ArchiveEntry for IsoEntry {
+    fn perm(&self) -> String {
+        self.perm.clone()
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn time(&self) -> String {
+        self.time.clone()
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    archives::SevenZipEntry,
+    traits::{Archive, ArchiveEntry, ArchiveEntryIter},
+};
+
+use std::io::Result;
+
+/// 7z アーカイブを扱う構造体。
+///
+/// 一覧の取得にはヘッダーのみを読み込み、データの展開は行わない。
+///
+/// # Fields
+/// * `time_style` - 日時のフォーマット文字列
+/// * `archive` - sevenz_rust::Archive インスタンス
+pub struct SevenZip {
+    time_style: String,
+    archive: sevenz_rust::Archive,
+}
+
+impl SevenZip {
+    /// 新しい SevenZip インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `path` - 7z ファイルのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// 初期化された SevenZip インスタンス
+    pub fn new(path: &str, time_style: &str) -> anyhow::Result<Self> {
+        let archive = sevenz_rust::Archive::open(path)?;
+        Ok(Self {
+            time_style: time_style.to_owned(),
+            archive,
+        })
+    }
+}
+
+impl Archive for SevenZip {
+    fn entries(&mut self) -> Result<ArchiveEntryIter<'_>> {
+        let iter = self.archive.files.iter().map(|e| {
+            let mut item = SevenZipEntry::new(&self.time_style);
+            item.init(e);
+            Ok(Box::new(item) as Box<dyn ArchiveEntry>)
+        });
+        Ok(Box::new(iter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unicode_normalization::UnicodeNormalization as _;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    #[test]
+    fn test_run_success() -> anyhow::Result<()> {
+        let archive = "./tests/archives/dir-entries.7z";
+        let time_style = "%y/%m/%d %H:%M:%S";
+        let mut sz = SevenZip::new(archive, time_style)?;
+        let mut entries: Vec<Box<dyn ArchiveEntry>> = vec![];
+        for entry in sz.entries()? {
+            let entry = entry?;
+            entries.push(entry);
+        }
+        assert_eq!(entries.len(), 31);
+        assert_eq!(entries[0].path(), "._2 image.jpg");
+        assert_eq!(entries[1].perm(), "-rw-r--r--");
+        assert_eq!(entries[1].size(), 176819);
+        assert_eq!(entries[12].path(), nfc("1 ゲーム/1 テキスト.txt"));
+        assert_eq!(entries[29].path(), nfc("1 ゲーム/"));
+        assert_eq!(entries[29].perm(), "drwxr-xr-x");
+        assert_eq!(entries[30].path(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_error() -> Result<()> {
+        let archive = "./tests/archives/nonexistent.7z";
+        let time_style = "%y/%m/%d %H:%M:%S";
+        let sz = SevenZip::new(archive, time_style);
+        assert!(sz.is_err());
+        let archive = "./tests/archives/dir-entries.zip";
+        let sz = SevenZip::new(archive, time_style);
+        assert!(sz.is_err());
+        Ok(())
+    }
+}
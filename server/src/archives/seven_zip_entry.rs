@@ -0,0 +1,162 @@
+use crate::{helpers::perm_string, traits::ArchiveEntry};
+
+use chrono::{Local, TimeZone as _};
+use sevenz_rust::SevenZArchiveEntry;
+
+/// Windows 属性の上位 16 ビットに UNIX のモードが
+/// 格納されていることを示すフラグ。
+///
+/// p7zip や libarchive で作成されたアーカイブで使われる。
+const UNIX_EXTENSION: u32 = 0x8000;
+
+/// シンボリックリンクを表すファイル種別 (S_IFLNK)。
+const S_IFLNK: u32 = 0o120000;
+
+/// ファイル種別を取り出すためのマスク (S_IFMT)。
+const S_IFMT: u32 = 0o170000;
+
+/// 7z のエントリを表す構造体。
+///
+/// # Fields
+/// * `time_style` - 日時のフォーマット文字列
+/// * `perm` - パーミッション文字列
+/// * `path` - エントリのパス
+/// * `size` - ファイルサイズ (バイト)
+/// * `time` - 最終更新日時
+pub struct SevenZipEntry {
+    time_style: String,
+    perm: String,
+    path: String,
+    size: u64,
+    time: String,
+}
+
+impl SevenZipEntry {
+    /// 新しい SevenZipEntry インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// デフォルト値で初期化された SevenZipEntry インスタンス
+    pub fn new(time_style: &str) -> Self {
+        let mut ins = Self {
+            time_style: time_style.to_owned(),
+            perm: String::new(),
+            path: String::new(),
+            size: 0,
+            time: String::new(),
+        };
+        ins.perm = ins.default_perm('-');
+        ins.size = ins.default_size();
+        ins.time = ins.default_time();
+        ins
+    }
+
+    /// SevenZArchiveEntry を元にこのインスタンスを初期化する。
+    ///
+    /// # Arguments
+    /// * `file` - SevenZArchiveEntry の参照
+    pub fn init(&mut self, file: &SevenZArchiveEntry) {
+        self.perm = self.get_perm(file);
+        self.path = self.get_path(file);
+        self.size = self.get_size(file);
+        self.time = self.get_time(file);
+    }
+
+    /// エントリのパーミッション文字列を取得する。
+    ///
+    /// UNIX のモードが格納されていない場合は
+    /// デフォルトのパーミッション文字列を返す。
+    ///
+    /// # Arguments
+    /// * `file` - SevenZArchiveEntry の参照
+    ///
+    /// # Returns
+    /// `ls -l` 形式のパーミッション文字列
+    /// 例： `drwxr-xr-x`
+    fn get_perm(&self, file: &SevenZArchiveEntry) -> String {
+        let attrs = file.windows_attributes();
+        let mode = (file.has_windows_attributes && attrs & UNIX_EXTENSION != 0)
+            .then_some(attrs >> 16);
+        let first = if mode.is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            'l'
+        } else if file.is_directory() {
+            'd'
+        } else {
+            '-'
+        };
+        let Some(mode) = mode else {
+            return self.default_perm(first);
+        };
+        let perm = perm_string(mode);
+        format!("{}{}", first, perm)
+    }
+
+    /// エントリのパスを取得する。
+    ///
+    /// 他のアーカイブ形式に合わせて、区切り文字はスラッシュに統一し、
+    /// ディレクトリの場合は末尾にスラッシュを付ける。
+    ///
+    /// # Arguments
+    /// * `file` - SevenZArchiveEntry の参照
+    ///
+    /// # Returns
+    /// デコードされたパス文字列
+    fn get_path(&self, file: &SevenZArchiveEntry) -> String {
+        let mut path = file.name().replace('\\', "/");
+        if file.is_directory() && !path.ends_with('/') {
+            path.push('/');
+        }
+        self.decode_path(path.as_bytes())
+    }
+
+    /// エントリのサイズを取得する。
+    ///
+    /// # Arguments
+    /// * `file` - SevenZArchiveEntry の参照
+    ///
+    /// # Returns
+    /// ファイルサイズ (バイト)
+    fn get_size(&self, file: &SevenZArchiveEntry) -> u64 {
+        file.size()
+    }
+
+    /// エントリの最終更新日時を取得する。
+    ///
+    /// `time_style` に基づいてフォーマットされる。
+    ///
+    /// # Arguments
+    /// * `file` - SevenZArchiveEntry の参照
+    ///
+    /// # Returns
+    /// フォーマットされた日時文字列
+    fn get_time(&self, file: &SevenZArchiveEntry) -> String {
+        if !file.has_last_modified_date {
+            return self.default_time();
+        }
+        let ts = file.last_modified_date().to_unix_time();
+        let Some(dt) = Local.timestamp_opt(ts, 0).single() else {
+            return self.default_time();
+        };
+        dt.format(&self.time_style).to_string()
+    }
+}
+
+impl ArchiveEntry for SevenZipEntry {
+    fn perm(&self) -> String {
+        self.perm.clone()
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn time(&self) -> String {
+        self.time.clone()
+    }
+}
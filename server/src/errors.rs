@@ -2,14 +2,24 @@
 
 mod bookmark_error;
 mod command_error;
+mod fs_error;
+mod journal_error;
+mod rename_error;
+mod search_error;
 mod sender_error;
 mod task_error;
+mod trash_error;
 mod virtual_dir_error;
 mod watch_error;
 
 pub use bookmark_error::BookmarkError;
 pub use command_error::CommandError;
+pub use fs_error::FsError;
+pub use journal_error::JournalError;
+pub use rename_error::RenameError;
+pub use search_error::SearchError;
 pub use sender_error::SenderError;
 pub use task_error::TaskError;
+pub use trash_error::TrashError;
 pub use virtual_dir_error::VirtualDirError;
 pub use watch_error::WatchError;
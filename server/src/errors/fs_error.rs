@@ -0,0 +1,37 @@
+use crate::traits::ErrorCode;
+
+use thiserror::Error;
+
+/// ファイルシステム操作に関するエラー。
+///
+/// # Variants
+/// * `NotDir` - ディレクトリではない
+/// * `IntoItself` - ディレクトリを自身の配下にコピーしようとした
+/// * `Aborted` - 操作が中止された
+/// * `AlreadyExists` - 同名のエントリがすでに存在する
+/// * `ReplaceDir` - ディレクトリをディレクトリ以外で上書きしようとした
+#[derive(Debug, Error, PartialEq)]
+pub enum FsError {
+    #[error("Not a directory ({0})")]
+    NotDir(String),
+    #[error("Cannot copy a directory into itself ({0})")]
+    IntoItself(String),
+    #[error("Operation aborted")]
+    Aborted,
+    #[error("Already exists ({0})")]
+    AlreadyExists(String),
+    #[error("Cannot replace a directory with a non-directory ({0})")]
+    ReplaceDir(String),
+}
+
+impl ErrorCode for FsError {
+    fn code(&self) -> &str {
+        match self {
+            Self::NotDir(_) => "E007001",
+            Self::IntoItself(_) => "E007002",
+            Self::Aborted => "E007003",
+            Self::AlreadyExists(_) => "E007004",
+            Self::ReplaceDir(_) => "E007005",
+        }
+    }
+}
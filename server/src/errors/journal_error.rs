@@ -0,0 +1,33 @@
+use crate::traits::ErrorCode;
+
+use thiserror::Error;
+
+/// 操作履歴 (undo/redo) に関するエラー。
+///
+/// # Variants
+/// * `NothingToUndo` - 取り消せる操作がない
+/// * `NothingToRedo` - やり直せる操作がない
+/// * `Conflict` - 操作後にエントリが変更されており、取り消し・やり直しできない
+/// * `IO` - ファイルアクセスに失敗した
+#[derive(Debug, Error, PartialEq)]
+pub enum JournalError {
+    #[error("Nothing to undo")]
+    NothingToUndo,
+    #[error("Nothing to redo")]
+    NothingToRedo,
+    #[error("Entry has been changed ({0})")]
+    Conflict(String),
+    #[error("Failed to access file: {0}")]
+    IO(String),
+}
+
+impl ErrorCode for JournalError {
+    fn code(&self) -> &str {
+        match self {
+            Self::NothingToUndo => "E010001",
+            Self::NothingToRedo => "E010002",
+            Self::Conflict(_) => "E010003",
+            Self::IO(_) => "E010004",
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::traits::ErrorCode;
+
+use thiserror::Error;
+
+/// 一括リネームに関するエラー。
+///
+/// # Variants
+/// * `InvalidPattern` - 正規表現またはテンプレートが不正である
+/// * `InvalidName` - 変更後の名前がファイル名として不正である
+/// * `Collision` - 変更後の名前が他のエントリと衝突する
+#[derive(Debug, Error, PartialEq)]
+pub enum RenameError {
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+    #[error("Invalid file name ({0})")]
+    InvalidName(String),
+    #[error("Name collision ({0})")]
+    Collision(String),
+}
+
+impl ErrorCode for RenameError {
+    fn code(&self) -> &str {
+        match self {
+            Self::InvalidPattern(_) => "E009001",
+            Self::InvalidName(_) => "E009002",
+            Self::Collision(_) => "E009003",
+        }
+    }
+}
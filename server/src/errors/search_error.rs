@@ -0,0 +1,21 @@
+use crate::traits::ErrorCode;
+
+use thiserror::Error;
+
+/// ファイル検索に関するエラー。
+///
+/// # Variants
+/// * `InvalidPattern` - glob パターンまたは正規表現が不正である
+#[derive(Debug, Error, PartialEq)]
+pub enum SearchError {
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+}
+
+impl ErrorCode for SearchError {
+    fn code(&self) -> &str {
+        match self {
+            Self::InvalidPattern(_) => "E011001",
+        }
+    }
+}
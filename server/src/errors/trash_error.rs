@@ -0,0 +1,33 @@
+use crate::traits::ErrorCode;
+
+use thiserror::Error;
+
+/// ゴミ箱操作に関するエラー。
+///
+/// # Variants
+/// * `NotAvailable` - ゴミ箱が利用できない
+/// * `NotFound` - 指定されたアイテムがゴミ箱に見つからない
+/// * `Exists` - 復元先にエントリがすでに存在する
+/// * `IO` - ファイルアクセスに失敗した
+#[derive(Debug, Error, PartialEq)]
+pub enum TrashError {
+    #[error("Trash not available")]
+    NotAvailable,
+    #[error("Trash item not found ({0})")]
+    NotFound(String),
+    #[error("Entry already exists ({0})")]
+    Exists(String),
+    #[error("Failed to access file: {0}")]
+    IO(String),
+}
+
+impl ErrorCode for TrashError {
+    fn code(&self) -> &str {
+        match self {
+            Self::NotAvailable => "E008001",
+            Self::NotFound(_) => "E008002",
+            Self::Exists(_) => "E008003",
+            Self::IO(_) => "E008004",
+        }
+    }
+}
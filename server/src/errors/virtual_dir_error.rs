@@ -8,6 +8,12 @@ use thiserror::Error;
 /// * `UnsupportedArchive` - サポートされていないアーカイブ形式である
 /// * `OutsideRoot` - 仮想ディレクトリ外へのアクセスである
 /// * `Args` - コマンド引数が不正である
+/// * `NotFound` - アーカイブ内にエントリが存在しない
+/// * `AlreadyExists` - アーカイブ内に同名のエントリがすでに存在する
+/// * `UnknownFormat` - 内容からアーカイブ形式を判別できない
+/// * `PasswordRequired` - 暗号化されたエントリの展開にパスワードが必要である
+/// * `WrongPassword` - パスワードが間違っている
+/// * `TooLarge` - エントリが大きすぎて展開できない
 #[derive(Debug, Error, PartialEq)]
 pub enum VirtualDirError {
     #[error("Unsupported archive type")]
@@ -16,6 +22,18 @@ pub enum VirtualDirError {
     OutsideRoot,
     #[error("Invalid arguments")]
     Args,
+    #[error("Entry not found in the archive: {0}")]
+    NotFound(String),
+    #[error("Entry already exists in the archive: {0}")]
+    AlreadyExists(String),
+    #[error("Unknown archive format: {0}")]
+    UnknownFormat(String),
+    #[error("Password required: {0}")]
+    PasswordRequired(String),
+    #[error("Wrong password: {0}")]
+    WrongPassword(String),
+    #[error("Entry too large to extract: {0}")]
+    TooLarge(String),
 }
 
 impl ErrorCode for VirtualDirError {
@@ -24,6 +42,12 @@ impl ErrorCode for VirtualDirError {
             Self::UnsupportedArchive => "E006001",
             Self::OutsideRoot => "E006002",
             Self::Args => "E006003",
+            Self::NotFound(_) => "E006004",
+            Self::AlreadyExists(_) => "E006005",
+            Self::UnknownFormat(_) => "E006006",
+            Self::PasswordRequired(_) => "E006007",
+            Self::WrongPassword(_) => "E006008",
+            Self::TooLarge(_) => "E006009",
         }
     }
 }
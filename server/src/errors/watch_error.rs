@@ -8,12 +8,15 @@ use thiserror::Error;
 /// * `Watch` - ディレクトリ監視の開始に失敗した
 /// * `Dir` - ディレクトリが利用不可になった
 ///   対象が削除された場合などに発生する。
+/// * `NotWatched` - ディレクトリが監視されていない
 #[derive(Debug, Error, PartialEq)]
 pub enum WatchError {
     #[error("Failed to watch ({1}): {0}")]
     Watch(String, String),
     #[error("Directory unavailable ({1}): {0}")]
     Dir(String, String),
+    #[error("Directory not watched ({0})")]
+    NotWatched(String),
 }
 
 impl ErrorCode for WatchError {
@@ -21,6 +24,7 @@ impl ErrorCode for WatchError {
         match self {
             Self::Watch(_, _) => "E004001",
             Self::Dir(_, _) => "E004002",
+            Self::NotWatched(_) => "E004003",
         }
     }
 }
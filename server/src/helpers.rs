@@ -12,6 +12,7 @@ use std::{
     path::{Path, PathBuf},
 };
 use unicode_normalization::UnicodeNormalization as _;
+use uuid::Uuid;
 
 /// バイト列を文字列にデコードする。
 ///
@@ -35,6 +36,25 @@ pub fn decode_string(raw: &[u8]) -> String {
     decoded.into_owned()
 }
 
+/// バイト列がテキストか否かを判定する。
+///
+/// ファイルの先頭部分を渡して、テキストファイルか否かの判定に使用する。
+/// 空のバイト列はテキストとして扱う。
+///
+/// # Arguments
+/// * `bytes` - 判定対象のバイト列
+pub fn is_text(bytes: &[u8]) -> bool {
+    if bytes.is_empty() {
+        return true;
+    }
+    if let Some(kind) = infer::get(bytes)
+        && !kind.mime_type().starts_with("text/")
+    {
+        return false;
+    }
+    content_inspector::inspect(bytes).is_text()
+}
+
 /// ファイルサイズを ls コマンド風の文字列に変換する。
 ///
 /// # Arguments
@@ -265,6 +285,22 @@ pub fn quote_paths(paths: &[String]) -> String {
         .join(" ")
 }
 
+/// 指定したパスと同じディレクトリに、一時ファイルのパスを作成する。
+///
+/// 書き出し後に rename で置き換えるためのもので、
+/// アトミックに置き換えられるよう同じファイルシステム上に作成する。
+///
+/// # Arguments
+/// * `path` - 置き換え先のパス
+///
+/// # Returns
+/// `.{ファイル名}.{UUID}.tmp` 形式の隠しファイルのパス
+pub fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = format!(".{name}.{}.tmp", Uuid::new_v4());
+    path.with_file_name(tmp)
+}
+
 /// アプリケーションロゴを表示する (Standard)。
 pub fn logo_standard() {
     let logo = r"
@@ -289,6 +325,14 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_is_text() {
+        assert!(is_text(b""));
+        assert!(is_text("テスト".as_bytes()));
+        assert!(!is_text(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(!is_text(b"foo\0bar"));
+    }
+
     #[test]
     fn test_decode_string() {
         let utf8_nfc = "テスト".as_bytes();
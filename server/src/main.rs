@@ -13,7 +13,7 @@ use axum::{
     Json, Router,
     body::Body,
     extract::{
-        Path as AxumPath, State,
+        Path as AxumPath, Query, State,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
     http::{HeaderMap, HeaderValue, Request, Response, StatusCode},
@@ -23,11 +23,19 @@ use axum::{
 use base64::{Engine as _, engine::general_purpose};
 use clap::Parser;
 use futures::stream::StreamExt as _;
-use helpers::{decode_string, logo_standard};
+use helpers::{decode_string, is_text, logo_standard};
 use html_escape::encode_quoted_attribute;
-use managers::{BookmarkManager, TaskManager, WatchManager};
-use misc::{Command, FrameSet, Sender, SenderTrait};
-use models::{ClientConfig, MimeType, TaskArg};
+use managers::{
+    ArchiveCacheManager, ArchiveIndexManager, BookmarkManager, JournalManager,
+    TaskManager, ThumbnailManager, TrashManager, WatchManager,
+};
+use misc::{
+    ArchiveSummary, Command, DirSummary, FrameSet, Highlighter, Sender,
+    SenderTrait,
+};
+use models::{
+    ArchiveKind, CacheLease, ClientConfig, MimeType, TaskArg, ThumbnailQuery,
+};
 use regex::Regex;
 use std::{
     fs::{self, create_dir_all},
@@ -35,8 +43,11 @@ use std::{
     sync::Arc,
 };
 use tasks::{
-    AbortProgressTask, BookmarkTask, ChangeDirTask, ChangeVirtualDirTask,
-    ExtractEntriesTask, OpenTask, ProgressTask, RemoveClientTask, ShTask,
+    AbortProgressTask, AddEntriesTask, BookmarkTask, ChangeDirTask,
+    ChangeVirtualDirTask, CopyTask, DeleteEntriesTask, DeleteTask,
+    ExtractEntriesTask, GrepTask, HistoryTask, MkdirTask, MoveTask, OpenTask,
+    PackTask, ProgressTask, RedoTask, RemoveClientTask, RenameEntryTask,
+    RenameTask, ResyncTask, SearchTask, ShTask, TrashTask, UndoTask,
 };
 use tokio::{io::AsyncReadExt as _, sync::OnceCell};
 use tower_http::services::{ServeDir, ServeFile};
@@ -56,6 +67,10 @@ const HTML_TEMPLATE: &str = r#"
 </html>
 "#;
 
+/// テキストのプレビューで読み込むバイト数の上限。(1 MiB)
+/// これを超える部分は表示しない。
+const MAX_PREVIEW_TEXT_SIZE: u64 = 1024 * 1024;
+
 /// コマンドライン引数の構造体。
 ///
 /// # Fields
@@ -93,10 +108,20 @@ struct Args {
 /// # Fields
 /// * `args` - コマンドライン引数
 /// * `task_manager` - TaskManager インスタンス
+/// * `archive_index_manager` - ArchiveIndexManager インスタンス
+///   アーカイブの概要をプレビューする際に使用する。
+/// * `archive_cache_manager` - ArchiveCacheManager インスタンス
+///   アーカイブ内のファイルをプレビューする際に使用する。
+/// * `highlighter` - テキストのプレビューで使用する Highlighter インスタンス
+/// * `thumbnail_manager` - ThumbnailManager インスタンス
 /// * `mime_types` - クライアントの MIME タイプ設定
 struct AppState {
     args: Arc<Args>,
     task_manager: Arc<TaskManager>,
+    archive_index_manager: Arc<ArchiveIndexManager>,
+    archive_cache_manager: Arc<ArchiveCacheManager>,
+    highlighter: Arc<Highlighter>,
+    thumbnail_manager: Arc<ThumbnailManager>,
     mime_types: OnceCell<Arc<Vec<MimeType>>>,
 }
 
@@ -200,35 +225,97 @@ fn validate_args(args: &Args) -> Result<()> {
 /// 以下のタスクを登録する。
 /// - kill: ProgressTask の中止
 /// - cd: ディレクトリの変更
+/// - resync: 監視中ディレクトリの全エントリの再送
 /// - bookmark: ブックマークの操作
 /// - open: エントリをアプリで開く
+/// - copy: エントリのコピー
+/// - move: エントリの移動
+/// - delete: エントリの削除
+/// - rename: エントリの一括リネーム
+/// - pack: アーカイブの作成
+/// - trash: ゴミ箱の操作
+/// - mkdir: ディレクトリの作成
+/// - undo: 直近のファイル操作の取り消し
+/// - redo: 取り消したファイル操作のやり直し
+/// - history: ファイル操作の履歴の取得
+/// - search: エントリの検索
+/// - grep: ファイルの内容の検索
 /// - progress: ProgressTask の実行
 /// - sh: シェルコマンドの実行
 /// - cvd: 仮想ディレクトリの変更
 /// - vcp: アーカイブからエントリを抽出
+/// - vadd: アーカイブにエントリを追加
+/// - vrm: アーカイブからエントリを削除
+/// - vmv: アーカイブ内のエントリの名前を変更
 /// - remove_client: クライアントの削除 (内部タスク)
 ///
 /// # Arguments
 /// * `args` - コマンドライン引数
+/// * `archive_index_manager` - ArchiveIndexManager インスタンス
+/// * `archive_cache_manager` - ArchiveCacheManager インスタンス
 ///
 /// # Returns
 /// TaskManager インスタンス
-fn create_task_manager(args: &Args) -> Arc<TaskManager> {
+fn create_task_manager(
+    args: &Args,
+    archive_index_manager: Arc<ArchiveIndexManager>,
+    archive_cache_manager: Arc<ArchiveCacheManager>,
+) -> Arc<TaskManager> {
     let watch_manager = WatchManager::new(&args.time_style);
     let bookmark_manager = BookmarkManager::new(&args.bookmark);
+    let trash_manager =
+        TrashManager::new(TrashManager::default_data_home(), &args.time_style);
+    let journal_manager =
+        JournalManager::new(&args.bookmark, trash_manager.clone());
     let mut task_manager = TaskManager::new();
     task_manager.register("kill", AbortProgressTask::new());
     task_manager.register("cd", ChangeDirTask::new(watch_manager.clone()));
+    task_manager.register("resync", ResyncTask::new(watch_manager.clone()));
     task_manager
         .register("bookmark", BookmarkTask::new(bookmark_manager.clone()));
     task_manager.register("open", OpenTask::new());
+    task_manager.register("copy", CopyTask::new());
+    task_manager.register("move", MoveTask::new(journal_manager.clone()));
+    task_manager.register("delete", DeleteTask::new());
+    task_manager.register("rename", RenameTask::new(journal_manager.clone()));
+    task_manager.register("pack", PackTask::new());
+    task_manager.register(
+        "trash",
+        TrashTask::new(trash_manager, journal_manager.clone()),
+    );
+    task_manager.register("mkdir", MkdirTask::new(journal_manager.clone()));
+    task_manager.register("undo", UndoTask::new(journal_manager.clone()));
+    task_manager.register("redo", RedoTask::new(journal_manager.clone()));
+    task_manager.register("history", HistoryTask::new(journal_manager.clone()));
+    task_manager.register("search", SearchTask::new(&args.time_style));
+    task_manager.register("grep", GrepTask::new());
     task_manager.register("progress", ProgressTask::new());
     task_manager.register("sh", ShTask::new());
     task_manager.register(
         "cvd",
-        ChangeVirtualDirTask::new(watch_manager.clone(), &args.time_style),
+        ChangeVirtualDirTask::new(
+            watch_manager.clone(),
+            archive_cache_manager.clone(),
+            archive_index_manager.clone(),
+            &args.time_style,
+        ),
+    );
+    task_manager.register(
+        "vcp",
+        ExtractEntriesTask::new(journal_manager, archive_cache_manager),
+    );
+    task_manager.register(
+        "vadd",
+        AddEntriesTask::new(archive_index_manager.clone(), &args.time_style),
+    );
+    task_manager.register(
+        "vrm",
+        DeleteEntriesTask::new(archive_index_manager.clone(), &args.time_style),
+    );
+    task_manager.register(
+        "vmv",
+        RenameEntryTask::new(archive_index_manager.clone(), &args.time_style),
     );
-    task_manager.register("vcp", ExtractEntriesTask::new());
     task_manager.register_internal(
         "remove_client",
         RemoveClientTask::new(watch_manager.clone()),
@@ -244,6 +331,7 @@ fn create_task_manager(args: &Args) -> Arc<TaskManager> {
 /// - `/init`: クライアント設定の初期化
 /// - `/config/{name}`: 設定ファイルの取得
 /// - `/preview/{*path}`: プレビュー機能
+/// - `/thumb/{*path}`: サムネイル画像
 /// - fallback: 静的ファイル配信
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -252,9 +340,26 @@ async fn main() -> Result<()> {
         eprintln!("{err}");
         std::process::exit(1);
     }
+    let archive_index_manager =
+        ArchiveIndexManager::new(ArchiveIndexManager::DEFAULT_LIMIT);
+    let archive_cache_manager = ArchiveCacheManager::new(
+        ArchiveCacheManager::default_root(),
+        ArchiveCacheManager::DEFAULT_LIMIT,
+        archive_index_manager.clone(),
+    );
     let state = Arc::new(AppState {
         args: Arc::new(args.clone()),
-        task_manager: create_task_manager(&args),
+        task_manager: create_task_manager(
+            &args,
+            archive_index_manager.clone(),
+            archive_cache_manager.clone(),
+        ),
+        archive_index_manager,
+        archive_cache_manager,
+        highlighter: Arc::new(Highlighter::new()),
+        thumbnail_manager: ThumbnailManager::new(
+            ThumbnailManager::default_root(),
+        ),
         mime_types: OnceCell::new(),
     });
     let app = Router::new()
@@ -264,6 +369,7 @@ async fn main() -> Result<()> {
         .route("/init", put(init_handler))
         .route("/config/{name}", get(config_handler))
         .route("/preview/{*path}", get(preview_handler))
+        .route("/thumb/{*path}", get(thumb_handler))
         .fallback_service(ServeDir::new(args.root))
         .with_state(state);
     let addr = format!("127.0.0.1:{}", args.port);
@@ -341,37 +447,110 @@ async fn is_text_file(path: &Path) -> Result<bool> {
     // 先頭 4096 バイトを判定に使う。
     let mut buf = [0u8; 4096];
     let len = file.read(&mut buf).await?;
-    // 空ファイルはテキストとして扱う。
-    if len == 0 {
-        return Ok(true);
-    }
-    let bytes = &buf[..len];
-    if let Some(kind) = infer::get(bytes)
-        && !kind.mime_type().starts_with("text/")
-    {
-        return Ok(false);
-    }
-    Ok(content_inspector::inspect(bytes).is_text())
+    Ok(is_text(&buf[..len]))
 }
 
 /// テキストファイルをプレビュー用 HTML テンプレートに埋め込んで返す。
 ///
+/// 構文を判別できるソースコードはシンタックスハイライトする。
+/// `MAX_PREVIEW_TEXT_SIZE` を超える部分は切り捨てる。
 /// テキストでない場合は None を返す。
 ///
 /// # Arguments
+/// * `highlighter` - Highlighter インスタンス
 /// * `path` - 読み込むテキストファイルのパス
+/// * `mime` - ユーザーのカスタム MIME タイプ
 ///
 /// # Returns
 /// テキスト内容を埋め込んだ HTML レスポンスまたは None
-async fn process_text(path: &Path) -> Option<Response<Body>> {
+async fn process_text(
+    highlighter: &Arc<Highlighter>,
+    path: &Path,
+    mime: Option<&str>,
+) -> Option<Response<Body>> {
     match is_text_file(path).await {
         Ok(true) => (),
         _ => return None,
     }
-    let bytes = tokio::fs::read(path).await.ok()?;
+    // 巨大なファイルでもメモリを使い切らないよう、先頭部分だけを読み込む。
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let mut bytes = Vec::new();
+    file.take(MAX_PREVIEW_TEXT_SIZE)
+        .read_to_end(&mut bytes)
+        .await
+        .ok()?;
     let text = decode_string(&bytes);
-    let text = encode_quoted_attribute(&text);
-    let html = HTML_TEMPLATE.replace("<!---->", &text);
+
+    // ハイライトは CPU 負荷が高いため、専用のスレッドで行う。
+    // ハイライトできない場合は、エスケープしただけのテキストを埋め込む。
+    let highlighter = highlighter.clone();
+    let path = path.to_owned();
+    let mime = mime.map(str::to_owned);
+    let html = tokio::task::spawn_blocking(move || {
+        highlighter
+            .highlight(&text, &path, mime.as_deref())
+            .unwrap_or_else(|| encode_quoted_attribute(&text).into_owned())
+    })
+    .await
+    .ok()?;
+    let html = HTML_TEMPLATE.replace("<!---->", &html);
+    Some(ok_200(html))
+}
+
+/// ディレクトリの概要をプレビュー用 HTML テンプレートに埋め込んで返す。
+///
+/// 走査はブロッキング処理のため、専用のスレッドで行う。
+///
+/// # Arguments
+/// * `path` - ディレクトリのパス
+/// * `time_style` - 日時のフォーマット文字列
+///
+/// # Returns
+/// 概要を埋め込んだ HTML レスポンス
+/// ディレクトリを読み込めない場合は None
+async fn process_dir(path: &Path, time_style: &str) -> Option<Response<Body>> {
+    let path = path.to_owned();
+    let time_style = time_style.to_owned();
+    let html = tokio::task::spawn_blocking(move || {
+        DirSummary::new(&path).map(|summary| summary.html(&time_style))
+    })
+    .await
+    .ok()?
+    .ok()?;
+    let html = HTML_TEMPLATE.replace("<!---->", &html);
+    Some(ok_200(html))
+}
+
+/// アーカイブの概要をプレビュー用 HTML テンプレートに埋め込んで返す。
+///
+/// アーカイブの種類はファイルの内容から判別する。
+/// 走査はブロッキング処理のため、専用のスレッドで行う。
+/// 一度走査したアーカイブは索引が使われるため、二回目以降は速い。
+///
+/// # Arguments
+/// * `state` - アプリケーション共有データ
+/// * `path` - アーカイブファイルのパス
+///
+/// # Returns
+/// 概要を埋め込んだ HTML レスポンス
+/// アーカイブでない、または読み込めない場合は None
+async fn process_archive(
+    state: &AppState,
+    path: &Path,
+) -> Option<Response<Body>> {
+    let manager = state.archive_index_manager.clone();
+    let time_style = state.args.time_style.clone();
+    let path = path.to_str()?.to_owned();
+    let html = tokio::task::spawn_blocking(move || -> Result<String> {
+        let kind = ArchiveKind::detect(&path)?;
+        let size = fs::metadata(&path)?.len();
+        let mut archive = manager.open(&kind, &path, &time_style)?;
+        Ok(ArchiveSummary::new(&mut archive, size)?.html())
+    })
+    .await
+    .ok()?
+    .ok()?;
+    let html = HTML_TEMPLATE.replace("<!---->", &html);
     Some(ok_200(html))
 }
 
@@ -539,12 +718,72 @@ async fn config_handler(
     }
 }
 
+/// アーカイブ内のファイルエントリを展開し、そのパスを取得する。
+///
+/// 展開はブロッキング処理のため、専用のスレッドで行う。
+///
+/// # Arguments
+/// * `state` - アプリケーション共有データ
+/// * `path` - 仮想ディレクトリとしてのファイルエントリのパス
+/// * `limit` - 展開するバイト数の上限
+///   None の場合は、エントリ全体を展開する。
+///
+/// # Returns
+/// 展開されたファイルのパスとその利用権
+/// 利用権を保持している間は、ファイルがキャッシュから削除されない。
+/// アーカイブ内のパスでない、展開できない、
+/// またはキャッシュに収まらないほど大きい場合は None
+async fn extract_archive_entry(
+    state: &AppState,
+    path: &Path,
+    limit: Option<u64>,
+) -> Option<(PathBuf, CacheLease)> {
+    let manager = state.archive_cache_manager.clone();
+    let path = path.to_str()?.to_owned();
+    let res =
+        tokio::task::spawn_blocking(move || manager.extract_file(&path, limit))
+            .await;
+    let (path, lease) = res.ok()?.ok()??;
+    Some((PathBuf::from(path), lease))
+}
+
+/// アーカイブ内のファイルエントリを、プレビューのために展開する。
+///
+/// まずは先頭部分だけを展開し、テキストであればそれを使う。
+/// テキストのプレビューは先頭部分しか表示しないため、全体を展開するまでもない。
+/// テキストでない場合 (メディアやアーカイブ) に限り、全体を展開する。
+///
+/// # Arguments
+/// * `state` - アプリケーション共有データ
+/// * `path` - 仮想ディレクトリとしてのファイルエントリのパス
+///
+/// # Returns
+/// 展開されたファイルのパスとその利用権
+async fn extract_preview_entry(
+    state: &AppState,
+    path: &Path,
+) -> Option<(PathBuf, CacheLease)> {
+    let limit = MAX_PREVIEW_TEXT_SIZE;
+    let (head, lease) = extract_archive_entry(state, path, Some(limit)).await?;
+    // 上限に満たない場合は、エントリ全体を展開できている。
+    let len = tokio::fs::metadata(&head).await.ok()?.len();
+    if len < limit || is_text_file(&head).await.unwrap_or(false) {
+        return Some((head, lease));
+    }
+    extract_archive_entry(state, path, None).await
+}
+
 /// ファイルプレビューのハンドラー。
 ///
 /// パス `/preview/{*path}` に対するハンドラー。
+/// 仮想ディレクトリ内のパス (アーカイブのパス + アーカイブ内のパス) の場合は、
+/// エントリを展開したファイルを対象とする。
 /// ファイルの種類に応じて以下の処理を行う。
 /// - テキストファイル： プレビュー用 HTML テンプレートに埋め込んで返す。
+///   ソースコードはシンタックスハイライトする。
 /// - 画像／動画／音声／PDF： そのまま配信。
+/// - ディレクトリ： 配下のエントリの概要を HTML で返す。
+/// - アーカイブ： 展開せずに、エントリの概要を HTML で返す。
 /// - その他： 204 No Contentを返す。
 ///
 /// # Arguments
@@ -559,9 +798,23 @@ async fn preview_handler(
 ) -> impl IntoResponse {
     let p = format!("/{path}");
     let path = PathBuf::from(p);
-    if tokio::fs::metadata(&path).await.is_err() {
-        return error_204();
-    }
+    // ディレクトリの場合は、その概要を返す。
+    // 実在しないパスの場合は、アーカイブ内のファイルとみなして展開する。
+    // パスによる MIME タイプの判定には、展開前のパスを使う。
+    // 展開したファイルは、レスポンスを作り終えるまで利用権を保持する。
+    let (file, _lease) = match tokio::fs::metadata(&path).await {
+        Ok(meta) if meta.is_dir() => {
+            let time_style = &state.args.time_style;
+            return process_dir(&path, time_style)
+                .await
+                .unwrap_or_else(error_204);
+        }
+        Ok(_) => (path.clone(), None),
+        Err(_) => match extract_preview_entry(&state, &path).await {
+            Some((file, lease)) => (file, Some(lease)),
+            None => return error_204(),
+        },
+    };
 
     // ユーザーのカスタム MIME タイプ設定にマッチするものがあれば、
     // その MIME を基準にメディアかテキストかを判別して配信する。
@@ -574,19 +827,21 @@ async fn preview_handler(
         }
     });
     if let Some(mime) = mime {
-        if let Some(res) = process_media(&mime, &path).await {
+        if let Some(res) = process_media(&mime, &file).await {
             return res;
         }
-        if let Some(res) = process_text(&path).await {
+        if let Some(res) =
+            process_text(&state.highlighter, &file, Some(&mime)).await
+        {
             return res;
         }
         return error_204();
     }
 
     // infer で MIME タイプを判定し、メディアかどうかを判別する。
-    if let Ok(Some(kind)) = infer::get_from_path(&path) {
+    if let Ok(Some(kind)) = infer::get_from_path(&file) {
         let mime = kind.mime_type();
-        if let Some(res) = process_media(mime, &path).await {
+        if let Some(res) = process_media(mime, &file).await {
             return res;
         }
     }
@@ -594,12 +849,17 @@ async fn preview_handler(
     // infer で判定できない場合は mime_guess で拡張子から判定する。
     let guess = mime_guess::from_path(&path).first_or_octet_stream();
     let mime = guess.essence_str();
-    if let Some(res) = process_media(mime, &path).await {
+    if let Some(res) = process_media(mime, &file).await {
+        return res;
+    }
+
+    // アーカイブの場合は、その概要を返す。
+    if let Some(res) = process_archive(&state, &file).await {
         return res;
     }
 
     // 最後にテキストファイルかどうかを判別する。
-    if let Some(res) = process_text(&path).await {
+    if let Some(res) = process_text(&state.highlighter, &file, None).await {
         return res;
     }
 
@@ -607,6 +867,51 @@ async fn preview_handler(
     error_204()
 }
 
+/// サムネイル画像のハンドラー。
+///
+/// パス `/thumb/{*path}` に対するハンドラー。
+/// 画像を縮小した JPEG または WebP を返す。
+/// 生成したサムネイルはキャッシュされ、元画像が更新されるまで使い回される。
+/// 仮想ディレクトリ内のパスの場合は、エントリを展開した画像を対象とする。
+/// 画像でない、またはデコードできない場合は 204 No Content を返す。
+///
+/// # Arguments
+/// * `path` - 元画像のパス
+/// * `query` - サムネイルのサイズと形式
+/// * `state` - アプリケーション共有データ
+///
+/// # Returns
+/// サムネイル画像の HTTP レスポンス
+async fn thumb_handler(
+    AxumPath(path): AxumPath<String>,
+    Query(query): Query<ThumbnailQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let p = format!("/{path}");
+    let path = PathBuf::from(p);
+    // 展開した画像は、サムネイルを作り終えるまで利用権を保持する。
+    let (file, _lease) = match tokio::fs::metadata(&path).await {
+        Ok(meta) if meta.is_file() => (path, None),
+        Ok(_) => return error_204(),
+        Err(_) => match extract_archive_entry(&state, &path, None).await {
+            Some((file, lease)) => (file, Some(lease)),
+            None => return error_204(),
+        },
+    };
+
+    // デコードとエンコードは CPU 負荷が高いため、専用のスレッドで行う。
+    let manager = state.thumbnail_manager.clone();
+    let size = query.size.unwrap_or(ThumbnailManager::DEFAULT_SIZE);
+    let res = tokio::task::spawn_blocking(move || {
+        manager.thumbnail(&file, size, query.format)
+    })
+    .await;
+    let Ok(Ok(thumb)) = res else {
+        return error_204();
+    };
+    process_file(&thumb).await.unwrap_or_else(|_| error_500())
+}
+
 /// WebSocket ハンドラー。
 ///
 /// パス `/ws` に対するハンドラー。
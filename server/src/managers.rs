@@ -1,11 +1,21 @@
 //! 情報の管理機構を提供するモジュール。
 
+mod archive_cache_manager;
+mod archive_index_manager;
 mod bookmark_manager;
+mod journal_manager;
 mod task_manager;
+mod thumbnail_manager;
+mod trash_manager;
 mod watch_manager;
 
+pub use archive_cache_manager::ArchiveCacheManager;
+pub use archive_index_manager::ArchiveIndexManager;
 pub use bookmark_manager::BookmarkManager;
+pub use journal_manager::JournalManager;
 pub use task_manager::TaskManager;
+pub use thumbnail_manager::ThumbnailManager;
+pub use trash_manager::TrashManager;
 pub use watch_manager::{WatchManager, WatchManagerTrait};
 
 #[cfg(test)]
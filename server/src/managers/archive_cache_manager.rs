@@ -0,0 +1,749 @@
+use crate::{
+    errors::VirtualDirError,
+    managers::ArchiveIndexManager,
+    misc::{Extractor, ProgressJob, VirtualDir},
+    models::{ArchiveKind, ArchiveLocation, CacheLease},
+};
+
+use anyhow::Result;
+use std::{
+    collections::HashSet,
+    env, fs,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+};
+
+/// キャッシュされた入れ子のアーカイブ。
+///
+/// # Fields
+/// * `key` - キャッシュキー
+/// * `dir` - 展開先ディレクトリ
+/// * `path` - 展開されたアーカイブファイルのパス
+/// * `size` - アーカイブファイルのサイズ (バイト)
+/// * `lease` - 利用権
+///   複製が保持されている間は、上限を超えても削除しない。
+struct CacheItem {
+    key: u64,
+    dir: PathBuf,
+    path: String,
+    size: u64,
+    lease: CacheLease,
+}
+
+/// キャッシュの状態。
+///
+/// # Fields
+/// * `items` - キャッシュされたアーカイブ
+///   最も古く使われたものから順に並ぶ。
+/// * `pending` - 展開中のキャッシュキー
+struct Cache {
+    items: Vec<CacheItem>,
+    pending: HashSet<u64>,
+}
+
+/// 展開中であることを示す印。
+///
+/// 破棄された時点で展開中のキャッシュキーから取り除き、
+/// 展開を待っているスレッドを起こす。
+/// 展開が失敗や panic で終わっても、待っているスレッドが取り残されないようにするため。
+///
+/// # Fields
+/// * `manager` - ArchiveCacheManager インスタンス
+/// * `key` - キャッシュキー
+struct Pending<'a> {
+    manager: &'a ArchiveCacheManager,
+    key: u64,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        self.manager.lock().pending.remove(&self.key);
+        self.manager.cond.notify_all();
+    }
+}
+
+/// アーカイブ内のアーカイブを一時ディレクトリに展開してキャッシュする構造体。
+///
+/// 仮想ディレクトリのパスに含まれる入れ子のアーカイブを順に展開し、
+/// 最も内側のアーカイブの所在を解決する。
+/// キャッシュの合計サイズが上限を超えた場合、最も古く使われたものから削除する。
+/// ただし、利用権が保持されているもの (使用中のもの) は削除しない。
+/// 単体で上限を超えるエントリは展開しない。
+/// 一時ディレクトリはサーバーの起動時と終了時に削除される。
+///
+/// # Fields
+/// * `root` - キャッシュを保存するディレクトリ
+/// * `limit` - キャッシュの合計サイズの上限 (バイト)
+/// * `archive_index_manager` - ArchiveIndexManager インスタンス
+/// * `cache` - キャッシュの状態
+///   ロックはキャッシュの参照と追加の間だけ保持し、展開中は保持しない。
+/// * `cond` - 展開の完了を待つための条件変数
+pub struct ArchiveCacheManager {
+    root: PathBuf,
+    limit: u64,
+    archive_index_manager: Arc<ArchiveIndexManager>,
+    cache: Mutex<Cache>,
+    cond: Condvar,
+}
+
+impl ArchiveCacheManager {
+    /// キャッシュの合計サイズの上限の既定値。(1 GiB)
+    pub const DEFAULT_LIMIT: u64 = 1024 * 1024 * 1024;
+
+    /// 新しい ArchiveCacheManager を作成する。
+    ///
+    /// シングルトンとして使用される。
+    /// `root` に残っている以前のキャッシュは削除される。
+    ///
+    /// # Arguments
+    /// * `root` - キャッシュを保存するディレクトリ
+    /// * `limit` - キャッシュの合計サイズの上限 (バイト)
+    /// * `archive_index_manager` - ArchiveIndexManager インスタンス
+    ///
+    /// # Returns
+    /// ArchiveCacheManager の Arc
+    pub fn new(
+        root: PathBuf,
+        limit: u64,
+        archive_index_manager: Arc<ArchiveIndexManager>,
+    ) -> Arc<Self> {
+        let _ = fs::remove_dir_all(&root);
+        Arc::new(Self {
+            root,
+            limit,
+            archive_index_manager,
+            cache: Mutex::new(Cache {
+                items: Vec::new(),
+                pending: HashSet::new(),
+            }),
+            cond: Condvar::new(),
+        })
+    }
+
+    /// キャッシュを保存するディレクトリの既定値を取得する。
+    ///
+    /// 複数のサーバーが同時に起動しても衝突しないよう、
+    /// 一時ディレクトリ配下にプロセス ID ごとに作成する。
+    pub fn default_root() -> PathBuf {
+        env::temp_dir().join(format!("footloose2-{}", process::id()))
+    }
+
+    /// キャッシュのロックを取得する。
+    ///
+    /// ロックが汚染されていても、キャッシュの整合性は
+    /// ファイルの有無で確認するため、そのまま使用する。
+    fn lock(&self) -> MutexGuard<'_, Cache> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 仮想ディレクトリのパスが指すアーカイブの所在を解決する。
+    ///
+    /// パスの途中にアーカイブファイルが含まれる場合、それを展開して中に入る。
+    /// 例： /a/release.tgz/dist/app.zip/src
+    /// -> release.tgz 内の dist/app.zip を展開し、その中の src を指す。
+    ///
+    /// # Arguments
+    /// * `kind` - 最も外側のアーカイブの種類
+    /// * `archive` - 最も外側のアーカイブファイルのパス
+    /// * `path` - 仮想ディレクトリの絶対パス
+    /// * `password` - 暗号化された zip を展開する際のパスワード
+    ///
+    /// # Returns
+    /// `path` を含む最も内側のアーカイブの所在
+    /// `path` がアーカイブ外の場合は、最も外側のアーカイブとなる。
+    /// 入れ子のアーカイブの場合は利用権を含み、
+    /// それを保持している間は展開されたファイルが削除されない。
+    pub fn resolve(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        path: &str,
+        password: Option<&str>,
+    ) -> Result<ArchiveLocation> {
+        let mut location = ArchiveLocation::new(*kind, archive);
+        let Some(mut rest) = VirtualDir::inner_path(archive, path) else {
+            return Ok(location);
+        };
+        loop {
+            // アーカイブらしい名前が含まれていなければ、探すまでもない。
+            if !rest.split('/').any(|s| ArchiveKind::from_name(s).is_some()) {
+                return Ok(location);
+            }
+            let Some(entry) = self.find_nested(&location, &rest)? else {
+                return Ok(location);
+            };
+            // 外側のアーカイブの利用権は、内側の展開が終わるまで保持される。
+            let (path, lease) =
+                self.extract(&location, &entry, password, None)?;
+            // 拡張子ではなく、展開したファイルの内容から種類を判別する。
+            let kind = ArchiveKind::detect(&path)?;
+            rest = rest[entry.len()..].trim_start_matches('/').to_owned();
+            location = ArchiveLocation {
+                kind,
+                path,
+                root: format!("{}/{entry}", location.root),
+                lease: Some(lease),
+            };
+        }
+    }
+
+    /// 仮想ディレクトリのパスが指すファイルエントリを展開し、そのパスを取得する。
+    ///
+    /// パスの祖先のうち、実在するファイルを最も外側のアーカイブとみなし、
+    /// その種類はファイルの内容から判別する。
+    /// 入れ子のアーカイブ内のエントリも展開でき、
+    /// 展開したファイルは入れ子のアーカイブと同様にキャッシュされる。
+    /// パスワードは扱わないため、暗号化されたエントリは展開できない。
+    ///
+    /// # Arguments
+    /// * `path` - 仮想ディレクトリとしてのファイルエントリの絶対パス
+    ///   例： /Users/xxxx/Desktop/release.tgz/dist/readme.md
+    /// * `limit` - 展開するバイト数の上限
+    ///   指定した場合は、エントリの先頭部分だけを展開する。
+    ///   None の場合は、エントリ全体を展開する。
+    ///
+    /// # Returns
+    /// 展開されたファイルのパスとその利用権
+    /// 利用権を保持している間は、ファイルがキャッシュから削除されない。
+    /// アーカイブ内のパスでない場合は None
+    ///
+    /// # Errors
+    /// - `VirtualDirError::UnknownFormat`:
+    ///   アーカイブ形式を判別できない。
+    /// - `VirtualDirError::NotFound`:
+    ///   エントリがない、またはファイルではない。
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   エントリが暗号化されている。
+    /// - `VirtualDirError::TooLarge`:
+    ///   エントリ全体がキャッシュの上限を超える。
+    pub fn extract_file(
+        &self,
+        path: &str,
+        limit: Option<u64>,
+    ) -> Result<Option<(String, CacheLease)>> {
+        let Some(archive) =
+            Path::new(path).ancestors().skip(1).find(|p| p.is_file())
+        else {
+            return Ok(None);
+        };
+        let archive = archive.to_string_lossy();
+        let kind = ArchiveKind::detect(&archive)?;
+        let location = self.resolve(&kind, &archive, path, None)?;
+        let Some(entry) = VirtualDir::inner_path(&location.root, path) else {
+            return Ok(None);
+        };
+        // 入れ子のアーカイブ自身を指している場合は、展開済みのファイルを返す。
+        if entry.is_empty() {
+            let lease = location.lease.unwrap_or_default();
+            return Ok(Some((location.path, lease)));
+        }
+        self.extract(&location, &entry, None, limit).map(Some)
+    }
+
+    /// アーカイブ内のパスに含まれる、入れ子のアーカイブを探す。
+    ///
+    /// 候補となるのは、アーカイブの拡張子を持つファイルエントリである。
+    ///
+    /// # Arguments
+    /// * `location` - 探索するアーカイブの所在
+    /// * `rest` - アーカイブ内のパス (先頭のスラッシュなし)
+    ///
+    /// # Returns
+    /// 最も浅い位置にあるアーカイブファイルのパス
+    /// 見つからない場合は None
+    fn find_nested(
+        &self,
+        location: &ArchiveLocation,
+        rest: &str,
+    ) -> Result<Option<String>> {
+        let mut archive = self.archive_index_manager.open(
+            &location.kind,
+            &location.path,
+            "",
+        )?;
+        let mut found: Option<String> = None;
+        for entry in archive.entries()? {
+            let path = entry?.path();
+            // ディレクトリエントリは対象外。
+            if path.ends_with('/') || ArchiveKind::from_name(&path).is_none() {
+                continue;
+            }
+            if rest != path && !rest.starts_with(&format!("{path}/")) {
+                continue;
+            }
+            if found.as_ref().is_none_or(|p| path.len() < p.len()) {
+                found = Some(path);
+            }
+        }
+        Ok(found)
+    }
+
+    /// アーカイブ内のファイルエントリのサイズを取得する。
+    ///
+    /// # Arguments
+    /// * `location` - アーカイブの所在
+    /// * `entry` - アーカイブ内のパス (先頭のスラッシュなし)
+    ///
+    /// # Returns
+    /// 展開後のサイズ (バイト)
+    /// エントリが見つからない場合は None
+    fn entry_size(
+        &self,
+        location: &ArchiveLocation,
+        entry: &str,
+    ) -> Result<Option<u64>> {
+        let mut archive = self.archive_index_manager.open(
+            &location.kind,
+            &location.path,
+            "",
+        )?;
+        for e in archive.entries()? {
+            let e = e?;
+            if e.path() == entry {
+                return Ok(Some(e.size()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// キャッシュキーを求める。
+    ///
+    /// アーカイブファイルが更新された場合に古いキャッシュを使わないよう、
+    /// パスに加えて更新日時とサイズも含める。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `entry` - アーカイブ内のパス
+    /// * `limit` - 展開するバイト数の上限
+    fn cache_key(
+        &self,
+        archive: &str,
+        entry: &str,
+        limit: Option<u64>,
+    ) -> Result<u64> {
+        let meta = fs::metadata(archive)?;
+        let mut hasher = DefaultHasher::new();
+        archive.hash(&mut hasher);
+        meta.mtime().hash(&mut hasher);
+        meta.mtime_nsec().hash(&mut hasher);
+        meta.size().hash(&mut hasher);
+        entry.hash(&mut hasher);
+        limit.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// 入れ子のアーカイブを展開し、そのパスを取得する。
+    ///
+    /// キャッシュ済みの場合は展開せずにそのパスを返す。
+    /// 同じエントリを展開中の場合は、その完了を待ってから結果を使う。
+    /// 異なるエントリの展開は並行して行われる。
+    ///
+    /// # Arguments
+    /// * `location` - 展開元のアーカイブの所在
+    /// * `entry` - 展開するアーカイブ内のパス (先頭のスラッシュなし)
+    /// * `password` - 暗号化された zip を展開する際のパスワード
+    /// * `limit` - 展開するバイト数の上限
+    ///   None の場合は、エントリ全体を展開する。
+    ///
+    /// # Returns
+    /// 展開されたアーカイブファイルのパスとその利用権
+    ///
+    /// # Errors
+    /// - `VirtualDirError::NotFound`:
+    ///   エントリを展開できなかった。
+    /// - `VirtualDirError::PasswordRequired`, `VirtualDirError::WrongPassword`:
+    ///   暗号化されたエントリを復号できなかった。
+    /// - `VirtualDirError::TooLarge`:
+    ///   エントリ全体がキャッシュの上限を超える。
+    fn extract(
+        &self,
+        location: &ArchiveLocation,
+        entry: &str,
+        password: Option<&str>,
+        limit: Option<u64>,
+    ) -> Result<(String, CacheLease)> {
+        let too_large = || VirtualDirError::TooLarge(entry.to_owned());
+        // キャッシュに収まらないエントリは展開しない。
+        // 展開前にエントリ一覧のサイズで確認し、
+        // サイズを偽ったエントリに備えて書き出すバイト数にも上限を設ける。
+        if limit.is_none()
+            && self
+                .entry_size(location, entry)?
+                .is_some_and(|size| size > self.limit)
+        {
+            return Err(too_large().into());
+        }
+        let max = limit.unwrap_or(self.limit).min(self.limit);
+        // 全体を展開する場合は、上限を超えたことが分かるよう 1 バイト余分に書き出す。
+        let write_limit = match limit {
+            Some(_) => max,
+            None => max.saturating_add(1),
+        };
+        let key = self.cache_key(&location.path, entry, limit)?;
+        let mut cache = self.lock();
+        loop {
+            if let Some(cached) = Self::take_cached(&mut cache, key) {
+                return Ok(cached);
+            }
+            if cache.pending.insert(key) {
+                break;
+            }
+            cache = self
+                .cond
+                .wait(cache)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        drop(cache);
+        let _pending = Pending { manager: self, key };
+
+        let dir = self.root.join(format!("{key:016x}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let (cwd, name) = match entry.rsplit_once('/') {
+            Some((parent, name)) => {
+                (format!("{}/{parent}", location.path), name)
+            }
+            None => (location.path.clone(), entry),
+        };
+        let srcs = [format!("/{entry}")];
+        let dest = dir.to_string_lossy();
+        // 進捗は報告しないため、使い捨ての ProgressJob を渡す。
+        let job = ProgressJob::new();
+        let res = Extractor::new(&job).with_limit(write_limit).extract(
+            &location.kind,
+            &location.path,
+            &srcs,
+            &dest,
+            &cwd,
+            password,
+        );
+        let path = dir.join(name);
+        if res.is_err() || !path.is_file() {
+            let _ = fs::remove_dir_all(&dir);
+            res?;
+            return Err(VirtualDirError::NotFound(entry.to_owned()).into());
+        }
+
+        let size = fs::metadata(&path)?.len();
+        if size > max {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(too_large().into());
+        }
+        let path = path.to_string_lossy().to_string();
+        let lease = CacheLease::new();
+        let mut cache = self.lock();
+        cache.items.push(CacheItem {
+            key,
+            dir,
+            path: path.clone(),
+            size,
+            lease: lease.clone(),
+        });
+        self.evict(&mut cache.items);
+        Ok((path, lease))
+    }
+
+    /// キャッシュ済みのアーカイブを探し、最後に使われたものとする。
+    ///
+    /// ファイルが削除されている場合は、キャッシュから取り除く。
+    ///
+    /// # Arguments
+    /// * `cache` - キャッシュの状態
+    /// * `key` - キャッシュキー
+    ///
+    /// # Returns
+    /// 展開されたアーカイブファイルのパスとその利用権
+    /// キャッシュされていない場合は None
+    fn take_cached(
+        cache: &mut Cache,
+        key: u64,
+    ) -> Option<(String, CacheLease)> {
+        let i = cache.items.iter().position(|item| item.key == key)?;
+        let item = cache.items.remove(i);
+        if !Path::new(&item.path).is_file() {
+            return None;
+        }
+        let cached = (item.path.clone(), item.lease.clone());
+        cache.items.push(item);
+        Some(cached)
+    }
+
+    /// 合計サイズが上限を超えている場合、古いキャッシュから削除する。
+    ///
+    /// 利用権が保持されているキャッシュは、使用中のため削除しない。
+    /// その場合は一時的に上限を超えることがあり、次回の追加時に改めて削除する。
+    /// 上限を超えるエントリは展開しないため、
+    /// 最後に使われたキャッシュが削除されることはない。
+    ///
+    /// # Arguments
+    /// * `items` - キャッシュされたアーカイブ
+    fn evict(&self, items: &mut Vec<CacheItem>) {
+        let mut total: u64 = items.iter().map(|item| item.size).sum();
+        let mut i = 0;
+        while total > self.limit && i + 1 < items.len() {
+            if items[i].lease.is_held() {
+                i += 1;
+                continue;
+            }
+            let item = items.remove(i);
+            total -= item.size;
+            let _ = fs::remove_dir_all(&item.dir);
+        }
+    }
+}
+
+impl Drop for ArchiveCacheManager {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn setup(limit: u64) -> Result<(String, Arc<ArchiveCacheManager>)> {
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        let index =
+            ArchiveIndexManager::new(ArchiveIndexManager::DEFAULT_LIMIT);
+        let cache = PathBuf::from(format!("{root}/cache"));
+        let manager = ArchiveCacheManager::new(cache, limit, index);
+        Ok((root, manager))
+    }
+
+    #[test]
+    fn test_resolve() -> Result<()> {
+        let (root, manager) = setup(ArchiveCacheManager::DEFAULT_LIMIT)?;
+        let archive = "./tests/archives/nested.tgz";
+
+        // 入れ子のアーカイブを含まない場合はそのまま。
+        let loc = manager.resolve(&ArchiveKind::Tgz, archive, archive, None)?;
+        assert!(loc.kind == ArchiveKind::Tgz);
+        assert_eq!(loc.path, archive);
+        assert_eq!(loc.root, archive);
+        let path = format!("{archive}/dist");
+        let loc = manager.resolve(&ArchiveKind::Tgz, archive, &path, None)?;
+        assert_eq!(loc.path, archive);
+        let loc =
+            manager.resolve(&ArchiveKind::Tgz, archive, "/foo/bar", None)?;
+        assert_eq!(loc.path, archive);
+
+        // 一段階の入れ子。
+        let path = format!("{archive}/dist/dir-entries.zip/1 text.txt");
+        let loc = manager.resolve(&ArchiveKind::Tgz, archive, &path, None)?;
+        assert!(loc.kind == ArchiveKind::Zip);
+        assert!(loc.path.starts_with(&format!("{root}/cache/")));
+        assert!(loc.path.ends_with("/dir-entries.zip"));
+        assert_eq!(loc.root, format!("{archive}/dist/dir-entries.zip"));
+        let cached = loc.path;
+
+        // 二回目はキャッシュが使われる。
+        let loc = manager.resolve(&ArchiveKind::Tgz, archive, &path, None)?;
+        assert_eq!(loc.path, cached);
+
+        // 二段階の入れ子。
+        let path = format!("{archive}/dist/inner.tar/dir-entries.7z");
+        let loc = manager.resolve(&ArchiveKind::Tgz, archive, &path, None)?;
+        assert!(loc.kind == ArchiveKind::SevenZip);
+        assert!(loc.path.ends_with("/dir-entries.7z"));
+        assert_eq!(loc.root, path);
+        assert_eq!(manager.lock().items.len(), 3);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_evict() -> Result<()> {
+        // dir-entries.zip と inner.tar の両方は収まらない上限。
+        let (root, manager) = setup(1_100_000)?;
+        let archive = "./tests/archives/nested.tgz";
+        let path = format!("{archive}/dist/dir-entries.zip");
+        let zip = manager
+            .resolve(&ArchiveKind::Tgz, archive, &path, None)?
+            .path;
+        assert!(Path::new(&zip).is_file());
+
+        // 上限を超えたため、古いキャッシュは削除される。
+        let path = format!("{archive}/dist/inner.tar");
+        let tar = manager
+            .resolve(&ArchiveKind::Tgz, archive, &path, None)?
+            .path;
+        assert!(!Path::new(&zip).exists());
+        assert!(Path::new(&tar).is_file());
+        assert_eq!(manager.lock().items.len(), 1);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_evict_leased() -> Result<()> {
+        // dir-entries.zip と inner.tar の両方は収まらない上限。
+        let (root, manager) = setup(1_100_000)?;
+        let archive = "./tests/archives/nested.tgz";
+        let path = format!("{archive}/dist/dir-entries.zip");
+        let zip = manager.resolve(&ArchiveKind::Tgz, archive, &path, None)?;
+
+        // 利用権が保持されている間は、上限を超えても削除されない。
+        let path = format!("{archive}/dist/inner.tar");
+        let tar = manager
+            .resolve(&ArchiveKind::Tgz, archive, &path, None)?
+            .path;
+        assert!(Path::new(&zip.path).is_file());
+        assert!(Path::new(&tar).is_file());
+        assert_eq!(manager.lock().items.len(), 2);
+
+        // 利用権が破棄されると、次の追加時に削除される。
+        let ArchiveLocation {
+            path: zip, lease, ..
+        } = zip;
+        drop(lease);
+        let path =
+            format!("{archive}/dist/inner.tar/dir-entries.7z/1 text.txt");
+        let (text, _) = manager.extract_file(&path, Some(16))?.unwrap();
+        assert!(!Path::new(&zip).exists());
+        assert!(Path::new(&tar).is_file());
+        assert!(Path::new(&text).is_file());
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_concurrently() -> Result<()> {
+        let (root, manager) = setup(ArchiveCacheManager::DEFAULT_LIMIT)?;
+        let archive = "./tests/archives/nested.tgz";
+        let path = format!("{archive}/dist/dir-entries.zip/1 text.txt");
+
+        // 同じエントリは一度だけ展開され、全員が同じパスを得る。
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (manager, path) = (manager.clone(), path.clone());
+                thread::spawn(move || {
+                    manager
+                        .resolve(&ArchiveKind::Tgz, archive, &path, None)
+                        .map(|loc| loc.path)
+                })
+            })
+            .collect();
+        let paths = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Result<Vec<_>>>()?;
+        assert!(paths.iter().all(|p| p == &paths[0]));
+        let cache = manager.lock();
+        assert_eq!(cache.items.len(), 1);
+        assert!(cache.pending.is_empty());
+        drop(cache);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_file() -> Result<()> {
+        let (root, manager) = setup(ArchiveCacheManager::DEFAULT_LIMIT)?;
+        let archive = format!("{root}/nested.tgz");
+        fs::copy("./tests/archives/nested.tgz", &archive)?;
+
+        // アーカイブ内のファイル。
+        let path = format!("{archive}/dist/inner.tar");
+        let (file, _) = manager.extract_file(&path, None)?.unwrap();
+        assert!(file.starts_with(&format!("{root}/cache/")));
+        assert!(file.ends_with("/inner.tar"));
+
+        // 入れ子のアーカイブ内のファイル。
+        let path = format!("{archive}/dist/dir-entries.zip/1 text.txt");
+        let (file, _) = manager.extract_file(&path, None)?.unwrap();
+        assert!(file.ends_with("/1 text.txt"));
+        assert_eq!(fs::read(&file)?, fs::read("./tests/files/1 text.txt")?);
+
+        // 入れ子のアーカイブ自身。
+        let path = format!("{archive}/dist/dir-entries.zip");
+        let (file, _) = manager.extract_file(&path, None)?.unwrap();
+        assert!(file.ends_with("/dir-entries.zip"));
+
+        // ディレクトリや存在しないエントリは展開できない。
+        assert!(
+            manager
+                .extract_file(&format!("{archive}/dist"), None)
+                .is_err()
+        );
+        let path = format!("{archive}/dist/nonexistent.txt");
+        assert!(manager.extract_file(&path, None).is_err());
+
+        // 暗号化されたエントリは展開できない。
+        let encrypted = format!("{root}/encrypted.zip");
+        fs::copy("./tests/archives/encrypted-aes.zip", &encrypted)?;
+        let err = manager
+            .extract_file(&format!("{encrypted}/1 text.txt"), None)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDirError>(),
+            Some(VirtualDirError::PasswordRequired(_))
+        ));
+
+        // アーカイブ内のパスでない場合は None。
+        let path = format!("{root}/nonexistent/1 text.txt");
+        assert!(manager.extract_file(&path, None)?.is_none());
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_file_limit() -> Result<()> {
+        let (root, manager) = setup(500_000)?;
+        let archive = format!("{root}/nested.tgz");
+        fs::copy("./tests/archives/nested.tgz", &archive)?;
+        let text = fs::read("./tests/files/1 text.txt")?;
+
+        // 先頭部分だけを展開する。
+        let path =
+            format!("{archive}/dist/inner.tar/dir-entries.7z/1 text.txt");
+        let (head, _) = manager.extract_file(&path, Some(16))?.unwrap();
+        assert_eq!(fs::read(&head)?, text[..16]);
+        let (full, _) = manager.extract_file(&path, None)?.unwrap();
+        assert_ne!(head, full);
+        assert_eq!(fs::read(&full)?, text);
+
+        // 上限に満たないエントリはそのまま展開される。
+        let (file, _) =
+            manager.extract_file(&path, Some(1024 * 1024))?.unwrap();
+        assert_eq!(fs::read(&file)?, text);
+
+        // キャッシュに収まらないエントリは展開できない。
+        let path = format!("{archive}/dist/dir-entries.zip");
+        let err = manager.extract_file(&path, None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VirtualDirError>(),
+            Some(VirtualDirError::TooLarge(_))
+        ));
+        let total: u64 = manager.lock().items.iter().map(|i| i.size).sum();
+        assert!(total <= 500_000);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_error() -> Result<()> {
+        let (root, manager) = setup(ArchiveCacheManager::DEFAULT_LIMIT)?;
+        let archive = "./tests/archives/nonexistent.tgz";
+        let path = format!("{archive}/dist/dir-entries.zip");
+        assert!(
+            manager
+                .resolve(&ArchiveKind::Tgz, archive, &path, None)
+                .is_err()
+        );
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    archives::{Indexed, IndexedEntry},
+    misc::VirtualDir,
+    models::ArchiveKind,
+    traits::Archive,
+};
+
+use anyhow::Result;
+use std::{
+    fs,
+    os::unix::fs::MetadataExt as _,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// アーカイブファイルの同一性を判定するための情報。
+///
+/// いずれかが変わった場合、アーカイブは更新されたものとみなす。
+///
+/// # Fields
+/// * `ino` - inode 番号
+/// * `mtime` - 最終更新日時 (秒)
+/// * `mtime_nsec` - 最終更新日時 (ナノ秒)
+/// * `size` - ファイルサイズ (バイト)
+#[derive(Clone, Copy, PartialEq)]
+struct Stamp {
+    ino: u64,
+    mtime: i64,
+    mtime_nsec: i64,
+    size: u64,
+}
+
+/// キャッシュされたアーカイブの索引。
+///
+/// # Fields
+/// * `path` - アーカイブファイルのパス
+/// * `time_style` - 日時のフォーマット文字列
+/// * `stamp` - 走査した時点のアーカイブファイルの情報
+/// * `entries` - 全エントリの一覧
+/// * `mem_size` - 索引が占有するおおよそのメモリ量 (バイト)
+struct IndexItem {
+    path: String,
+    time_style: String,
+    stamp: Stamp,
+    entries: Arc<Vec<IndexedEntry>>,
+    mem_size: usize,
+}
+
+/// アーカイブを走査して得た全エントリの一覧 (索引) をキャッシュする構造体。
+///
+/// 一度走査したアーカイブは、ファイルが更新されない限り
+/// 開き直さずに一覧を作成できる。
+/// 索引の合計メモリ量が上限を超えた場合、最も古く使われたものから破棄する。
+///
+/// # Fields
+/// * `limit` - 索引の合計メモリ量の上限 (バイト)
+/// * `items` - キャッシュされた索引
+///   最も古く使われたものから順に並ぶ。
+pub struct ArchiveIndexManager {
+    limit: usize,
+    items: Mutex<Vec<IndexItem>>,
+}
+
+impl ArchiveIndexManager {
+    /// 索引の合計メモリ量の上限の既定値。(256 MiB)
+    pub const DEFAULT_LIMIT: usize = 256 * 1024 * 1024;
+
+    /// 新しい ArchiveIndexManager を作成する。
+    ///
+    /// シングルトンとして使用される。
+    ///
+    /// # Arguments
+    /// * `limit` - 索引の合計メモリ量の上限 (バイト)
+    ///
+    /// # Returns
+    /// ArchiveIndexManager の Arc
+    pub fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            items: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// キャッシュのロックを取得する。
+    ///
+    /// 索引は読み取り専用のため、ロックが汚染されていてもそのまま使用する。
+    fn lock(&self) -> MutexGuard<'_, Vec<IndexItem>> {
+        self.items.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// アーカイブファイルの同一性を判定するための情報を取得する。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    fn stamp(&self, archive: &str) -> Result<Stamp> {
+        let meta = fs::metadata(archive)?;
+        Ok(Stamp {
+            ino: meta.ino(),
+            mtime: meta.mtime(),
+            mtime_nsec: meta.mtime_nsec(),
+            size: meta.size(),
+        })
+    }
+
+    /// 索引を使ってアーカイブを開く。
+    ///
+    /// 索引がない、またはアーカイブが更新されている場合は
+    /// アーカイブを走査して索引を作成する。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// 索引のエントリ一覧を返す Archive インスタンス
+    pub fn open(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        time_style: &str,
+    ) -> Result<Box<dyn Archive>> {
+        let stamp = self.stamp(archive)?;
+        if let Some(entries) = self.get(archive, time_style, stamp) {
+            return Ok(Box::new(Indexed::new(entries)));
+        }
+
+        // 走査には時間がかかるため、ロックせずに行う。
+        let mut entries = Vec::new();
+        for entry in VirtualDir::open(kind, archive, time_style)?.entries()? {
+            entries.push(IndexedEntry::new(entry?.as_ref()));
+        }
+        let entries = Arc::new(entries);
+        self.insert(IndexItem {
+            path: archive.to_owned(),
+            time_style: time_style.to_owned(),
+            stamp,
+            mem_size: entries.iter().map(IndexedEntry::mem_size).sum(),
+            entries: entries.clone(),
+        });
+        Ok(Box::new(Indexed::new(entries)))
+    }
+
+    /// キャッシュされた索引を取得する。
+    ///
+    /// 取得した索引は最後に使われたものとして扱われる。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    /// * `stamp` - 現在のアーカイブファイルの情報
+    ///
+    /// # Returns
+    /// 索引がない、またはアーカイブが更新されている場合は None
+    fn get(
+        &self,
+        archive: &str,
+        time_style: &str,
+        stamp: Stamp,
+    ) -> Option<Arc<Vec<IndexedEntry>>> {
+        let mut items = self.lock();
+        let i = items.iter().position(|item| {
+            item.path == archive
+                && item.time_style == time_style
+                && item.stamp == stamp
+        })?;
+        let item = items.remove(i);
+        let entries = item.entries.clone();
+        items.push(item);
+        Some(entries)
+    }
+
+    /// 索引をキャッシュに追加する。
+    ///
+    /// 同じアーカイブの古い索引は破棄される。
+    /// 合計メモリ量が上限を超える場合は、古い索引から破棄する。
+    /// 単独で上限を超える索引はキャッシュしない。
+    ///
+    /// # Arguments
+    /// * `item` - 追加する索引
+    fn insert(&self, item: IndexItem) {
+        if item.mem_size > self.limit {
+            return;
+        }
+        let mut items = self.lock();
+        items
+            .retain(|i| i.path != item.path || i.time_style != item.time_style);
+        let mut total: usize = items.iter().map(|i| i.mem_size).sum();
+        while total + item.mem_size > self.limit && !items.is_empty() {
+            total -= items.remove(0).mem_size;
+        }
+        items.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread::sleep, time::Duration};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn paths(archive: &mut Box<dyn Archive>) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for entry in archive.entries()? {
+            paths.push(entry?.path());
+        }
+        Ok(paths)
+    }
+
+    #[test]
+    fn test_open() -> Result<()> {
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        let archive = format!("{root}/archive.tar");
+        fs::copy("./tests/archives/dir-entries.tar", &archive)?;
+        let manager =
+            ArchiveIndexManager::new(ArchiveIndexManager::DEFAULT_LIMIT);
+        let kind = ArchiveKind::Tar;
+
+        let mut index = manager.open(&kind, &archive, "%y")?;
+        let expected = paths(&mut VirtualDir::open(&kind, &archive, "%y")?)?;
+        assert_eq!(paths(&mut index)?, expected);
+        assert_eq!(manager.lock().len(), 1);
+
+        // 二回目は索引が使われる。
+        let mut index = manager.open(&kind, &archive, "%y")?;
+        assert_eq!(paths(&mut index)?, expected);
+        assert_eq!(manager.lock().len(), 1);
+
+        // 日時のフォーマットが異なる場合は別の索引となる。
+        manager.open(&kind, &archive, "%Y")?;
+        assert_eq!(manager.lock().len(), 2);
+
+        // アーカイブが更新された場合は走査し直す。
+        sleep(Duration::from_millis(10));
+        fs::copy("./tests/archives/no-dir-entries.tar", &archive)?;
+        let mut index = manager.open(&kind, &archive, "%y")?;
+        let expected = paths(&mut VirtualDir::open(&kind, &archive, "%y")?)?;
+        assert_eq!(paths(&mut index)?, expected);
+        assert_eq!(manager.lock().len(), 2);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_evict() -> Result<()> {
+        let zip = "./tests/archives/dir-entries.zip";
+        let tar = "./tests/archives/dir-entries.tar";
+        let manager =
+            ArchiveIndexManager::new(ArchiveIndexManager::DEFAULT_LIMIT);
+        manager.open(&ArchiveKind::Zip, zip, "%y")?;
+        manager.open(&ArchiveKind::Tar, tar, "%y")?;
+        let sizes = manager
+            .lock()
+            .iter()
+            .map(|i| i.mem_size)
+            .collect::<Vec<_>>();
+
+        // 上限を超えるため、古い索引は破棄される。
+        let manager = ArchiveIndexManager::new(sizes[0].max(sizes[1]));
+        manager.open(&ArchiveKind::Zip, zip, "%y")?;
+        manager.open(&ArchiveKind::Tar, tar, "%y")?;
+        let items = manager.lock();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tar);
+        drop(items);
+
+        // 単独で上限を超える索引はキャッシュしない。
+        let manager = ArchiveIndexManager::new(1);
+        let mut index = manager.open(&ArchiveKind::Zip, zip, "%y")?;
+        assert!(!paths(&mut index)?.is_empty());
+        assert!(manager.lock().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+use crate::{
+    errors::{FsError, JournalError, RenameError, TrashError},
+    managers::TrashManager,
+    misc::{Mover, ProgressJob, Renamer},
+    models::{ConflictPolicy, JournalEntry, Operation, RenameItem},
+};
+
+use anyhow::{Error, Result, bail};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use uuid::Uuid;
+
+/// 操作履歴ファイルの名前。
+///
+/// ブックマークファイルと同じディレクトリに保存される。
+const FILE_NAME: &str = "journal.json";
+
+/// 保持する操作履歴の最大件数。
+const MAX_ENTRIES: usize = 100;
+
+/// 操作日時のフォーマット。
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// 操作履歴。
+///
+/// いずれも古いものから順に並ぶ。
+///
+/// # Fields
+/// * `undo` - 取り消し可能な操作
+/// * `redo` - やり直し可能な操作
+#[derive(Default, Deserialize, Serialize)]
+struct Journal {
+    undo: Vec<JournalEntry>,
+    redo: Vec<JournalEntry>,
+}
+
+/// ファイル操作の履歴を管理し、取り消し・やり直しを行う構造体。
+///
+/// 履歴はブックマークファイルと同じディレクトリに保存され、
+/// サーバーを再起動しても引き継がれる。
+/// ブックマークファイルが未指定の場合はメモリ上でのみ保持する。
+///
+/// # Fields
+/// * `path` - 操作履歴ファイルのパス
+/// * `trash_manager` - TrashManager インスタンス
+/// * `journal` - 操作履歴
+pub struct JournalManager {
+    path: Option<PathBuf>,
+    trash_manager: Arc<TrashManager>,
+    journal: Mutex<Journal>,
+}
+
+impl JournalManager {
+    /// エラーを JournalError 型に変換する。
+    ///
+    /// # Arguments
+    /// * `err` - 変換元のエラー
+    ///
+    /// # Returns
+    /// 変換後のエラー
+    pub fn to_error(err: Error) -> Error {
+        if err.is::<JournalError>()
+            || err.is::<TrashError>()
+            || err.is::<RenameError>()
+            || err.is::<FsError>()
+        {
+            return err;
+        }
+        JournalError::IO(err.to_string()).into()
+    }
+
+    /// 新しい JournalManager を作成する。
+    ///
+    /// シングルトンとして使用される。
+    /// 操作履歴ファイルが存在する場合は読み込む。
+    ///
+    /// # Arguments
+    /// * `bookmark` - ブックマーク JSON ファイルのパス
+    /// * `trash_manager` - TrashManager インスタンス
+    ///
+    /// # Returns
+    /// JournalManager の Arc
+    pub fn new(
+        bookmark: &Option<String>,
+        trash_manager: Arc<TrashManager>,
+    ) -> Arc<Self> {
+        let path = bookmark
+            .as_ref()
+            .and_then(|p| Path::new(p).parent())
+            .map(|p| p.join(FILE_NAME));
+        // 読み込めない場合は空の履歴から始める。
+        let journal = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Arc::new(Self {
+            path,
+            trash_manager,
+            journal: Mutex::new(journal),
+        })
+    }
+
+    /// 操作を履歴に記録する。
+    ///
+    /// やり直し可能な操作は破棄される。
+    ///
+    /// # Arguments
+    /// * `op` - 記録する操作
+    pub fn record(&self, op: Operation) {
+        let entry = JournalEntry {
+            id: Uuid::new_v4().to_string(),
+            time: Local::now().format(DATE_FORMAT).to_string(),
+            op,
+        };
+        let mut journal = self.lock();
+        journal.undo.push(entry);
+        if journal.undo.len() > MAX_ENTRIES {
+            let n = journal.undo.len() - MAX_ENTRIES;
+            journal.undo.drain(..n);
+        }
+        journal.redo.clear();
+        // 保存に失敗しても操作自体は完了しているため、無視する。
+        let _ = self.save(&journal);
+    }
+
+    /// 直近の操作を取り消す。
+    ///
+    /// 失敗した場合、操作は取り消し可能なまま残る。
+    ///
+    /// # Returns
+    /// 取り消した後の操作履歴
+    ///
+    /// # Errors
+    /// - `JournalError::NothingToUndo`:
+    ///   取り消せる操作がない。
+    /// - `JournalError::Conflict`:
+    ///   操作後にエントリが変更されている。
+    pub fn undo(&self) -> Result<Value> {
+        let Some(mut entry) = self.lock().undo.pop() else {
+            bail!(JournalError::NothingToUndo);
+        };
+        let res = self.revert(&mut entry.op);
+        {
+            let mut journal = self.lock();
+            match res {
+                Ok(()) => journal.redo.push(entry),
+                Err(_) => journal.undo.push(entry),
+            }
+            let _ = self.save(&journal);
+        }
+        res?;
+        Ok(self.history())
+    }
+
+    /// 直近に取り消した操作をやり直す。
+    ///
+    /// 失敗した場合、操作はやり直し可能なまま残る。
+    ///
+    /// # Returns
+    /// やり直した後の操作履歴
+    ///
+    /// # Errors
+    /// - `JournalError::NothingToRedo`:
+    ///   やり直せる操作がない。
+    /// - `JournalError::Conflict`:
+    ///   取り消した後にエントリが変更されている。
+    pub fn redo(&self) -> Result<Value> {
+        let Some(mut entry) = self.lock().redo.pop() else {
+            bail!(JournalError::NothingToRedo);
+        };
+        let res = self.replay(&mut entry.op);
+        {
+            let mut journal = self.lock();
+            match res {
+                Ok(()) => journal.undo.push(entry),
+                Err(_) => journal.redo.push(entry),
+            }
+            let _ = self.save(&journal);
+        }
+        res?;
+        Ok(self.history())
+    }
+
+    /// 操作履歴を取得する。
+    ///
+    /// # Returns
+    /// `undo` と `redo` をキーに持つ JSON。いずれも新しいものから順に並ぶ。
+    pub fn history(&self) -> Value {
+        let journal = self.lock();
+        json!({
+            "undo": journal.undo.iter().rev().collect::<Vec<_>>(),
+            "redo": journal.redo.iter().rev().collect::<Vec<_>>(),
+        })
+    }
+
+    /// 操作を取り消す。
+    ///
+    /// 失敗した場合に中途半端な状態にならないよう、
+    /// 事前にすべてのエントリの状態を確認する。
+    ///
+    /// # Arguments
+    /// * `op` - 取り消す操作
+    ///   ゴミ箱を経由する場合、やり直しに必要な情報が書き込まれる。
+    fn revert(&self, op: &mut Operation) -> Result<()> {
+        match op {
+            Operation::Rename { items } => {
+                let items = invert(items);
+                check(&items)?;
+                Renamer::apply(&items)
+            }
+            Operation::Move { items } => {
+                let items = invert(items);
+                check(&items)?;
+                move_items(&items)
+            }
+            Operation::Mkdir { path } => fs::remove_dir(&*path)
+                .map_err(|_| JournalError::Conflict(path.clone()).into()),
+            Operation::Extract { paths, trashed } => {
+                if let Some(p) = paths.iter().find(|p| !exists(p)) {
+                    bail!(JournalError::Conflict(p.clone()));
+                }
+                trashed.clear();
+                for path in paths.iter() {
+                    let id = self.trash_manager.put(Path::new(path))?;
+                    trashed.push(RenameItem {
+                        from: path.clone(),
+                        to: id,
+                    });
+                }
+                Ok(())
+            }
+            Operation::Trash { items } => self.restore(items),
+        }
+    }
+
+    /// 取り消した操作をやり直す。
+    ///
+    /// # Arguments
+    /// * `op` - やり直す操作
+    ///   ゴミ箱を経由する場合、取り消しに必要な情報が書き込まれる。
+    fn replay(&self, op: &mut Operation) -> Result<()> {
+        match op {
+            Operation::Rename { items } => {
+                check(items)?;
+                Renamer::apply(items)
+            }
+            Operation::Move { items } => {
+                check(items)?;
+                move_items(items)
+            }
+            Operation::Mkdir { path } => {
+                if exists(path) {
+                    bail!(JournalError::Conflict(path.clone()));
+                }
+                Ok(fs::create_dir(&*path)?)
+            }
+            Operation::Extract { trashed, .. } => {
+                self.restore(trashed)?;
+                trashed.clear();
+                Ok(())
+            }
+            Operation::Trash { items } => {
+                check(items)?;
+                for item in items.iter_mut() {
+                    item.to = self.trash_manager.put(Path::new(&item.from))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// ゴミ箱内のアイテムを元の場所に復元する。
+    ///
+    /// # Arguments
+    /// * `items` - 復元するアイテム (`from` は元のパス、`to` は識別子)
+    fn restore(&self, items: &[RenameItem]) -> Result<()> {
+        check(&invert(items))?;
+        for item in items {
+            self.trash_manager.restore(&item.to)?;
+        }
+        Ok(())
+    }
+
+    /// 操作履歴をロックする。
+    ///
+    /// 操作履歴は常に整合した状態で更新されるため、
+    /// ロックがポイズンされていてもそのまま使用する。
+    fn lock(&self) -> MutexGuard<'_, Journal> {
+        self.journal.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 操作履歴をファイルに保存する。
+    ///
+    /// # Arguments
+    /// * `journal` - 保存する操作履歴
+    fn save(&self, journal: &Journal) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        fs::write(path, serde_json::to_string_pretty(journal)?)?;
+        Ok(())
+    }
+}
+
+/// `from` と `to` を入れ替える。
+///
+/// # Arguments
+/// * `items` - 入れ替える対象
+fn invert(items: &[RenameItem]) -> Vec<RenameItem> {
+    items
+        .iter()
+        .map(|item| RenameItem {
+            from: item.to.clone(),
+            to: item.from.clone(),
+        })
+        .collect()
+}
+
+/// `from` から `to` へ移すことができるか否かを検証する。
+///
+/// `from` がすべて存在し、`to` が存在しない (または `from` のいずれかである)
+/// 必要がある。
+///
+/// # Arguments
+/// * `items` - 検証する対象
+///
+/// # Errors
+/// - `JournalError::Conflict`:
+///   エントリが変更されている。
+fn check(items: &[RenameItem]) -> Result<()> {
+    let srcs = items
+        .iter()
+        .map(|i| i.from.as_str())
+        .collect::<HashSet<_>>();
+    for item in items {
+        if !exists(&item.from) {
+            bail!(JournalError::Conflict(item.from.clone()));
+        }
+        if exists(&item.to) && !srcs.contains(item.to.as_str()) {
+            bail!(JournalError::Conflict(item.to.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// エントリを移動する。
+///
+/// 移動先の親ディレクトリが存在しない場合は作成する。
+///
+/// # Arguments
+/// * `items` - 移動する対象
+fn move_items(items: &[RenameItem]) -> Result<()> {
+    let job = ProgressJob::new();
+    let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+    for item in items {
+        let dst = Path::new(&item.to);
+        if let Some(p) = dst.parent() {
+            fs::create_dir_all(p)?;
+        }
+        mover.move_entry(Path::new(&item.from), dst)?;
+    }
+    Ok(())
+}
+
+/// エントリが存在するか否かを判定する。
+///
+/// シンボリックリンクは辿らない。
+///
+/// # Arguments
+/// * `path` - エントリのパス
+fn exists(path: &str) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, setup_resources, teardown_resources,
+    };
+
+    use super::*;
+
+    async fn setup() -> Result<(String, Arc<JournalManager>)> {
+        let path = setup_resources("test").await?;
+        let data_home = PathBuf::from(format!("{path}/data"));
+        let trash = TrashManager::new(Some(data_home), "%y/%m/%d %H:%M:%S");
+        let bookmark = Some(format!("{path}/bookmark.json"));
+        Ok((path.clone(), JournalManager::new(&bookmark, trash)))
+    }
+
+    fn item(path: &str, from: &str, to: &str) -> RenameItem {
+        RenameItem {
+            from: format!("{path}/{from}"),
+            to: format!("{path}/{to}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_rename() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items =
+            vec![item(&path, "test1", "test2"), item(&path, "test2", "test1")];
+        Renamer::apply(&items)?;
+        manager.record(Operation::Rename { items });
+        manager.undo()?;
+        assert!(Path::new(&format!("{path}/test1/test1.txt")).is_file());
+        assert!(Path::new(&format!("{path}/test2/test2.txt")).is_file());
+        manager.redo()?;
+        assert!(Path::new(&format!("{path}/test2/test1.txt")).is_file());
+        assert!(Path::new(&format!("{path}/test1/test2.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_move() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = vec![item(&path, "test.txt", "test3/test.txt")];
+        fs::rename(&items[0].from, &items[0].to)?;
+        manager.record(Operation::Move { items });
+        manager.undo()?;
+        assert!(Path::new(&format!("{path}/test.txt")).is_file());
+        assert!(!Path::new(&format!("{path}/test3/test.txt")).exists());
+        manager.redo()?;
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        assert!(Path::new(&format!("{path}/test3/test.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_mkdir() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let dir = format!("{path}/new");
+        fs::create_dir(&dir)?;
+        manager.record(Operation::Mkdir { path: dir.clone() });
+        manager.undo()?;
+        assert!(!Path::new(&dir).exists());
+        manager.redo()?;
+        assert!(Path::new(&dir).is_dir());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_extract() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let paths = vec![format!("{path}/test1")];
+        let trashed = vec![];
+        manager.record(Operation::Extract { paths, trashed });
+        manager.undo()?;
+        assert!(!Path::new(&format!("{path}/test1")).exists());
+        let history = manager.history();
+        assert_eq!(
+            history["redo"][0]["trashed"][0]["from"],
+            json!(format!("{path}/test1"))
+        );
+        manager.redo()?;
+        assert!(Path::new(&format!("{path}/test1/test1.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_trash() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let from = format!("{path}/test.txt");
+        let to = manager.trash_manager.put(Path::new(&from))?;
+        manager.record(Operation::Trash {
+            items: vec![RenameItem { from, to }],
+        });
+        manager.undo()?;
+        assert!(Path::new(&format!("{path}/test.txt")).is_file());
+        manager.redo()?;
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        manager.undo()?;
+        assert!(Path::new(&format!("{path}/test.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_conflict() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = vec![item(&path, "test.txt", "test3/test.txt")];
+        fs::rename(&items[0].from, &items[0].to)?;
+        manager.record(Operation::Move { items });
+        fs::write(format!("{path}/test.txt"), "new")?;
+        let res = manager.undo();
+        assert_err(&res.unwrap_err(), &JournalError::Conflict("".to_owned()));
+        assert_eq!(manager.history()["undo"].as_array().unwrap().len(), 1);
+        assert!(Path::new(&format!("{path}/test3/test.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nothing_to_undo() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let res = manager.undo();
+        assert_err(&res.unwrap_err(), &JournalError::NothingToUndo);
+        let res = manager.redo();
+        assert_err(&res.unwrap_err(), &JournalError::NothingToRedo);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record() -> Result<()> {
+        let (path, manager) = setup().await?;
+        fs::create_dir(format!("{path}/new"))?;
+        manager.record(Operation::Mkdir {
+            path: format!("{path}/new"),
+        });
+        manager.undo()?;
+        for i in 0..MAX_ENTRIES + 1 {
+            let path = format!("{path}/{i}");
+            manager.record(Operation::Mkdir { path });
+        }
+        let history = manager.history();
+        assert_eq!(history["undo"].as_array().unwrap().len(), MAX_ENTRIES);
+        assert_eq!(history["undo"][0]["path"], json!(format!("{path}/100")));
+        assert_eq!(history["undo"][0]["kind"], json!("mkdir"));
+        assert_eq!(history["redo"].as_array().unwrap().len(), 0);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_persist() -> Result<()> {
+        let (path, manager) = setup().await?;
+        manager.record(Operation::Mkdir {
+            path: format!("{path}/new"),
+        });
+        assert!(Path::new(&format!("{path}/{FILE_NAME}")).is_file());
+        let bookmark = Some(format!("{path}/bookmark.json"));
+        let trash = manager.trash_manager.clone();
+        let manager = JournalManager::new(&bookmark, trash);
+        assert_eq!(manager.history()["undo"].as_array().unwrap().len(), 1);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -49,7 +49,10 @@ impl TaskManager {
         tokio::spawn(async move {
             while let Some(TaskControl { pid, status }) = rx.recv().await {
                 // 中断処理関数を取得しつつ、マップから削除する。
-                let dispose = disposers_.lock().await.remove(&pid).unwrap();
+                // 登録前に終了したタスクなど、未知の pid は無視する。
+                let Some(dispose) = disposers_.lock().await.remove(&pid) else {
+                    continue;
+                };
                 // 中止の場合は中断処理関数を実行する。
                 // それ以外の場合はすでにタスクは終わってるはずなため何もしない。
                 if status == TaskStatus::Abort {
@@ -147,6 +150,10 @@ impl TaskManager {
                     .entry(arg.sender.id().to_owned())
                     .or_insert_with(Vec::new)
                     .push(res.pid);
+                // 登録が済んでから処理本体を開始させる。
+                if let Some(start) = res.start {
+                    let _ = start.send(());
+                }
                 Ok(())
             }
             TaskResult::Error(res) => Err(res.err),
@@ -216,6 +223,7 @@ impl TaskManager {
 #[cfg(test)]
 mod tests {
     use crate::{
+        misc::ProgressJob,
         models::TaskResult,
         test_helpers::{
             assert_err, create_command, setup_sender, setup_task_arg, sleep,
@@ -223,6 +231,9 @@ mod tests {
         traits::{MockInternalTaskBase, MockTaskBase},
     };
 
+    use mockall::Sequence;
+    use serde_json::Value;
+
     use super::*;
 
     fn setup_cmd() -> Result<Command> {
@@ -283,6 +294,46 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_run_progress_task_finishing_immediately() -> Result<()> {
+        // 登録前に終了しないよう、PROGRESS_TASK の後に終了メッセージが届く。
+        let mut seq = Sequence::new();
+        let mut sender = setup_sender();
+        for _ in 0..2 {
+            sender
+                .expect_progress_task()
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_, _| Ok(()));
+            sender
+                .expect_progress_end()
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_, _| Ok(()));
+        }
+        let task_arg = setup_task_arg(sender);
+        let cmd = setup_cmd()?;
+        let mut mock = MockTaskBase::new();
+        mock.expect_validate().times(2).return_const(true);
+        mock.expect_run().times(2).returning(|_, arg, tx| {
+            let job = ProgressJob::new();
+            let (pid, dispose, start) =
+                job.spawn(arg.sender.clone(), tx, |_| Ok(Value::Null));
+            TaskResult::progress(pid, dispose, Some(start))
+        });
+        let mut manager = TaskManager::new();
+        manager.register("test", mock);
+        manager.run(&cmd, &task_arg).await?;
+        sleep(100).await;
+        assert!(manager.disposers.lock().await.is_empty());
+
+        // 終了メッセージの処理後も、タスク制御は動き続ける。
+        manager.run(&cmd, &task_arg).await?;
+        sleep(100).await;
+        assert!(manager.disposers.lock().await.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_register_and_run_internal_task() -> Result<()> {
         let sender = setup_sender();
@@ -0,0 +1,266 @@
+use crate::{helpers::temp_path, models::ThumbnailFormat};
+
+use anyhow::Result;
+use image::{
+    DynamicImage, ImageDecoder as _, ImageReader,
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+};
+use std::{
+    env,
+    fs::{self, File},
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    io::BufWriter,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// JPEG の品質。
+const JPEG_QUALITY: u8 = 80;
+
+/// サムネイル画像を生成し、ディスクにキャッシュする構造体。
+///
+/// 元画像のパス、更新日時、サイズをキーとするため、
+/// 元画像が更新された場合は生成し直す。
+/// キャッシュはサーバーを再起動しても保持される。
+///
+/// # Fields
+/// * `root` - キャッシュを保存するディレクトリ
+pub struct ThumbnailManager {
+    root: PathBuf,
+}
+
+impl ThumbnailManager {
+    /// 長辺の最大ピクセル数の既定値。
+    pub const DEFAULT_SIZE: u32 = 256;
+
+    /// 長辺の最大ピクセル数の下限。
+    pub const MIN_SIZE: u32 = 16;
+
+    /// 長辺の最大ピクセル数の上限。
+    pub const MAX_SIZE: u32 = 1024;
+
+    /// 新しい ThumbnailManager を作成する。
+    ///
+    /// シングルトンとして使用される。
+    ///
+    /// # Arguments
+    /// * `root` - キャッシュを保存するディレクトリ
+    ///
+    /// # Returns
+    /// ThumbnailManager の Arc
+    pub fn new(root: PathBuf) -> Arc<Self> {
+        Arc::new(Self { root })
+    }
+
+    /// キャッシュを保存するディレクトリの既定値を取得する。
+    ///
+    /// `$XDG_CACHE_HOME` が未設定または相対パスの場合は `$HOME/.cache` とし、
+    /// `$HOME` も未設定の場合は一時ディレクトリとする。
+    pub fn default_root() -> PathBuf {
+        let cache_home = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| {
+                env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache"))
+            })
+            .unwrap_or_else(env::temp_dir);
+        cache_home.join("footloose2/thumbnails")
+    }
+
+    /// キャッシュキーを求める。
+    ///
+    /// # Arguments
+    /// * `path` - 元画像のパス
+    /// * `size` - 長辺の最大ピクセル数
+    /// * `format` - 画像の形式
+    fn cache_key(
+        &self,
+        path: &Path,
+        size: u32,
+        format: ThumbnailFormat,
+    ) -> Result<u64> {
+        let meta = fs::metadata(path)?;
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        meta.mtime().hash(&mut hasher);
+        meta.mtime_nsec().hash(&mut hasher);
+        meta.size().hash(&mut hasher);
+        size.hash(&mut hasher);
+        format.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// サムネイル画像を取得する。
+    ///
+    /// キャッシュ済みの場合は生成せずにそのパスを返す。
+    /// 元画像が指定したサイズより小さい場合は拡大しない。
+    /// EXIF の回転情報は反映される。
+    ///
+    /// # Arguments
+    /// * `path` - 元画像のパス
+    /// * `size` - 長辺の最大ピクセル数
+    ///   `MIN_SIZE` から `MAX_SIZE` の範囲に丸められる。
+    /// * `format` - 画像の形式
+    ///
+    /// # Returns
+    /// サムネイル画像のパス
+    ///
+    /// # Errors
+    /// 元画像を読み込めない、またはデコードできない場合はエラー
+    pub fn thumbnail(
+        &self,
+        path: &Path,
+        size: u32,
+        format: ThumbnailFormat,
+    ) -> Result<PathBuf> {
+        let size = size.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        let key = self.cache_key(path, size, format)?;
+        let thumb =
+            self.root.join(format!("{key:016x}.{}", format.extension()));
+        if thumb.is_file() {
+            return Ok(thumb);
+        }
+
+        let mut decoder = ImageReader::open(path)?
+            .with_guessed_format()?
+            .into_decoder()?;
+        let orientation = decoder.orientation()?;
+        let mut img = DynamicImage::from_decoder(decoder)?;
+        if img.width() > size || img.height() > size {
+            img = img.thumbnail(size, size);
+        }
+        img.apply_orientation(orientation);
+
+        // 同時に同じサムネイルが要求されても壊れないよう、
+        // 一時ファイルに書き出してから置き換える。
+        fs::create_dir_all(&self.root)?;
+        let tmp = temp_path(&thumb);
+        let res = self.encode(&img, &tmp, format);
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+            res?;
+        }
+        fs::rename(&tmp, &thumb)?;
+        Ok(thumb)
+    }
+
+    /// 画像をエンコードしてファイルに書き出す。
+    ///
+    /// # Arguments
+    /// * `img` - 書き出す画像
+    /// * `path` - 書き出し先のパス
+    /// * `format` - 画像の形式
+    fn encode(
+        &self,
+        img: &DynamicImage,
+        path: &Path,
+        format: ThumbnailFormat,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            // JPEG はアルファチャンネルを扱えないため、RGB に変換する。
+            ThumbnailFormat::Jpeg => {
+                let encoder =
+                    JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY);
+                DynamicImage::from(img.to_rgb8())
+                    .write_with_encoder(encoder)?;
+            }
+            ThumbnailFormat::Webp => {
+                let encoder = WebPEncoder::new_lossless(&mut writer);
+                DynamicImage::from(img.to_rgba8())
+                    .write_with_encoder(encoder)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView as _, ImageFormat};
+    use std::{thread::sleep, time::Duration};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn setup() -> Result<(String, Arc<ThumbnailManager>)> {
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        let manager = ThumbnailManager::new(PathBuf::from(&root).join("cache"));
+        Ok((root, manager))
+    }
+
+    #[test]
+    fn test_thumbnail() -> Result<()> {
+        let (root, manager) = setup()?;
+        let src = Path::new("./tests/files/2 image.jpg");
+        let (width, height) = image::open(src)?.dimensions();
+
+        let thumb = manager.thumbnail(src, 64, ThumbnailFormat::Jpeg)?;
+        assert!(thumb.starts_with(format!("{root}/cache")));
+        assert_eq!(thumb.extension().unwrap(), "jpg");
+        let img = image::open(&thumb)?;
+        assert_eq!(img.width().max(img.height()), 64);
+        assert_eq!(width > height, img.width() > img.height());
+
+        // 二回目はキャッシュが使われる。
+        let mtime = fs::metadata(&thumb)?.modified()?;
+        sleep(Duration::from_millis(10));
+        assert_eq!(manager.thumbnail(src, 64, ThumbnailFormat::Jpeg)?, thumb);
+        assert_eq!(fs::metadata(&thumb)?.modified()?, mtime);
+
+        // サイズや形式が異なる場合は別のサムネイルとなる。
+        let webp = manager.thumbnail(src, 64, ThumbnailFormat::Webp)?;
+        assert_ne!(webp, thumb);
+        assert_eq!(
+            image::ImageReader::open(&webp)?
+                .with_guessed_format()?
+                .format(),
+            Some(ImageFormat::WebP)
+        );
+        let large = manager.thumbnail(src, 128, ThumbnailFormat::Jpeg)?;
+        assert_ne!(large, thumb);
+
+        // 範囲外のサイズは丸められる。
+        let thumb = manager.thumbnail(src, 1, ThumbnailFormat::Jpeg)?;
+        let img = image::open(&thumb)?;
+        assert_eq!(img.width().max(img.height()), ThumbnailManager::MIN_SIZE);
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail_no_upscale() -> Result<()> {
+        let (root, manager) = setup()?;
+        let src = format!("{root}/small.png");
+        DynamicImage::new_rgba8(10, 20).save(&src)?;
+        let thumb = manager.thumbnail(
+            Path::new(&src),
+            ThumbnailManager::DEFAULT_SIZE,
+            ThumbnailFormat::Webp,
+        )?;
+        assert_eq!(image::open(&thumb)?.dimensions(), (10, 20));
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_thumbnail_error() -> Result<()> {
+        let (root, manager) = setup()?;
+        let size = ThumbnailManager::DEFAULT_SIZE;
+        let format = ThumbnailFormat::Jpeg;
+
+        // 存在しない、または画像ではない。
+        let src = Path::new("./tests/files/nonexistent.jpg");
+        assert!(manager.thumbnail(src, size, format).is_err());
+        let src = Path::new("./tests/files/1 text.txt");
+        assert!(manager.thumbnail(src, size, format).is_err());
+
+        // 失敗した場合は何も残らない。
+        let cache = PathBuf::from(&root).join("cache");
+        assert!(!cache.exists() || fs::read_dir(&cache)?.next().is_none());
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,689 @@
+use crate::{
+    errors::{FsError, TrashError},
+    misc::{Ls, Mover, ProgressJob, numbered_path, remove_entry},
+    models::{ConflictPolicy, TrashItem},
+};
+
+use anyhow::{Error, Result, bail};
+use chrono::{Local, NaiveDateTime};
+use percent_encoding::{
+    AsciiSet, NON_ALPHANUMERIC, percent_decode_str, percent_encode,
+};
+use serde_json::Value;
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{ErrorKind, Write as _},
+    os::unix::{
+        ffi::{OsStrExt as _, OsStringExt as _},
+        fs::{DirBuilderExt as _, MetadataExt as _},
+    },
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// `.trashinfo` の `Path` キーでエスケープしない文字。
+///
+/// RFC 2396 の非予約文字に加えて、パス区切りの `/` はそのまま残す。
+const PATH_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// `.trashinfo` の `DeletionDate` キーの日時フォーマット。
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// freedesktop.org Trash specification に従ってゴミ箱を管理する構造体。
+///
+/// ホームのゴミ箱 (`$XDG_DATA_HOME/Trash`) と同じデバイス上のエントリは
+/// ホームのゴミ箱に、それ以外はボリュームごとのゴミ箱
+/// (`$topdir/.Trash/$uid` または `$topdir/.Trash-$uid`) に移動する。
+///
+/// # Fields
+/// * `home` - ホームのゴミ箱のパス
+///   None の場合、ボリュームごとのゴミ箱のみ利用できる。
+/// * `ls` - エントリ情報の取得に使用する Ls インスタンス
+/// * `time_style` - 日時のフォーマット文字列
+pub struct TrashManager {
+    home: Option<PathBuf>,
+    ls: Ls,
+    time_style: String,
+}
+
+impl TrashManager {
+    /// エラーを TrashError 型に変換する。
+    ///
+    /// # Arguments
+    /// * `err` - 変換元のエラー
+    ///
+    /// # Returns
+    /// 変換後のエラー
+    pub fn to_error(err: Error) -> Error {
+        if err.is::<TrashError>() || err.is::<FsError>() {
+            return err;
+        }
+        TrashError::IO(err.to_string()).into()
+    }
+
+    /// 新しい TrashManager を作成する。
+    ///
+    /// シングルトンとして使用される。
+    ///
+    /// # Arguments
+    /// * `data_home` - `$XDG_DATA_HOME` に相当するディレクトリのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// TrashManager の Arc
+    pub fn new(data_home: Option<PathBuf>, time_style: &str) -> Arc<Self> {
+        Arc::new(Self {
+            home: data_home.map(|p| p.join("Trash")),
+            ls: Ls::new(time_style),
+            time_style: time_style.to_owned(),
+        })
+    }
+
+    /// 環境変数から `$XDG_DATA_HOME` を決定する。
+    ///
+    /// 未設定または相対パスの場合は `$HOME/.local/share` とする。
+    ///
+    /// # Returns
+    /// ディレクトリのパス、`$HOME` も未設定の場合は None
+    pub fn default_data_home() -> Option<PathBuf> {
+        if let Some(p) = env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            && p.is_absolute()
+        {
+            return Some(p);
+        }
+        env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share"))
+    }
+
+    /// ゴミ箱内のアイテムに対する操作を実行する。
+    ///
+    /// - `list`: ゴミ箱内のアイテム一覧を取得
+    /// - `restore`: アイテムを元の場所に復元
+    /// - `purge`: アイテムを完全に削除
+    ///
+    /// エントリをゴミ箱に移動する場合は `put` を使用する。
+    ///
+    /// # Arguments
+    /// * `action` - 実行する操作
+    ///   `&str` 型だが、JSON Schema により検証済みの値が渡される。
+    /// * `ids` - 操作対象のアイテムの識別子
+    ///
+    /// # Returns
+    /// 操作後のアイテム一覧
+    pub fn process(&self, action: &str, ids: &[String]) -> Result<Value> {
+        for id in ids {
+            match action {
+                "restore" => self.restore(id)?,
+                "purge" => self.purge(id)?,
+                _ => {} // 事実上 `list`
+            }
+        }
+        Ok(serde_json::to_value(self.list()?)?)
+    }
+
+    /// エントリをゴミ箱に移動する。
+    ///
+    /// # Arguments
+    /// * `path` - エントリの絶対パス
+    ///
+    /// # Returns
+    /// ゴミ箱内のアイテムの識別子
+    ///
+    /// # Errors
+    /// - `TrashError::NotAvailable`:
+    ///   利用できるゴミ箱がない。
+    /// - `FsError::IntoItself`:
+    ///   ゴミ箱自身、またはゴミ箱を含むディレクトリを移動しようとした。
+    pub fn put(&self, path: &Path) -> Result<String> {
+        let meta = fs::symlink_metadata(path)?;
+        let (trash, origin) = self.trash_for(path, meta.dev())?;
+        if trash.starts_with(path) {
+            bail!(FsError::IntoItself(path.to_string_lossy().to_string()));
+        }
+        let name = Path::new(path.file_name().unwrap_or_default());
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(origin.as_os_str().as_bytes(), PATH_SET),
+            Local::now().format(DATE_FORMAT),
+        );
+
+        // 同名のアイテムと衝突しないよう、`.trashinfo` を排他的に作成して
+        // ゴミ箱内の名前を確保する。
+        let mut n = 0;
+        let (dst, info_path) = loop {
+            let cand = match n {
+                0 => name.to_path_buf(),
+                _ => numbered_path(name, n),
+            };
+            n += 1;
+            let dst = trash.join("files").join(&cand);
+            if fs::symlink_metadata(&dst).is_ok() {
+                continue;
+            }
+            let info_path = info_path(&trash, cand.as_os_str());
+            let res = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path);
+            match res {
+                Ok(mut file) => {
+                    file.write_all(info.as_bytes())?;
+                    break (dst, info_path);
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        if let Err(err) = move_path(path, &dst) {
+            let _ = fs::remove_file(&info_path);
+            return Err(err);
+        }
+        Ok(dst.to_string_lossy().to_string())
+    }
+
+    /// アイテムを元の場所に復元する。
+    ///
+    /// 元の親ディレクトリが存在しない場合は作成する。
+    ///
+    /// # Arguments
+    /// * `id` - アイテムの識別子
+    ///
+    /// # Errors
+    /// - `TrashError::NotFound`:
+    ///   アイテムが見つからない。
+    /// - `TrashError::Exists`:
+    ///   元の場所にエントリがすでに存在する。
+    pub fn restore(&self, id: &str) -> Result<()> {
+        let (trash, name) = self.locate(id)?;
+        let (origin, _) = read_info(&trash, &name)?;
+        if fs::symlink_metadata(&origin).is_ok() {
+            bail!(TrashError::Exists(origin.to_string_lossy().to_string()));
+        }
+        if let Some(parent) = origin.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_path(Path::new(id), &origin)?;
+        fs::remove_file(info_path(&trash, &name))?;
+        Ok(())
+    }
+
+    /// アイテムを完全に削除する。
+    ///
+    /// # Arguments
+    /// * `id` - アイテムの識別子
+    ///
+    /// # Errors
+    /// - `TrashError::NotFound`:
+    ///   アイテムが見つからない。
+    fn purge(&self, id: &str) -> Result<()> {
+        let (trash, name) = self.locate(id)?;
+        remove_entry(Path::new(id))?;
+        fs::remove_file(info_path(&trash, &name))?;
+        Ok(())
+    }
+
+    /// すべてのゴミ箱内のアイテム一覧を取得する。
+    ///
+    /// `.trashinfo` が壊れているアイテムや、実体のないアイテムは除外する。
+    ///
+    /// # Returns
+    /// 削除日時の新しい順に並んだアイテム一覧
+    fn list(&self) -> Result<Vec<TrashItem>> {
+        let mut items = vec![];
+        for trash in self.trash_dirs() {
+            let Ok(entries) = fs::read_dir(trash.join("info")) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name();
+                let Some(name) = file_name
+                    .as_bytes()
+                    .strip_suffix(b".trashinfo")
+                    .map(|n| OsString::from_vec(n.to_vec()))
+                else {
+                    continue;
+                };
+                let path = trash.join("files").join(&name);
+                if fs::symlink_metadata(&path).is_err() {
+                    continue;
+                }
+                let Ok((origin, date)) = read_info(&trash, &name) else {
+                    continue;
+                };
+                let mut entry = self.ls.entry(&path);
+                if let Some(n) = origin.file_name() {
+                    entry.name = n.to_string_lossy().to_string();
+                }
+                let deleted_at =
+                    match NaiveDateTime::parse_from_str(&date, DATE_FORMAT) {
+                        Ok(dt) => dt.format(&self.time_style).to_string(),
+                        Err(_) => date.clone(),
+                    };
+                let item = TrashItem {
+                    entry,
+                    id: path.to_string_lossy().to_string(),
+                    origin: origin.to_string_lossy().to_string(),
+                    deleted_at,
+                };
+                items.push((date, item));
+            }
+        }
+        // ISO 8601 形式のため、文字列のまま比較できる。
+        items.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// エントリの移動先となるゴミ箱を決定する。
+    ///
+    /// ゴミ箱が存在しない場合は作成する。
+    ///
+    /// # Arguments
+    /// * `path` - エントリの絶対パス
+    /// * `dev` - エントリのデバイス番号
+    ///
+    /// # Returns
+    /// ゴミ箱のパスと、`.trashinfo` に記録する元のパスのタプル
+    /// ボリュームごとのゴミ箱の場合、元のパスはトップディレクトリからの相対パス。
+    ///
+    /// # Errors
+    /// - `TrashError::NotAvailable`:
+    ///   利用できるゴミ箱がない。
+    fn trash_for(&self, path: &Path, dev: u64) -> Result<(PathBuf, PathBuf)> {
+        let home = self.home.as_ref().filter(|h| prepare(h).is_ok());
+        if let Some(home) = home
+            && fs::metadata(home)?.dev() == dev
+        {
+            return Ok((home.clone(), path.to_path_buf()));
+        }
+        let topdir = topdir(path, dev);
+        if let Some(trash) = volume_trash(&topdir) {
+            let origin = path.strip_prefix(&topdir).unwrap_or(path);
+            return Ok((trash, origin.to_path_buf()));
+        }
+        // ボリュームごとのゴミ箱が使えない場合は、
+        // デバイスを跨いでホームのゴミ箱に移動する。
+        match home {
+            Some(home) => Ok((home.clone(), path.to_path_buf())),
+            None => bail!(TrashError::NotAvailable),
+        }
+    }
+
+    /// 既存のゴミ箱の一覧を取得する。
+    ///
+    /// ホームのゴミ箱と、マウントされている各ボリュームのゴミ箱を返す。
+    ///
+    /// # Returns
+    /// ゴミ箱のパスの配列
+    fn trash_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![];
+        if let Some(home) = &self.home {
+            dirs.push(home.clone());
+        }
+        let uid = uid();
+        for mount in mount_points() {
+            for trash in [
+                mount.join(".Trash").join(uid.to_string()),
+                mount.join(format!(".Trash-{uid}")),
+            ] {
+                if trash.is_dir() && !dirs.contains(&trash) {
+                    dirs.push(trash);
+                }
+            }
+        }
+        dirs
+    }
+
+    /// アイテムの識別子を検証し、ゴミ箱のパスとゴミ箱内の名前を取得する。
+    ///
+    /// 識別子はゴミ箱の `files` ディレクトリ直下のエントリの絶対パスであり、
+    /// ゴミ箱以外のパスを操作させないよう、その構造を厳密に確認する。
+    ///
+    /// # Arguments
+    /// * `id` - アイテムの識別子
+    ///
+    /// # Returns
+    /// ゴミ箱のパスとゴミ箱内の名前のタプル
+    ///
+    /// # Errors
+    /// - `TrashError::NotFound`:
+    ///   アイテムが見つからない。
+    fn locate(&self, id: &str) -> Result<(PathBuf, OsString)> {
+        let not_found = || TrashError::NotFound(id.to_owned());
+        let path = Path::new(id);
+        let (Some(name), Some(files)) = (path.file_name(), path.parent())
+        else {
+            bail!(not_found());
+        };
+        let Some(trash) = files.parent() else {
+            bail!(not_found());
+        };
+        let uid = uid().to_string();
+        let is_trash = self.home.as_deref() == Some(trash)
+            || trash.file_name() == Some(format!(".Trash-{uid}").as_ref())
+            || (trash.file_name() == Some(uid.as_ref())
+                && trash.parent().and_then(Path::file_name)
+                    == Some(".Trash".as_ref()));
+        if !path.is_absolute()
+            || files.file_name() != Some("files".as_ref())
+            || !is_trash
+            || !info_path(trash, name).is_file()
+            || fs::symlink_metadata(path).is_err()
+        {
+            bail!(not_found());
+        }
+        Ok((trash.to_path_buf(), name.to_owned()))
+    }
+}
+
+/// ゴミ箱内のアイテムに対応する `.trashinfo` のパスを返す。
+///
+/// # Arguments
+/// * `trash` - ゴミ箱のパス
+/// * `name` - ゴミ箱内の名前
+fn info_path(trash: &Path, name: &std::ffi::OsStr) -> PathBuf {
+    let mut file_name = name.to_owned();
+    file_name.push(".trashinfo");
+    trash.join("info").join(file_name)
+}
+
+/// `.trashinfo` を読み込む。
+///
+/// # Arguments
+/// * `trash` - ゴミ箱のパス
+/// * `name` - ゴミ箱内の名前
+///
+/// # Returns
+/// 元の絶対パスと削除日時文字列のタプル
+fn read_info(
+    trash: &Path,
+    name: &std::ffi::OsStr,
+) -> Result<(PathBuf, String)> {
+    let content = fs::read_to_string(info_path(trash, name))?;
+    let mut lines = content.lines();
+    if lines.next().map(str::trim) != Some("[Trash Info]") {
+        bail!("Invalid trashinfo: {}", name.to_string_lossy());
+    }
+    let (mut origin, mut date) = (None, String::new());
+    for line in lines {
+        if let Some(v) = line.strip_prefix("Path=") {
+            let bytes: Vec<u8> = percent_decode_str(v).collect();
+            origin = Some(PathBuf::from(OsString::from_vec(bytes)));
+        } else if let Some(v) = line.strip_prefix("DeletionDate=") {
+            date = v.to_owned();
+        }
+    }
+    let Some(origin) = origin else {
+        bail!("Invalid trashinfo: {}", name.to_string_lossy());
+    };
+    // ボリュームごとのゴミ箱では、トップディレクトリからの相対パスになり得る。
+    let origin = match origin.is_absolute() {
+        true => origin,
+        false => trash_topdir(trash).join(origin),
+    };
+    Ok((origin, date))
+}
+
+/// ボリュームごとのゴミ箱のトップディレクトリを返す。
+///
+/// # Arguments
+/// * `trash` - ゴミ箱のパス
+///   `$topdir/.Trash/$uid` または `$topdir/.Trash-$uid`
+fn trash_topdir(trash: &Path) -> PathBuf {
+    let parent = trash.parent().unwrap_or(trash);
+    if parent.file_name() == Some(".Trash".as_ref()) {
+        return parent.parent().unwrap_or(parent).to_path_buf();
+    }
+    parent.to_path_buf()
+}
+
+/// エントリを移動する。
+///
+/// 同じデバイス上であれば `rename` で移動し、そうでなければコピーして削除する。
+///
+/// # Arguments
+/// * `src` - 移動元のパス
+/// * `dst` - 移動先のパス (存在しないこと)
+fn move_path(src: &Path, dst: &Path) -> Result<()> {
+    let job = ProgressJob::new();
+    Mover::new(ConflictPolicy::Skip, &job).move_entry(src, dst)?;
+    Ok(())
+}
+
+/// ゴミ箱の `files` と `info` ディレクトリを用意する。
+///
+/// # Arguments
+/// * `trash` - ゴミ箱のパス
+fn prepare(trash: &Path) -> Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(trash.join("files"))?;
+    builder.create(trash.join("info"))?;
+    Ok(())
+}
+
+/// エントリが属するボリュームのトップディレクトリ (マウントポイント) を返す。
+///
+/// # Arguments
+/// * `path` - エントリの絶対パス
+/// * `dev` - エントリのデバイス番号
+fn topdir(path: &Path, dev: u64) -> PathBuf {
+    let start = path.parent().unwrap_or(path);
+    let mut cur = fs::canonicalize(start).unwrap_or(start.to_path_buf());
+    while let Some(parent) = cur.parent() {
+        match fs::metadata(parent) {
+            Ok(meta) if meta.dev() == dev => cur = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    cur
+}
+
+/// ボリュームごとのゴミ箱を用意する。
+///
+/// 管理者が用意した `$topdir/.Trash` (スティッキービット付き) があれば
+/// その配下の `$uid` を、なければ `$topdir/.Trash-$uid` を使用する。
+///
+/// # Arguments
+/// * `topdir` - ボリュームのトップディレクトリ
+///
+/// # Returns
+/// ゴミ箱のパス、利用できない場合は None
+fn volume_trash(topdir: &Path) -> Option<PathBuf> {
+    let uid = uid();
+    let shared = topdir.join(".Trash");
+    if let Ok(meta) = fs::symlink_metadata(&shared)
+        && meta.is_dir()
+        && meta.mode() & 0o1000 != 0
+    {
+        let trash = shared.join(uid.to_string());
+        if prepare(&trash).is_ok() {
+            return Some(trash);
+        }
+    }
+    let trash = topdir.join(format!(".Trash-{uid}"));
+    prepare(&trash).ok()?;
+    // シンボリックリンクや他人のディレクトリは使用しない。
+    let meta = fs::symlink_metadata(&trash).ok()?;
+    (meta.is_dir() && meta.uid() == uid).then_some(trash)
+}
+
+/// マウントポイントの一覧を取得する。
+///
+/// `/proc/self/mounts` を読めない環境では空になる。
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string("/proc/self/mounts") else {
+        return vec![];
+    };
+    content
+        .lines()
+        .filter_map(|line| line.split(' ').nth(1))
+        .map(|p| PathBuf::from(unescape_mount_point(p)))
+        .collect()
+}
+
+/// `/proc/self/mounts` の 8 進数エスケープ (`\040` など) を元に戻す。
+///
+/// # Arguments
+/// * `s` - エスケープされたパス
+fn unescape_mount_point(s: &str) -> OsString {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(oct) = s.get(i + 1..i + 4)
+            && let Ok(b) = u8::from_str_radix(oct, 8)
+        {
+            res.push(b);
+            i += 4;
+            continue;
+        }
+        res.push(bytes[i]);
+        i += 1;
+    }
+    OsString::from_vec(res)
+}
+
+/// 実行ユーザーの UID を返す。
+fn uid() -> u32 {
+    // SAFETY: getuid は常に成功し、副作用もない。
+    unsafe { libc::getuid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, setup_resources, teardown_resources,
+    };
+
+    use super::*;
+
+    async fn setup() -> Result<(String, Arc<TrashManager>)> {
+        let path = setup_resources("test").await?;
+        let data_home = PathBuf::from(format!("{path}/data"));
+        let manager = TrashManager::new(Some(data_home), "%y/%m/%d %H:%M:%S");
+        Ok((path, manager))
+    }
+
+    fn put(manager: &TrashManager, path: &str) -> Result<Vec<TrashItem>> {
+        manager.put(Path::new(path))?;
+        manager.list()
+    }
+
+    #[tokio::test]
+    async fn test_put() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = put(&manager, &format!("{path}/test.txt"))?;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].entry.name, "test.txt");
+        assert_eq!(items[0].entry.size, "4");
+        assert_eq!(items[0].origin, format!("{path}/test.txt"));
+        assert_eq!(items[0].id, format!("{path}/data/Trash/files/test.txt"));
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        let info = fs::read_to_string(format!(
+            "{path}/data/Trash/info/test.txt.trashinfo"
+        ))?;
+        assert!(info.starts_with("[Trash Info]\nPath=/"));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_put_same_name() -> Result<()> {
+        let (path, manager) = setup().await?;
+        put(&manager, &format!("{path}/test.txt"))?;
+        fs::write(format!("{path}/test.txt"), "")?;
+        let items = put(&manager, &format!("{path}/test.txt"))?;
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| i.entry.name == "test.txt"));
+        let ids: Vec<_> = items.iter().map(|i| i.id.clone()).collect();
+        assert!(ids.contains(&format!("{path}/data/Trash/files/test (1).txt")));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_put_into_itself() -> Result<()> {
+        let (path, manager) = setup().await?;
+        fs::create_dir(format!("{path}/data"))?;
+        let res = manager.put(Path::new(&format!("{path}/data")));
+        assert_err(&res.unwrap_err(), &FsError::IntoItself("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = put(&manager, &format!("{path}/test1"))?;
+        manager.process("restore", &[items[0].id.clone()])?;
+        assert!(Path::new(&format!("{path}/test1/test1.txt")).is_file());
+        assert!(manager.list()?.is_empty());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_restore_exists() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = put(&manager, &format!("{path}/test.txt"))?;
+        fs::write(format!("{path}/test.txt"), "")?;
+        let res = manager.process("restore", &[items[0].id.clone()]);
+        assert_err(&res.unwrap_err(), &TrashError::Exists("".to_owned()));
+        assert_eq!(manager.list()?.len(), 1);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let items = put(&manager, &format!("{path}/test1"))?;
+        let data = manager.process("purge", &[items[0].id.clone()])?;
+        assert_eq!(data, serde_json::json!([]));
+        assert!(!Path::new(&items[0].id).exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_locate_error() -> Result<()> {
+        let (path, manager) = setup().await?;
+        let err = TrashError::NotFound("".to_owned());
+        let res = manager.process("purge", &[format!("{path}/test.txt")]);
+        assert_err(&res.unwrap_err(), &err);
+        let id = format!("{path}/data/Trash/files/../../../test.txt");
+        let res = manager.process("purge", &[id]);
+        assert_err(&res.unwrap_err(), &err);
+        assert!(Path::new(&format!("{path}/test.txt")).exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_volume_trash() -> Result<()> {
+        let (path, _) = setup().await?;
+        let topdir = Path::new(&path);
+        let trash = volume_trash(topdir).unwrap();
+        assert_eq!(trash, topdir.join(format!(".Trash-{}", uid())));
+        assert!(trash.join("files").is_dir());
+        assert_eq!(trash_topdir(&trash), topdir);
+        let shared = trash_topdir(&topdir.join(".Trash/1000"));
+        assert_eq!(shared, topdir);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unescape_mount_point() {
+        let res = unescape_mount_point(r"/mnt/my\040disk\134x");
+        assert_eq!(res, OsString::from(r"/mnt/my disk\x"));
+    }
+}
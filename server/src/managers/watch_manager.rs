@@ -43,6 +43,19 @@ pub trait WatchManagerTrait: Send + Sync {
     /// # Arguments
     /// * `arg` - タスク引数
     async fn remove_subscriber(&self, arg: &Arc<TaskArg>);
+
+    /// 監視中のディレクトリの最新情報を返す。
+    ///
+    /// # Arguments
+    /// * `path` - 監視中のパス
+    ///
+    /// # Returns
+    /// 最新のディレクトリ情報
+    ///
+    /// # Errors
+    /// - `WatchError::NotWatched`:
+    ///   指定されたパスは監視されていない。
+    async fn data(&self, path: &str) -> Result<Value>;
 }
 
 #[async_trait]
@@ -80,6 +93,13 @@ impl WatchManagerTrait for WatchManager {
             }
         }
     }
+
+    async fn data(&self, path: &str) -> Result<Value> {
+        match self.watches.get(path) {
+            Some(info) => Ok(info.lock().await.data()),
+            None => Err(WatchError::NotWatched(path.to_owned()).into()),
+        }
+    }
 }
 
 /// ディレクトリ監視を管理する構造体。
@@ -177,8 +197,8 @@ impl WatchManager {
 #[cfg(test)]
 mod tests {
     use crate::test_helpers::{
-        DirInfo, setup_resources, setup_sender, setup_task_arg, sleep,
-        teardown_resources,
+        DirInfo, assert_err, setup_resources, setup_sender, setup_task_arg,
+        sleep, teardown_resources,
     };
 
     use super::*;
@@ -206,6 +226,21 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_watch_manager_data() -> Result<()> {
+        let (path, manager, task_arg) = setup().await?;
+        let new_path = format!("{path}/test1");
+        let mut manager = manager.lock().await;
+        manager.watch("a", &new_path, &task_arg).await?;
+        let data = manager.data(&new_path).await?;
+        let dir_info = serde_json::from_value::<DirInfo>(data)?;
+        assert_eq!(dir_info.entries[1].name, "test1.txt");
+        let res = manager.data(&path).await;
+        assert_err(&res.unwrap_err(), &WatchError::NotWatched("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_watch_manager_unwatch() -> Result<()> {
         let (path, manager, task_arg) = setup().await?;
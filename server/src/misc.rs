@@ -1,16 +1,48 @@
 //! 種種雑多な機能を提供するモジュール。
 
+mod archive_summary;
+mod archive_writer;
 mod command;
+mod copier;
+mod deleter;
+mod dir_summary;
+mod extractor;
 mod frame_set;
+mod grepper;
+mod highlighter;
 mod ls;
+mod mover;
+mod notifier;
+mod packer;
+mod progress_job;
+mod renamer;
+mod searcher;
 mod sender;
+mod virtual_dir;
 mod watch;
 mod watch_info;
 
+pub use archive_summary::ArchiveSummary;
+pub use archive_writer::ArchiveWriter;
 pub use command::{CmdArgsType, Command};
+pub use copier::{
+    Copier, is_replacement, numbered_path, replace_entry, resolve_conflict,
+};
+pub use deleter::Deleter;
+pub use dir_summary::DirSummary;
+pub use extractor::Extractor;
 pub use frame_set::FrameSet;
+pub use grepper::Grepper;
+pub use highlighter::Highlighter;
 pub use ls::Ls;
+pub use mover::{Mover, remove_entry};
+pub use notifier::Notifier;
+pub use packer::Packer;
+pub use progress_job::{ProgressJob, ProgressReader};
+pub use renamer::{RenameRule, Renamer};
+pub use searcher::{SearchQuery, Searcher};
 pub use sender::{Sender, SenderTrait};
+pub use virtual_dir::VirtualDir;
 pub use watch::Watch;
 pub use watch_info::WatchInfo;
 
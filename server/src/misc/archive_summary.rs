@@ -0,0 +1,183 @@
+use crate::{helpers::ls_style_size, traits::Archive};
+
+use anyhow::Result;
+use html_escape::encode_text;
+use std::collections::{BTreeSet, HashSet};
+
+/// 一覧に表示するトップレベルのエントリ数の上限。
+const MAX_LISTING: usize = 20;
+
+/// アーカイブの概要をプレビューするための構造体。
+///
+/// 展開せずに、エントリ一覧だけから集計する。
+/// ディレクトリエントリを持たないアーカイブでも、
+/// パスに含まれるディレクトリを数える。
+///
+/// # Fields
+/// * `files` - ファイルの数
+/// * `dirs` - ディレクトリの数
+/// * `size` - ファイルの展開後の合計サイズ (バイト)
+/// * `archive_size` - アーカイブファイルのサイズ (バイト)
+/// * `listing` - トップレベルのエントリ名の一覧
+///   ディレクトリには末尾に `/` が付く。
+pub struct ArchiveSummary {
+    files: u64,
+    dirs: u64,
+    size: u64,
+    archive_size: u64,
+    listing: Vec<String>,
+}
+
+impl ArchiveSummary {
+    /// アーカイブのエントリ一覧から概要を作成する。
+    ///
+    /// # Arguments
+    /// * `archive` - Archive インスタンス
+    /// * `archive_size` - アーカイブファイルのサイズ (バイト)
+    pub fn new(
+        archive: &mut Box<dyn Archive>,
+        archive_size: u64,
+    ) -> Result<Self> {
+        let mut files = 0;
+        let mut size = 0;
+        let mut dirs = HashSet::new();
+        let mut top = BTreeSet::new();
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = entry.path();
+            let (path, is_dir) = match path.strip_suffix('/') {
+                Some(p) => (p, true),
+                None => (path.as_str(), false),
+            };
+            // tar のルート (./) などは対象外。
+            if path.is_empty() {
+                continue;
+            }
+            if !is_dir {
+                files += 1;
+                size += entry.size();
+            }
+            // パスに含まれる親ディレクトリも数える。
+            let mut parents = path.match_indices('/').map(|(i, _)| &path[..i]);
+            dirs.extend(parents.by_ref().map(str::to_owned));
+            if is_dir {
+                dirs.insert(path.to_owned());
+            }
+            match path.split_once('/') {
+                Some((name, _)) => top.insert(format!("{name}/")),
+                None if is_dir => top.insert(format!("{path}/")),
+                None => top.insert(path.to_owned()),
+            };
+        }
+        Ok(Self {
+            files,
+            dirs: dirs.len() as u64,
+            size,
+            archive_size,
+            listing: top.into_iter().collect(),
+        })
+    }
+
+    /// 概要を HTML に変換する。
+    ///
+    /// プレビュー用 HTML テンプレートは `white-space: pre-wrap` のため、
+    /// タグの間に改行を入れない。
+    ///
+    /// # Returns
+    /// HTML エスケープ済みの HTML
+    pub fn html(&self) -> String {
+        // 圧縮率は、展開後のサイズに対するアーカイブファイルのサイズの割合。
+        let ratio = match self.size {
+            0 => "-".to_owned(),
+            n => format!("{:.1}%", self.archive_size as f64 / n as f64 * 100.0),
+        };
+        let rows = [
+            ("Files", self.files.to_string()),
+            ("Directories", self.dirs.to_string()),
+            ("Total size", ls_style_size(self.size)),
+            ("Archive size", ls_style_size(self.archive_size)),
+            ("Compression ratio", ratio),
+        ];
+        let rows: String = rows
+            .iter()
+            .map(|(k, v)| format!("<tr><th>{k}</th><td>{v}</td></tr>"))
+            .collect();
+
+        let mut items: String = self
+            .listing
+            .iter()
+            .take(MAX_LISTING)
+            .map(|name| format!("<li>{}</li>", encode_text(name)))
+            .collect();
+        if self.listing.len() > MAX_LISTING {
+            let rest = self.listing.len() - MAX_LISTING;
+            items += &format!("<li>... and {rest} more</li>");
+        }
+
+        format!(
+            r#"<table class="previewWindow_summary">{rows}</table><ul class="previewWindow_listing">{items}</ul>"#
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{misc::VirtualDir, models::ArchiveKind};
+
+    use unicode_normalization::UnicodeNormalization as _;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    #[test]
+    fn test_archive_summary() -> Result<()> {
+        // ディレクトリエントリの有無にかかわらず、同じ集計になる。
+        let archives = [
+            (ArchiveKind::Zip, "./tests/archives/dir-entries.zip"),
+            (ArchiveKind::Zip, "./tests/archives/no-dir-entries.zip"),
+            (ArchiveKind::Tar, "./tests/archives/dir-entries.tar"),
+            (ArchiveKind::Tgz, "./tests/archives/no-dir-entries.tgz"),
+        ];
+        let mut listings = Vec::new();
+        for (kind, path) in archives {
+            let mut archive = VirtualDir::open(&kind, path, "%y")?;
+            let summary = ArchiveSummary::new(&mut archive, 100)?;
+            assert_eq!(summary.dirs, 9);
+            assert_eq!(summary.archive_size, 100);
+            listings.push(summary.listing);
+        }
+        assert_eq!(listings[0], listings[1]);
+        assert_eq!(listings[2], listings[3]);
+        assert_eq!(
+            listings[0],
+            [
+                ".hidden_file".to_owned(),
+                "1 text.txt".to_owned(),
+                nfc("1 ゲーム/"),
+                "2 image.jpg".to_owned(),
+                "2 movies/".to_owned(),
+                "3 blank/".to_owned(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_summary_html() -> Result<()> {
+        let path = "./tests/archives/nested.tgz";
+        let mut archive = VirtualDir::open(&ArchiveKind::Tgz, path, "%y")?;
+        let summary = ArchiveSummary::new(&mut archive, 0)?;
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.dirs, 1);
+        assert_eq!(summary.listing, ["dist/"]);
+
+        let html = summary.html();
+        assert!(html.contains("<tr><th>Files</th><td>2</td></tr>"));
+        assert!(html.contains("<tr><th>Compression ratio</th><td>0.0%</td>"));
+        assert!(html.contains("<li>dist/</li>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,599 @@
+use crate::{
+    errors::VirtualDirError,
+    helpers::{decode_string, temp_path},
+    misc::{Packer, ProgressJob},
+    models::ArchiveKind,
+};
+
+use anyhow::Result;
+use bzip2::{read::BzDecoder, write::BzEncoder};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use std::{
+    ffi::{OsStr, OsString},
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    os::unix::ffi::{OsStrExt as _, OsStringExt as _},
+    path::{Path, PathBuf},
+};
+use unicode_normalization::UnicodeNormalization as _;
+use xz2::{read::XzDecoder, write::XzEncoder};
+use zip::{ZipArchive, ZipWriter};
+use zstd::stream::{
+    read::Decoder as ZstdDecoder, write::Encoder as ZstdEncoder,
+};
+
+/// アーカイブに追加するエントリ。
+///
+/// # Fields
+/// * `src` - 追加する実ファイルのパス
+/// * `name` - アーカイブ内のパス
+struct Addition {
+    src: PathBuf,
+    name: String,
+}
+
+/// アーカイブの内容を書き換える構造体。
+///
+/// アーカイブは同じディレクトリに作成した一時ファイルに書き出され、
+/// 成功した場合に限り rename で元のファイルと置き換えられる。
+/// そのため、途中で失敗しても元のアーカイブが壊れることはない。
+///
+/// アーカイブ内のパスは、先頭のスラッシュと `./` を含まない NFC の文字列で
+/// 指定する。ディレクトリの場合、その配下のエントリもすべて対象になる。
+///
+/// # Fields
+/// * `kind` - アーカイブの種類
+/// * `archive` - アーカイブファイルのパス
+pub struct ArchiveWriter {
+    kind: ArchiveKind,
+    archive: String,
+}
+
+impl ArchiveWriter {
+    /// 新しい ArchiveWriter インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    pub fn new(kind: ArchiveKind, archive: &str) -> Self {
+        Self {
+            kind,
+            archive: archive.to_owned(),
+        }
+    }
+
+    /// アーカイブ内の全エントリのパスを取得する。
+    ///
+    /// # Returns
+    /// 正規化されたパスの配列 (ディレクトリエントリは末尾スラッシュ付き)
+    pub fn names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        match self.kind {
+            ArchiveKind::Zip => {
+                let file = File::open(&self.archive)?;
+                let mut zip = ZipArchive::new(BufReader::new(file))?;
+                for i in 0..zip.len() {
+                    let entry = zip.by_index_raw(i)?;
+                    names.push(entry_name(entry.name_raw()));
+                }
+            }
+            // 7z アーカイブと ISO イメージの書き換えには対応していない。
+            ArchiveKind::SevenZip | ArchiveKind::Iso => {
+                return Err(VirtualDirError::UnsupportedArchive.into());
+            }
+            _ => {
+                let mut tar = self.open_tar()?;
+                for entry in tar.entries()? {
+                    names.push(entry_name(&entry?.path_bytes()));
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// アーカイブ内に指定したパスのエントリが存在するか否かを確認する。
+    ///
+    /// ディレクトリエントリを含まないアーカイブの場合、
+    /// 配下にエントリがあればディレクトリが存在するとみなす。
+    ///
+    /// # Arguments
+    /// * `names` - `names` メソッドで取得したパスの配列
+    /// * `path` - 確認するパス
+    pub fn contains(names: &[String], path: &str) -> bool {
+        names.iter().any(|n| is_under(n, path))
+    }
+
+    /// 実ファイルをアーカイブ内のディレクトリに追加する。
+    ///
+    /// ディレクトリは配下のエントリごと追加される。
+    /// シンボリックリンクは辿らずにリンクとして追加する。
+    /// アーカイブ内に同名のエントリがある場合は上書きせずにスキップする。
+    ///
+    /// # Arguments
+    /// * `srcs` - 追加する実ファイルのパスの配列
+    /// * `dir` - 追加先となるアーカイブ内のディレクトリ (ルートは空文字列)
+    ///
+    /// # Returns
+    /// スキップされた実ファイルのパスの配列
+    pub fn add(&self, srcs: &[String], dir: &str) -> Result<Vec<String>> {
+        let names = self.names()?;
+        let mut skipped = Vec::new();
+        let mut additions: Vec<Addition> = Vec::new();
+        for src in srcs {
+            let path = Path::new(src);
+            let Some(name) = path.file_name() else {
+                return Err(VirtualDirError::Args.into());
+            };
+            let name = name.to_string_lossy().nfc().to_string();
+            let name = if dir.is_empty() {
+                name
+            } else {
+                format!("{dir}/{name}")
+            };
+            if Self::contains(&names, &name)
+                || additions.iter().any(|a| a.name == name)
+            {
+                skipped.push(src.to_owned());
+                continue;
+            }
+            additions.push(Addition {
+                src: path.to_owned(),
+                name,
+            });
+        }
+        if !additions.is_empty() {
+            self.rewrite(|name| Some(name.to_owned()), &additions)?;
+        }
+        Ok(skipped)
+    }
+
+    /// アーカイブからエントリを削除する。
+    ///
+    /// # Arguments
+    /// * `paths` - 削除するエントリのパスの配列
+    ///
+    /// # Errors
+    /// - `VirtualDirError::NotFound`:
+    ///   アーカイブ内にエントリが存在しない。
+    pub fn delete(&self, paths: &[String]) -> Result<()> {
+        let names = self.names()?;
+        if let Some(p) = paths.iter().find(|p| !Self::contains(&names, p)) {
+            return Err(VirtualDirError::NotFound(p.to_owned()).into());
+        }
+        self.rewrite(
+            |name| {
+                if paths.iter().any(|p| is_under(name, p)) {
+                    None
+                } else {
+                    Some(name.to_owned())
+                }
+            },
+            &[],
+        )
+    }
+
+    /// アーカイブ内のエントリの名前を変更する。
+    ///
+    /// # Arguments
+    /// * `from` - 変更するエントリのパス
+    /// * `to` - 変更後のパス
+    ///
+    /// # Errors
+    /// - `VirtualDirError::NotFound`:
+    ///   アーカイブ内に `from` が存在しない。
+    /// - `VirtualDirError::AlreadyExists`:
+    ///   アーカイブ内に `to` がすでに存在する。
+    pub fn rename(&self, from: &str, to: &str) -> Result<()> {
+        let names = self.names()?;
+        if !Self::contains(&names, from) {
+            return Err(VirtualDirError::NotFound(from.to_owned()).into());
+        }
+        if Self::contains(&names, to) {
+            return Err(VirtualDirError::AlreadyExists(to.to_owned()).into());
+        }
+        self.rewrite(
+            |name| {
+                // 配下のエントリやディレクトリエントリの末尾スラッシュは維持する。
+                // foo/ -> bar/, foo/baz.txt -> bar/baz.txt
+                if is_under(name, from) {
+                    Some(format!("{to}{}", &name[from.len()..]))
+                } else {
+                    Some(name.to_owned())
+                }
+            },
+            &[],
+        )
+    }
+
+    /// アーカイブを書き換える。
+    ///
+    /// 既存のエントリは `map` の結果に応じてコピー、名前変更、削除され、
+    /// その後ろに `additions` が追加される。
+    ///
+    /// # Arguments
+    /// * `map` - 既存エントリのパスを受け取り、書き出すパスを返す関数
+    ///   None を返した場合、そのエントリは削除される。
+    /// * `additions` - 追加するエントリの配列
+    fn rewrite<F>(&self, map: F, additions: &[Addition]) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let archive = Path::new(&self.archive);
+        let tmp = temp_path(archive);
+        let res = match self.kind {
+            ArchiveKind::SevenZip | ArchiveKind::Iso => {
+                return Err(VirtualDirError::UnsupportedArchive.into());
+            }
+            ArchiveKind::Zip => self.rewrite_zip(&tmp, &map, additions),
+            ArchiveKind::Tar => {
+                let out = BufWriter::new(File::create(&tmp)?);
+                self.rewrite_tar(out, &map, additions).and_then(|out| {
+                    out.into_inner()?.sync_all()?;
+                    Ok(())
+                })
+            }
+            ArchiveKind::Tgz => {
+                let out = BufWriter::new(File::create(&tmp)?);
+                let out = GzEncoder::new(out, Compression::default());
+                self.rewrite_tar(out, &map, additions).and_then(|out| {
+                    out.finish()?.into_inner()?.sync_all()?;
+                    Ok(())
+                })
+            }
+            ArchiveKind::Txz => {
+                let out = BufWriter::new(File::create(&tmp)?);
+                let out = XzEncoder::new(out, 6);
+                self.rewrite_tar(out, &map, additions).and_then(|out| {
+                    out.finish()?.into_inner()?.sync_all()?;
+                    Ok(())
+                })
+            }
+            ArchiveKind::Tbz => {
+                let out = BufWriter::new(File::create(&tmp)?);
+                let out = BzEncoder::new(out, bzip2::Compression::default());
+                self.rewrite_tar(out, &map, additions).and_then(|out| {
+                    out.finish()?.into_inner()?.sync_all()?;
+                    Ok(())
+                })
+            }
+            ArchiveKind::Tzst => {
+                let out = BufWriter::new(File::create(&tmp)?);
+                let out = ZstdEncoder::new(out, 0)?;
+                self.rewrite_tar(out, &map, additions).and_then(|out| {
+                    out.finish()?.into_inner()?.sync_all()?;
+                    Ok(())
+                })
+            }
+        };
+        let res = res.and_then(|_| {
+            let perm = fs::metadata(archive)?.permissions();
+            fs::set_permissions(&tmp, perm)?;
+            fs::rename(&tmp, archive)?;
+            Ok(())
+        });
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        res
+    }
+
+    /// zip アーカイブを書き換える。
+    ///
+    /// 既存のエントリは再圧縮せずにそのままコピーする。
+    ///
+    /// # Arguments
+    /// * `tmp` - 書き出し先の一時ファイルのパス
+    /// * `map` - 既存エントリのパスを受け取り、書き出すパスを返す関数
+    /// * `additions` - 追加するエントリの配列
+    fn rewrite_zip<F>(
+        &self,
+        tmp: &Path,
+        map: &F,
+        additions: &[Addition],
+    ) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let file = File::open(&self.archive)?;
+        let mut zip = ZipArchive::new(BufReader::new(file))?;
+        let mut out = ZipWriter::new(BufWriter::new(File::create(tmp)?));
+        out.set_raw_comment(zip.comment().into());
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i)?;
+            let name = entry_name(entry.name_raw());
+            match map(&name) {
+                None => continue,
+                // 名前が変わらないエントリは、生のパス (バイト列) を維持する。
+                Some(new) if new == name => out.raw_copy_file(entry)?,
+                Some(new) => out.raw_copy_file_rename(entry, new)?,
+            }
+        }
+        let job = ProgressJob::new();
+        let packer = Packer::new(ArchiveKind::Zip, None, &job);
+        for Addition { src, name } in additions {
+            packer.append_zip(&mut out, src, name)?;
+        }
+        out.finish()?.into_inner()?.sync_all()?;
+        Ok(())
+    }
+
+    /// tar/tgz アーカイブを書き換える。
+    ///
+    /// # Arguments
+    /// * `out` - 書き出し先
+    /// * `map` - 既存エントリのパスを受け取り、書き出すパスを返す関数
+    /// * `additions` - 追加するエントリの配列
+    ///
+    /// # Returns
+    /// 書き出しを終えた `out`
+    fn rewrite_tar<W: Write, F>(
+        &self,
+        out: W,
+        map: &F,
+        additions: &[Addition],
+    ) -> Result<W>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut tar = self.open_tar()?;
+        let mut builder = tar::Builder::new(out);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let raw = entry.path_bytes().into_owned();
+            let name = entry_name(&raw);
+            let Some(new) = map(&name) else {
+                continue;
+            };
+            // 名前が変わらないエントリは、生のパス (バイト列) を維持する。
+            let path = if new == name {
+                PathBuf::from(OsString::from_vec(raw))
+            } else {
+                PathBuf::from(new)
+            };
+            // PAX 拡張ヘッダ (高精度の更新日時や xattr など) を引き継ぐ。
+            // パスとリンク先は名前の変更を反映して書き直すため除く。
+            let pax: Vec<(String, Vec<u8>)> = entry
+                .pax_extensions()?
+                .into_iter()
+                .flatten()
+                .filter_map(|ext| {
+                    let ext = ext.ok()?;
+                    let key = ext.key().ok()?;
+                    let keep = !matches!(key, "path" | "linkpath");
+                    keep.then(|| (key.to_owned(), ext.value_bytes().to_vec()))
+                })
+                .collect();
+            builder.append_pax_extensions(
+                pax.iter().map(|(k, v)| (k.as_str(), v.as_slice())),
+            )?;
+            // 長いパスは GNU 拡張ヘッダとして書き直される。
+            let mut header = entry.header().clone();
+            let kind = header.entry_type();
+            match entry.link_name_bytes() {
+                Some(link) if kind.is_symlink() || kind.is_hard_link() => {
+                    let link = link.into_owned();
+                    // ハードリンクはアーカイブ内のパスを指すため、
+                    // リンク先の名前変更に追従させる。
+                    let target = match map(&entry_name(&link)) {
+                        Some(new) if kind.is_hard_link() => PathBuf::from(new),
+                        _ => PathBuf::from(OsStr::from_bytes(&link)),
+                    };
+                    builder.append_link(&mut header, path, target)?;
+                }
+                _ => builder.append_data(&mut header, path, &mut entry)?,
+            }
+        }
+        let job = ProgressJob::new();
+        let packer = Packer::new(self.kind, None, &job);
+        for Addition { src, name } in additions {
+            packer.append_tar(&mut builder, src, name)?;
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// tar/tgz アーカイブを読み込み用に開く。
+    fn open_tar(&self) -> Result<tar::Archive<Box<dyn Read>>> {
+        let file = BufReader::new(File::open(&self.archive)?);
+        let reader: Box<dyn Read> = match self.kind {
+            ArchiveKind::Tgz => Box::new(GzDecoder::new(file)),
+            ArchiveKind::Txz => Box::new(XzDecoder::new(file)),
+            ArchiveKind::Tbz => Box::new(BzDecoder::new(file)),
+            ArchiveKind::Tzst => Box::new(ZstdDecoder::with_buffer(file)?),
+            _ => Box::new(file),
+        };
+        Ok(tar::Archive::new(reader))
+    }
+}
+
+/// アーカイブ内のパスのバイト列を、比較用の文字列に正規化する。
+///
+/// # Arguments
+/// * `raw` - パスのバイト列
+fn entry_name(raw: &[u8]) -> String {
+    let s = decode_string(raw);
+    let s = s.strip_prefix("./").unwrap_or(&s);
+    s.nfc().to_string()
+}
+
+/// `name` が `path` 自身か、その配下にあるか否かを判定する。
+///
+/// # Arguments
+/// * `name` - 判定するエントリのパス
+/// * `path` - 基準となるパス
+fn is_under(name: &str, path: &str) -> bool {
+    name.strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::VirtualDirError,
+        test_helpers::{assert_err, setup_resources, teardown_resources},
+    };
+
+    use unicode_normalization::UnicodeNormalization as _;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    fn setup(path: &str, name: &str) -> Result<ArchiveWriter> {
+        let archive = format!("{path}/{name}");
+        fs::copy(format!("./tests/archives/{name}"), &archive)?;
+        let kind = Path::new(name).extension().unwrap().to_string_lossy();
+        let kind = ArchiveKind::from_str(&kind)?;
+        Ok(ArchiveWriter::new(kind, &archive))
+    }
+
+    #[tokio::test]
+    async fn test_add() -> Result<()> {
+        for name in ["dir-entries.zip", "no-dir-entries.tar", "dir-entries.tgz"]
+        {
+            let path = setup_resources("test").await?;
+            let writer = setup(&path, name)?;
+            let srcs = [
+                format!("{path}/test1"),
+                format!("{path}/test.txt"),
+                format!("{path}/test1.txt's link"),
+            ];
+            let dir = nfc("1 ゲーム");
+            let skipped = writer.add(&srcs, &dir)?;
+            assert!(skipped.is_empty());
+            let names = writer.names()?;
+            for p in ["test1/test1.txt", "test.txt", "test1.txt's link"] {
+                assert!(ArchiveWriter::contains(&names, &format!("{dir}/{p}")));
+            }
+            // 既存のエントリはスキップされる。
+            let srcs = [format!("{path}/test.txt")];
+            assert_eq!(writer.add(&srcs, &dir)?, srcs);
+            assert_eq!(writer.names()?.len(), names.len());
+            teardown_resources(&path).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_rename() -> Result<()> {
+        for name in ["no-dir-entries.zip", "dir-entries.tar", "dir-entries.tgz"]
+        {
+            let path = setup_resources("test").await?;
+            let writer = setup(&path, name)?;
+            let dir = nfc("1 ゲーム");
+            writer.delete(&[dir.clone(), "1 text.txt".to_owned()])?;
+            let names = writer.names()?;
+            assert!(!ArchiveWriter::contains(&names, &dir));
+            assert!(!ArchiveWriter::contains(&names, "1 text.txt"));
+            assert!(ArchiveWriter::contains(&names, "2 image.jpg"));
+
+            writer.rename("2 movies", "3 videos")?;
+            let names = writer.names()?;
+            assert!(!ArchiveWriter::contains(&names, "2 movies"));
+            assert!(ArchiveWriter::contains(
+                &names,
+                "3 videos/1 action/1 text.txt"
+            ));
+            // 名前を変更したエントリの内容も読み出せる。
+            let out = format!("{path}/out");
+            match writer.kind {
+                ArchiveKind::Zip => {
+                    let file = File::open(&writer.archive)?;
+                    ZipArchive::new(file)?.extract(&out)?;
+                }
+                _ => writer.open_tar()?.unpack(&out)?,
+            }
+            let text = format!("{out}/3 videos/1 action/1 text.txt");
+            assert!(!fs::read_to_string(text)?.is_empty());
+            let leftovers = fs::read_dir(&path)?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+                .count();
+            assert_eq!(leftovers, 0);
+            teardown_resources(&path).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_tar_long_names() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let archive = format!("{path}/long.tar");
+        let long = format!("dir/{}.txt", "a".repeat(150));
+        let mut builder = tar::Builder::new(File::create(&archive)?);
+        builder.append_pax_extensions([
+            ("mtime", b"1234567890.5".as_slice()),
+            ("SCHILY.xattr.user.foo", b"bar".as_slice()),
+        ])?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        builder.append_data(&mut header, &long, b"long".as_slice())?;
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "link", &long)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        builder.append_data(&mut header, "x", [].as_slice())?;
+        builder.finish()?;
+        drop(builder);
+
+        let writer = ArchiveWriter::new(ArchiveKind::Tar, &archive);
+        writer.delete(&["x".to_owned()])?;
+        writer.rename("dir", "renamed")?;
+        let renamed = long.replacen("dir", "renamed", 1);
+        let mut tar = writer.open_tar()?;
+        let mut found = (false, false);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let name = entry_name(&entry.path_bytes());
+            if name == renamed {
+                let exts: Vec<_> = entry
+                    .pax_extensions()?
+                    .unwrap()
+                    .map(|e| Ok(e?.key()?.to_owned()))
+                    .collect::<Result<_>>()?;
+                assert!(exts.contains(&"mtime".to_owned()));
+                assert!(exts.contains(&"SCHILY.xattr.user.foo".to_owned()));
+                let mut text = String::new();
+                entry.read_to_string(&mut text)?;
+                assert_eq!(text, "long");
+                found.0 = true;
+            } else if name == "link" {
+                let target = entry.link_name_bytes().unwrap();
+                assert_eq!(entry_name(&target), long);
+                found.1 = true;
+            }
+        }
+        assert_eq!(found, (true, true));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let writer = setup(&path, "dir-entries.zip")?;
+        let res = writer.delete(&["nonexistent".to_owned()]);
+        assert_err(&res.unwrap_err(), &VirtualDirError::NotFound("".into()));
+        let res = writer.rename("nonexistent", "foo");
+        assert_err(&res.unwrap_err(), &VirtualDirError::NotFound("".into()));
+        let res = writer.rename("1 text.txt", "2 image.jpg");
+        let err = VirtualDirError::AlreadyExists("".into());
+        assert_err(&res.unwrap_err(), &err);
+        // 壊れたアーカイブは書き換えられず、一時ファイルも残らない。
+        let broken = format!("{path}/broken.zip");
+        fs::write(&broken, "broken")?;
+        let writer = ArchiveWriter::new(ArchiveKind::Zip, &broken);
+        assert!(writer.delete(&["foo".to_owned()]).is_err());
+        assert_eq!(fs::read_to_string(&broken)?, "broken");
+        // 7z アーカイブは書き換えに対応していない。
+        let writer = setup(&path, "dir-entries.7z")?;
+        let res = writer.delete(&["1 text.txt".to_owned()]);
+        let err = VirtualDirError::UnsupportedArchive;
+        assert_err(&res.unwrap_err(), &err);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,563 @@
+use crate::{
+    errors::FsError,
+    helpers::temp_path,
+    misc::{ProgressJob, remove_entry},
+    models::ConflictPolicy,
+};
+
+use anyhow::{Result, bail};
+use filetime::{FileTime, set_file_times, set_symlink_file_times};
+use std::{
+    fs::{self, File, Metadata},
+    io::{ErrorKind, Read, Write},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+/// 一度に読み書きするバイト数。
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// ファイルやディレクトリツリーをコピーする構造体。
+///
+/// パーミッションと更新日時を保持し、シンボリックリンクはリンクのままコピーする。
+/// 進捗状況は ProgressJob に報告される。
+///
+/// # Fields
+/// * `policy` - コピー先に同名のエントリが存在する場合の処理方針
+/// * `job` - 進捗状況
+/// * `buf` - 読み書き用のバッファ
+pub struct Copier<'a> {
+    policy: ConflictPolicy,
+    job: &'a ProgressJob,
+    buf: Vec<u8>,
+}
+
+impl<'a> Copier<'a> {
+    /// 新しい Copier インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `policy` - コピー先に同名のエントリが存在する場合の処理方針
+    /// * `job` - 進捗状況
+    pub fn new(policy: ConflictPolicy, job: &'a ProgressJob) -> Self {
+        Self {
+            policy,
+            job,
+            buf: vec![0; CHUNK_SIZE],
+        }
+    }
+
+    /// 処理対象の総バイト数とエントリ総数を算出する。
+    ///
+    /// シンボリックリンクは辿らない。
+    ///
+    /// # Arguments
+    /// * `paths` - 対象パスの配列
+    ///
+    /// # Returns
+    /// 総バイト数とエントリ総数のタプル
+    pub fn measure(paths: &[impl AsRef<Path>]) -> Result<(u64, u64)> {
+        let mut total = (0, 0);
+        for path in paths {
+            let (bytes, files) = Self::measure_entry(path.as_ref())?;
+            total.0 += bytes;
+            total.1 += files;
+        }
+        Ok(total)
+    }
+
+    /// エントリ配下の総バイト数とエントリ総数を算出する。
+    ///
+    /// # Arguments
+    /// * `path` - 対象パス
+    fn measure_entry(path: &Path) -> Result<(u64, u64)> {
+        let meta = fs::symlink_metadata(path)?;
+        if !meta.is_dir() {
+            let bytes = if meta.is_file() { meta.len() } else { 0 };
+            return Ok((bytes, 1));
+        }
+        let mut total = (0, 1);
+        for entry in fs::read_dir(path)? {
+            let (bytes, files) = Self::measure_entry(&entry?.path())?;
+            total.0 += bytes;
+            total.1 += files;
+        }
+        Ok(total)
+    }
+
+    /// エントリを指定したディレクトリの直下にコピーする。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dest` - コピー先のディレクトリ
+    ///
+    /// # Returns
+    /// コピーした場合はコピー先のパス、スキップした場合は None
+    ///
+    /// # Errors
+    /// - `FsError::IntoItself`:
+    ///   ディレクトリを自身の配下にコピーしようとした。
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn copy(&mut self, src: &Path, dest: &Path) -> Result<Option<PathBuf>> {
+        let Some(name) = src.file_name() else {
+            bail!("Invalid path: {}", src.display());
+        };
+        if dest.starts_with(src) && src.is_dir() {
+            bail!(FsError::IntoItself(src.to_string_lossy().to_string()));
+        }
+        self.copy_entry(src, &dest.join(name))
+    }
+
+    /// エントリをコピーする。
+    ///
+    /// ディレクトリの場合は配下のエントリを再帰的にコピーする。
+    /// 既存のエントリを上書きする場合は、一時的な名前でコピーしてから置き換える。
+    /// 途中で中止または失敗しても、既存のエントリが失われないようにするため。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dst` - コピー先のパス
+    ///
+    /// # Returns
+    /// コピーした場合はコピー先のパス、スキップした場合は None
+    pub fn copy_entry(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+    ) -> Result<Option<PathBuf>> {
+        self.job.check()?;
+        let meta = fs::symlink_metadata(src)?;
+        let Some(dst) = resolve_conflict(self.policy, src, &meta, dst)? else {
+            // スキップしたエントリも処理済みとして扱う。
+            let (bytes, files) = Self::measure_entry(src)?;
+            self.job.add_bytes(bytes);
+            self.job.add_files(files);
+            return Ok(None);
+        };
+        let copied = if is_replacement(&meta, &dst) {
+            let tmp = temp_path(&dst);
+            let res = self.copy_new(src, &tmp, &meta).and_then(|copied| {
+                if copied {
+                    replace_entry(&tmp, &dst)?;
+                }
+                Ok(copied)
+            });
+            if res.is_err() {
+                let _ = remove_entry(&tmp);
+            }
+            res?
+        } else {
+            self.copy_new(src, &dst, &meta)?
+        };
+        Ok(copied.then_some(dst))
+    }
+
+    /// エントリをコピーする (コピー先の解決済み)。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dst` - コピー先のパス
+    ///   ディレクトリの場合はマージ先として存在していてもよい。
+    /// * `meta` - コピー元のメタデータ
+    ///
+    /// # Returns
+    /// コピーした場合は true、特殊ファイルのためコピーしなかった場合は false
+    fn copy_new(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        meta: &Metadata,
+    ) -> Result<bool> {
+        if meta.is_dir() {
+            self.copy_dir(src, dst, meta)?;
+        } else if meta.is_symlink() {
+            symlink(fs::read_link(src)?, dst)?;
+            let mtime = FileTime::from_last_modification_time(meta);
+            let atime = FileTime::from_last_access_time(meta);
+            set_symlink_file_times(dst, atime, mtime)?;
+            self.job.add_files(1);
+        } else if meta.is_file() {
+            self.copy_file(src, dst, meta)?;
+        } else {
+            // FIFO やソケットなどの特殊ファイルはコピーしない。
+            self.job.add_files(1);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// ディレクトリをコピーする。
+    ///
+    /// コピー先にディレクトリが存在する場合はマージする。
+    /// パーミッションと更新日時は、配下のコピーが終わった後に設定する。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dst` - コピー先のパス
+    /// * `meta` - コピー元のメタデータ
+    fn copy_dir(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        meta: &Metadata,
+    ) -> Result<()> {
+        if !dst.is_dir() {
+            fs::create_dir(dst)?;
+        }
+        self.job.add_files(1);
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            self.copy_entry(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        fs::set_permissions(dst, meta.permissions())?;
+        self.set_times(dst, meta)
+    }
+
+    /// ファイルをコピーする。
+    ///
+    /// 中止された場合は、書きかけのファイルを削除する。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dst` - コピー先のパス
+    /// * `meta` - コピー元のメタデータ
+    fn copy_file(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+        meta: &Metadata,
+    ) -> Result<()> {
+        let res = self.write_file(src, dst);
+        if res.is_err() {
+            let _ = fs::remove_file(dst);
+            res?;
+        }
+        fs::set_permissions(dst, meta.permissions())?;
+        self.set_times(dst, meta)?;
+        self.job.add_files(1);
+        Ok(())
+    }
+
+    /// ファイルの内容をチャンク単位で書き出す。
+    ///
+    /// # Arguments
+    /// * `src` - コピー元のパス
+    /// * `dst` - コピー先のパス
+    fn write_file(&mut self, src: &Path, dst: &Path) -> Result<()> {
+        let mut reader = File::open(src)?;
+        let mut writer = File::create(dst)?;
+        loop {
+            self.job.check()?;
+            let n = match reader.read(&mut self.buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            };
+            writer.write_all(&self.buf[..n])?;
+            self.job.add_bytes(n as u64);
+        }
+        Ok(())
+    }
+
+    /// コピー元の更新日時とアクセス日時をコピー先に設定する。
+    ///
+    /// # Arguments
+    /// * `dst` - コピー先のパス
+    /// * `meta` - コピー元のメタデータ
+    fn set_times(&self, dst: &Path, meta: &Metadata) -> Result<()> {
+        let mtime = FileTime::from_last_modification_time(meta);
+        let atime = FileTime::from_last_access_time(meta);
+        set_file_times(dst, atime, mtime)?;
+        Ok(())
+    }
+}
+
+/// 処理方針に従って、実際のコピー先 (移動先) のパスを決定する。
+///
+/// 既存のエントリは削除しない。
+/// 上書きする場合は既存のエントリのパスを返すため、
+/// 呼び出し側で一時的な名前に書き出してから `replace_entry` で置き換えること。
+/// ディレクトリ同士の場合は、名前を変える方針以外ではマージするものとし、
+/// 既存のディレクトリのパスをそのまま返す。
+///
+/// # Arguments
+/// * `policy` - 同名のエントリが存在する場合の処理方針
+/// * `src` - コピー元のパス
+/// * `meta` - コピー元のメタデータ
+/// * `dst` - コピー先のパス
+///
+/// # Returns
+/// コピー先のパス、スキップする場合は None
+///
+/// # Errors
+/// - `FsError::ReplaceDir`:
+///   既存のディレクトリをディレクトリ以外で上書きしようとした。
+pub fn resolve_conflict(
+    policy: ConflictPolicy,
+    src: &Path,
+    meta: &Metadata,
+    dst: &Path,
+) -> Result<Option<PathBuf>> {
+    let dst_meta = match fs::symlink_metadata(dst) {
+        Ok(m) => m,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Ok(Some(dst.to_path_buf()));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // 自身と同じ場所へのコピーは、方針にかかわらず複製とする。
+    if src == dst {
+        return Ok(Some(unique_path(dst)));
+    }
+    // ディレクトリ同士はマージする。
+    let is_dirs = meta.is_dir() && dst_meta.is_dir();
+    if is_dirs && policy != ConflictPolicy::Rename {
+        return Ok(Some(dst.to_path_buf()));
+    }
+    let overwrite = match policy {
+        ConflictPolicy::Skip => false,
+        ConflictPolicy::Overwrite => true,
+        ConflictPolicy::Rename => return Ok(Some(unique_path(dst))),
+        ConflictPolicy::Newer => {
+            let src_time = FileTime::from_last_modification_time(meta);
+            src_time > FileTime::from_last_modification_time(&dst_meta)
+        }
+    };
+    if !overwrite {
+        return Ok(None);
+    }
+    // ディレクトリ配下がまるごと失われるため、ファイルでの上書きは拒否する。
+    if dst_meta.is_dir() {
+        bail!(FsError::ReplaceDir(dst.to_string_lossy().to_string()));
+    }
+    Ok(Some(dst.to_path_buf()))
+}
+
+/// `resolve_conflict` で決定したパスが、既存のエントリの上書きか否かを判定する。
+///
+/// # Arguments
+/// * `meta` - コピー元のメタデータ
+/// * `dst` - `resolve_conflict` で決定したパス
+pub fn is_replacement(meta: &Metadata, dst: &Path) -> bool {
+    match fs::symlink_metadata(dst) {
+        Ok(dst_meta) => !(meta.is_dir() && dst_meta.is_dir()),
+        Err(_) => false,
+    }
+}
+
+/// 一時的な名前で書き出したエントリで、既存のエントリを置き換える。
+///
+/// ディレクトリ以外は rename でアトミックに置き換える。
+/// ディレクトリはファイルの上に rename できないため、
+/// 既存のエントリを一時的な名前に退避してから置き換え、最後に削除する。
+///
+/// # Arguments
+/// * `tmp` - 書き出したエントリのパス
+/// * `dst` - 置き換えるエントリのパス (ディレクトリ以外)
+pub fn replace_entry(tmp: &Path, dst: &Path) -> Result<()> {
+    if !fs::symlink_metadata(tmp)?.is_dir() {
+        fs::rename(tmp, dst)?;
+        return Ok(());
+    }
+    let backup = temp_path(dst);
+    fs::rename(dst, &backup)?;
+    if let Err(err) = fs::rename(tmp, dst) {
+        let _ = fs::rename(&backup, dst);
+        return Err(err.into());
+    }
+    remove_entry(&backup)
+}
+
+/// 既存のエントリと重複しないパスを生成する。
+///
+/// 例： `foo.txt` -> `foo (1).txt` -> `foo (2).txt` ...
+///
+/// # Arguments
+/// * `path` - 元のパス
+///
+/// # Returns
+/// 存在しないパス
+pub fn unique_path(path: &Path) -> PathBuf {
+    let mut n = 1;
+    loop {
+        let p = numbered_path(path, n);
+        if fs::symlink_metadata(&p).is_err() {
+            return p;
+        }
+        n += 1;
+    }
+}
+
+/// パスのファイル名に連番を付与する。
+///
+/// 例： `foo.txt`, 2 -> `foo (2).txt`
+///
+/// # Arguments
+/// * `path` - 元のパス
+/// * `n` - 連番
+///
+/// # Returns
+/// 連番が付与されたパス
+pub fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    path.with_file_name(format!("{stem} ({n}){ext}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, count_entries, setup_resources, teardown_resources,
+    };
+
+    use std::{os::unix::fs::PermissionsExt as _, sync::Arc};
+
+    use super::*;
+
+    async fn setup() -> Result<(String, Arc<ProgressJob>)> {
+        let path = setup_resources("test").await?;
+        Ok((path, ProgressJob::new()))
+    }
+
+    #[tokio::test]
+    async fn test_measure() -> Result<()> {
+        let (path, _) = setup().await?;
+        let srcs = [format!("{path}/test1"), format!("{path}/test.txt")];
+        assert_eq!(Copier::measure(&srcs)?, (4, 3));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_tree() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut copier = Copier::new(ConflictPolicy::Skip, &job);
+        copier.copy(&p.join("test1"), &p.join("test3"))?;
+        copier.copy(&p.join("test1.txt's link"), &p.join("test3"))?;
+        assert_eq!(count_entries(&[format!("{path}/test3")])?, 4);
+        let meta = fs::metadata(p.join("test3/test1"))?;
+        assert_eq!(meta.permissions().mode() & 0o777, 0o766);
+        let src = fs::metadata(p.join("test1"))?;
+        assert_eq!(meta.modified()?, src.modified()?);
+        let link = fs::read_link(p.join("test3/test1.txt's link"))?;
+        assert_eq!(link, p.join("test1/test1.txt"));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_into_itself() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut copier = Copier::new(ConflictPolicy::Skip, &job);
+        let res = copier.copy(&p.join("test1"), &p.join("test1"));
+        assert_err(&res.unwrap_err(), &FsError::IntoItself("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_same_dir() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut copier = Copier::new(ConflictPolicy::Overwrite, &job);
+        let dst = copier.copy(&p.join("test.txt"), p)?;
+        assert_eq!(dst, Some(p.join("test (1).txt")));
+        assert_eq!(fs::read_to_string(p.join("test.txt"))?, "test");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_conflict() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let dst = p.join("test3/test.txt");
+        fs::write(&dst, "old")?;
+        let mut copier = Copier::new(ConflictPolicy::Skip, &job);
+        assert_eq!(copier.copy(&p.join("test.txt"), &p.join("test3"))?, None);
+        assert_eq!(fs::read_to_string(&dst)?, "old");
+        let mut copier = Copier::new(ConflictPolicy::Rename, &job);
+        let res = copier.copy(&p.join("test.txt"), &p.join("test3"))?;
+        assert_eq!(res, Some(p.join("test3/test (1).txt")));
+        // コピー先の方が新しい場合は上書きしない。
+        let mut copier = Copier::new(ConflictPolicy::Newer, &job);
+        assert_eq!(copier.copy(&p.join("test.txt"), &p.join("test3"))?, None);
+        assert_eq!(fs::read_to_string(&dst)?, "old");
+        let mut copier = Copier::new(ConflictPolicy::Overwrite, &job);
+        assert_eq!(
+            copier.copy(&p.join("test.txt"), &p.join("test3"))?,
+            Some(dst.clone())
+        );
+        assert_eq!(fs::read_to_string(&dst)?, "test");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_replace() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut copier = Copier::new(ConflictPolicy::Overwrite, &job);
+
+        // ファイルはディレクトリで置き換えられる。
+        fs::write(p.join("test3/test1"), "old")?;
+        copier.copy(&p.join("test1"), &p.join("test3"))?;
+        assert!(p.join("test3/test1/test1.txt").is_file());
+
+        // ディレクトリはファイルで置き換えられない。
+        fs::create_dir(p.join("test3/test.txt"))?;
+        fs::write(p.join("test3/test.txt/keep.txt"), "")?;
+        let res = copier.copy(&p.join("test.txt"), &p.join("test3"));
+        let dst = p.join("test3/test.txt").to_string_lossy().to_string();
+        assert_err(&res.unwrap_err(), &FsError::ReplaceDir(dst));
+        assert!(p.join("test3/test.txt/keep.txt").is_file());
+
+        // 一時的なエントリは残らない。
+        let names: Vec<_> = fs::read_dir(p.join("test3"))?
+            .map(|e| Ok(e?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<_>>()?;
+        assert!(names.iter().all(|n| !n.ends_with(".tmp")));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_merge() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        fs::create_dir(p.join("test3/test1"))?;
+        fs::write(p.join("test3/test1/other.txt"), "")?;
+        let mut copier = Copier::new(ConflictPolicy::Skip, &job);
+        copier.copy(&p.join("test1"), &p.join("test3"))?;
+        assert!(p.join("test3/test1/test1.txt").is_file());
+        assert!(p.join("test3/test1/other.txt").is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_aborted() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        job.abort();
+        let mut copier = Copier::new(ConflictPolicy::Skip, &job);
+        let res = copier.copy(&p.join("test.txt"), &p.join("test3"));
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        assert!(!p.join("test3/test.txt").exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let path = Path::new("/path/to/nowhere/foo.tar.gz");
+        let res = unique_path(path);
+        assert_eq!(res, Path::new("/path/to/nowhere/foo.tar (1).gz"));
+    }
+}
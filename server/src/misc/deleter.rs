@@ -0,0 +1,203 @@
+use crate::{misc::ProgressJob, models::DeleteFailure};
+
+use anyhow::Result;
+use std::{fs, path::Path};
+
+/// ファイルやディレクトリツリーを削除する構造体。
+///
+/// 削除に失敗したエントリがあっても処理を続け、失敗した理由を収集する。
+/// 進捗状況は ProgressJob に報告される。
+///
+/// # Fields
+/// * `job` - 進捗状況
+/// * `failures` - 削除に失敗したエントリの一覧
+pub struct Deleter<'a> {
+    job: &'a ProgressJob,
+    failures: Vec<DeleteFailure>,
+}
+
+impl<'a> Deleter<'a> {
+    /// 新しい Deleter インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `job` - 進捗状況
+    pub fn new(job: &'a ProgressJob) -> Self {
+        Self {
+            job,
+            failures: vec![],
+        }
+    }
+
+    /// 削除するエントリの総数を数える。
+    ///
+    /// 進捗の算出にだけ使うため、読み込めないエントリがあっても失敗しない。
+    /// 存在しないエントリや読み込めないディレクトリは 1 件として数える。
+    /// シンボリックリンクは辿らない。
+    ///
+    /// # Arguments
+    /// * `paths` - 対象パスの配列
+    pub fn count(paths: &[impl AsRef<Path>]) -> u64 {
+        paths.iter().map(|p| Self::count_entry(p.as_ref())).sum()
+    }
+
+    /// エントリ配下のエントリ総数を数える。
+    ///
+    /// # Arguments
+    /// * `path` - 対象パス
+    fn count_entry(path: &Path) -> u64 {
+        let is_dir = fs::symlink_metadata(path).is_ok_and(|m| m.is_dir());
+        let Some(entries) = is_dir.then(|| fs::read_dir(path).ok()).flatten()
+        else {
+            return 1;
+        };
+        let children = entries.flatten().map(|e| Self::count_entry(&e.path()));
+        1 + children.sum::<u64>()
+    }
+
+    /// エントリを削除する。
+    ///
+    /// ディレクトリの場合は配下を再帰的に削除する。
+    /// 配下に削除できなかったエントリがある場合、
+    /// ディレクトリ自身は失敗として記録せずに残す。
+    ///
+    /// # Arguments
+    /// * `path` - 削除するパス
+    ///
+    /// # Returns
+    /// 配下も含めてすべて削除できたか否か
+    ///
+    /// # Errors
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn delete(&mut self, path: &Path) -> Result<bool> {
+        self.job.check()?;
+        let meta = match fs::symlink_metadata(path) {
+            Ok(meta) => meta,
+            Err(err) => return Ok(self.fail(path, err)),
+        };
+        let res = if meta.is_dir() {
+            self.delete_dir(path)?
+        } else {
+            match fs::remove_file(path) {
+                Ok(()) => true,
+                Err(err) => self.fail(path, err),
+            }
+        };
+        self.job.add_files(1);
+        Ok(res)
+    }
+
+    /// ディレクトリの配下を削除した後、ディレクトリ自身を削除する。
+    ///
+    /// # Arguments
+    /// * `path` - 削除するディレクトリのパス
+    fn delete_dir(&mut self, path: &Path) -> Result<bool> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) => return Ok(self.fail(path, err)),
+        };
+        let mut ok = true;
+        for entry in entries {
+            match entry {
+                Ok(entry) => ok &= self.delete(&entry.path())?,
+                Err(err) => ok = self.fail(path, err),
+            }
+        }
+        if !ok {
+            return Ok(false);
+        }
+        match fs::remove_dir(path) {
+            Ok(()) => Ok(true),
+            Err(err) => Ok(self.fail(path, err)),
+        }
+    }
+
+    /// 削除に失敗したエントリを記録する。
+    ///
+    /// # Arguments
+    /// * `path` - エントリのパス
+    /// * `err` - 発生したエラー
+    ///
+    /// # Returns
+    /// 常に false
+    fn fail(&mut self, path: &Path, err: impl ToString) -> bool {
+        self.failures.push(DeleteFailure {
+            path: path.to_string_lossy().to_string(),
+            msg: err.to_string(),
+        });
+        false
+    }
+
+    /// 削除に失敗したエントリの一覧を取得する。
+    pub fn failures(&self) -> &[DeleteFailure] {
+        &self.failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::FsError,
+        test_helpers::{assert_err, setup_resources, teardown_resources},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_delete() -> Result<()> {
+        let path = setup_resources("").await?;
+        let p = Path::new(&path);
+        let job = ProgressJob::new();
+        job.set_total(0, 3);
+        let mut deleter = Deleter::new(&job);
+        assert!(deleter.delete(&p.join("test1"))?);
+        assert!(deleter.delete(&p.join("test1.txt's link"))?);
+        assert!(!p.join("test1").exists());
+        // リンク先は削除されない。
+        assert!(p.join("test.txt").exists());
+        assert!(deleter.failures().is_empty());
+        assert_eq!(job.percent(), 100);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_count() -> Result<()> {
+        let path = setup_resources("").await?;
+        let p = Path::new(&path);
+        assert_eq!(Deleter::count(&[p.join("test1")]), 2);
+        // 存在しないエントリも 1 件として数える。
+        assert_eq!(Deleter::count(&[p.join("nowhere"), p.join("test.txt")]), 2);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_failure() -> Result<()> {
+        let path = setup_resources("").await?;
+        let p = Path::new(&path);
+        let job = ProgressJob::new();
+        let mut deleter = Deleter::new(&job);
+        assert!(!deleter.delete(&p.join("nowhere"))?);
+        assert!(deleter.delete(&p.join("test2"))?);
+        let failures = deleter.failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].path, format!("{path}/nowhere"));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_aborted() -> Result<()> {
+        let path = setup_resources("").await?;
+        let p = Path::new(&path);
+        let job = ProgressJob::new();
+        job.abort();
+        let mut deleter = Deleter::new(&job);
+        let res = deleter.delete(&p.join("test1"));
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        assert!(p.join("test1").exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use crate::helpers::ls_style_size;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone as _};
+use html_escape::encode_text;
+use std::{
+    fs,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use unicode_normalization::UnicodeNormalization as _;
+
+/// 走査するエントリ数の上限。
+const MAX_ENTRIES: u64 = 100_000;
+
+/// 走査にかける時間の上限。
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 一覧に表示する直下のエントリ数の上限。
+const MAX_LISTING: usize = 20;
+
+/// 更新日時の記録されたファイル。
+///
+/// # Fields
+/// * `path` - ディレクトリからの相対パス
+/// * `mtime` - 最終更新日時 (UNIX 時間)
+struct FileStamp {
+    path: String,
+    mtime: i64,
+}
+
+/// ディレクトリの概要をプレビューするための構造体。
+///
+/// 配下のエントリを再帰的に走査して集計する。
+/// 巨大なディレクトリでも応答が遅くならないよう、
+/// エントリ数か時間が上限に達した時点で走査を打ち切る。
+/// シンボリックリンクは辿らない。
+///
+/// # Fields
+/// * `files` - ファイルの数
+/// * `dirs` - ディレクトリの数
+/// * `symlinks` - シンボリックリンクの数
+/// * `others` - その他 (ソケットなど) の数
+/// * `size` - ファイルの合計サイズ (バイト)
+/// * `newest` - 最も新しいファイル
+/// * `oldest` - 最も古いファイル
+/// * `listing` - 直下のエントリ名の一覧
+///   ディレクトリには末尾に `/` が付く。
+/// * `truncated` - 走査を打ち切ったか否か
+pub struct DirSummary {
+    files: u64,
+    dirs: u64,
+    symlinks: u64,
+    others: u64,
+    size: u64,
+    newest: Option<FileStamp>,
+    oldest: Option<FileStamp>,
+    listing: Vec<String>,
+    truncated: bool,
+}
+
+impl DirSummary {
+    /// ディレクトリを走査して概要を作成する。
+    ///
+    /// 読み込めないサブディレクトリは無視する。
+    ///
+    /// # Arguments
+    /// * `root` - ディレクトリのパス
+    ///
+    /// # Errors
+    /// `root` を読み込めない場合はエラー
+    pub fn new(root: &Path) -> Result<Self> {
+        let mut summary = Self {
+            files: 0,
+            dirs: 0,
+            symlinks: 0,
+            others: 0,
+            size: 0,
+            newest: None,
+            oldest: None,
+            listing: Self::listing(root)?,
+            truncated: false,
+        };
+        summary.walk(root, Instant::now() + TIMEOUT);
+        Ok(summary)
+    }
+
+    /// 直下のエントリ名の一覧を取得する。
+    ///
+    /// # Arguments
+    /// * `root` - ディレクトリのパス
+    ///
+    /// # Returns
+    /// 名前でソートされたエントリ名の一覧
+    fn listing(root: &Path) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(root)?.flatten() {
+            let name = entry.file_name().to_string_lossy().nfc().to_string();
+            match entry.file_type().is_ok_and(|t| t.is_dir()) {
+                true => names.push(format!("{name}/")),
+                false => names.push(name),
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// 配下のエントリを走査して集計する。
+    ///
+    /// # Arguments
+    /// * `root` - ディレクトリのパス
+    /// * `deadline` - 走査を打ち切る時刻
+    fn walk(&mut self, root: &Path, deadline: Instant) {
+        let mut stack: Vec<PathBuf> = vec![root.to_owned()];
+        let mut count = 0;
+        while let Some(dir) = stack.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                count += 1;
+                if count > MAX_ENTRIES || Instant::now() > deadline {
+                    self.truncated = true;
+                    return;
+                }
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                let path = entry.path();
+                if meta.is_dir() {
+                    self.dirs += 1;
+                    stack.push(path);
+                } else if meta.is_symlink() {
+                    self.symlinks += 1;
+                } else if meta.is_file() {
+                    self.files += 1;
+                    self.size += meta.len();
+                    let rel = path.strip_prefix(root).unwrap_or(&path);
+                    self.stamp(rel, meta.mtime());
+                } else {
+                    self.others += 1;
+                }
+            }
+        }
+    }
+
+    /// 最も新しいファイルと最も古いファイルを更新する。
+    ///
+    /// # Arguments
+    /// * `path` - ディレクトリからの相対パス
+    /// * `mtime` - ファイルの最終更新日時
+    fn stamp(&mut self, path: &Path, mtime: i64) {
+        let stamp = || FileStamp {
+            path: path.to_string_lossy().nfc().to_string(),
+            mtime,
+        };
+        if self.newest.as_ref().is_none_or(|s| mtime > s.mtime) {
+            self.newest = Some(stamp());
+        }
+        if self.oldest.as_ref().is_none_or(|s| mtime < s.mtime) {
+            self.oldest = Some(stamp());
+        }
+    }
+
+    /// 概要を HTML に変換する。
+    ///
+    /// プレビュー用 HTML テンプレートは `white-space: pre-wrap` のため、
+    /// タグの間に改行を入れない。
+    ///
+    /// # Arguments
+    /// * `time_style` - 日時のフォーマット文字列
+    ///
+    /// # Returns
+    /// HTML エスケープ済みの HTML
+    pub fn html(&self, time_style: &str) -> String {
+        // 打ち切った場合、集計値は下限となる。
+        let approx = if self.truncated { "&ge; " } else { "" };
+        let stamp = |s: &Option<FileStamp>| {
+            let Some(s) = s else {
+                return "-".to_owned();
+            };
+            let time = Local
+                .timestamp_opt(s.mtime, 0)
+                .single()
+                .map(|dt| dt.format(time_style).to_string())
+                .unwrap_or_default();
+            format!("{} ({time})", encode_text(&s.path))
+        };
+        let rows = [
+            ("Files", format!("{approx}{}", self.files)),
+            ("Directories", format!("{approx}{}", self.dirs)),
+            ("Symlinks", format!("{approx}{}", self.symlinks)),
+            ("Others", format!("{approx}{}", self.others)),
+            (
+                "Total size",
+                format!("{approx}{}", ls_style_size(self.size)),
+            ),
+            ("Newest", stamp(&self.newest)),
+            ("Oldest", stamp(&self.oldest)),
+        ];
+        let rows: String = rows
+            .iter()
+            .map(|(k, v)| format!("<tr><th>{k}</th><td>{v}</td></tr>"))
+            .collect();
+
+        let mut items: String = self
+            .listing
+            .iter()
+            .take(MAX_LISTING)
+            .map(|name| format!("<li>{}</li>", encode_text(name)))
+            .collect();
+        if self.listing.len() > MAX_LISTING {
+            let rest = self.listing.len() - MAX_LISTING;
+            items += &format!("<li>... and {rest} more</li>");
+        }
+
+        format!(
+            r#"<table class="previewWindow_summary">{rows}</table><ul class="previewWindow_listing">{items}</ul>"#
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{setup_resources, teardown_resources};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_dir_summary() -> Result<()> {
+        let path = setup_resources("").await?;
+        let summary = DirSummary::new(Path::new(&path))?;
+        assert_eq!(summary.files, 3);
+        assert_eq!(summary.dirs, 3);
+        assert_eq!(summary.symlinks, 1);
+        assert_eq!(summary.others, 0);
+        assert!(summary.newest.is_some());
+        assert!(summary.oldest.is_some());
+        assert!(!summary.truncated);
+        assert_eq!(
+            summary.listing,
+            ["test.txt", "test1.txt's link", "test1/", "test2/", "test3/"]
+        );
+
+        let html = summary.html("%y/%m/%d %H:%M:%S");
+        assert!(html.contains("<tr><th>Files</th><td>3</td></tr>"));
+        assert!(html.contains("<li>test1/</li>"));
+        assert!(!html.contains('\n'));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dir_summary_error() {
+        assert!(DirSummary::new(Path::new("./nonexistent")).is_err());
+    }
+}
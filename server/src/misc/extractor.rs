@@ -0,0 +1,852 @@
+use crate::{
+    archives::Iso,
+    errors::VirtualDirError,
+    helpers::decode_string,
+    misc::{ProgressJob, ProgressReader},
+    models::ArchiveKind,
+};
+
+use anyhow::{Result, bail};
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use sevenz_rust::{BlockDecoder, SevenZArchiveEntry};
+use std::{
+    fs::{File, create_dir_all, remove_file},
+    io::{self, BufReader, ErrorKind, Read, Seek, copy, empty, sink},
+    os::unix::ffi::OsStringExt as _,
+    path::Path,
+};
+use xz2::read::XzDecoder;
+use zip::{ZipArchive, read::ZipFile, result::ZipError};
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// アーカイブからエントリを実ディレクトリに展開する構造体。
+///
+/// すでに存在するファイルは上書きせず、スキップする。
+/// 進捗状況は ProgressJob に報告される。
+///
+/// # Fields
+/// * `job` - 進捗状況
+/// * `limit` - 書き出すファイルのバイト数の上限
+///   None の場合は上限なし。
+pub struct Extractor<'a> {
+    job: &'a ProgressJob,
+    limit: Option<u64>,
+}
+
+impl<'a> Extractor<'a> {
+    /// 新しい Extractor インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `job` - 進捗状況
+    pub fn new(job: &'a ProgressJob) -> Self {
+        Self { job, limit: None }
+    }
+
+    /// 書き出すファイルのバイト数に上限を設ける。
+    ///
+    /// 上限を超えるエントリは、先頭の `limit` バイトだけを書き出す。
+    /// プレビューなど、エントリの一部だけが必要な場合に使う。
+    ///
+    /// # Arguments
+    /// * `limit` - 書き出すファイルのバイト数の上限
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// アーカイブからエントリを展開する。
+    ///
+    /// tar 系のアーカイブは全体を読み進める必要があるため、
+    /// 読み込んだアーカイブファイルのバイト数を進捗とする。
+    /// それ以外は、展開したエントリのバイト数を進捗とする。
+    /// 中止した場合、書きかけのファイルは残らない。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    ///   アーカイブ内のパスで、スラッシュ始まりとする。
+    ///   例： `/rust/util/main.rs`
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    /// * `password` - 暗号化されたエントリを復号するパスワード
+    ///   zip 以外では使用しない。
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    ///
+    /// # Errors
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   暗号化されたエントリがあるが、パスワードが指定されていない。
+    /// - `VirtualDirError::WrongPassword`:
+    ///   パスワードが間違っている。
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn extract(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+        password: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let res = match kind {
+            ArchiveKind::Zip => {
+                self.copy_zip_entries(archive, srcs, dest, cwd, password)
+            }
+            ArchiveKind::Tar => self.copy_tar_entries(archive, srcs, dest, cwd),
+            ArchiveKind::Tgz => self.copy_tgz_entries(archive, srcs, dest, cwd),
+            ArchiveKind::Txz => self.copy_txz_entries(archive, srcs, dest, cwd),
+            ArchiveKind::Tbz => self.copy_tbz_entries(archive, srcs, dest, cwd),
+            ArchiveKind::Tzst => {
+                self.copy_tzst_entries(archive, srcs, dest, cwd)
+            }
+            ArchiveKind::SevenZip => {
+                self.copy_7z_entries(archive, srcs, dest, cwd)
+            }
+            ArchiveKind::Iso => self.copy_iso_entries(archive, srcs, dest, cwd),
+        };
+        if res.is_err() {
+            // 読み込み中に中止を検知した場合は io::Error に包まれているため、
+            // ここで改めて中止エラーとして返す。
+            self.job.check()?;
+        }
+        res
+    }
+
+    /// 展開の前に、暗号化されたエントリのパスワードを確認する。
+    ///
+    /// 対象のうち最初の暗号化されたエントリのみを確認する。
+    /// 展開を始める前にパスワードの入力を求められるようにするためのもので、
+    /// zip 以外では何もしない。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `password` - 暗号化されたエントリを復号するパスワード
+    ///
+    /// # Errors
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   暗号化されたエントリがあるが、パスワードが指定されていない。
+    /// - `VirtualDirError::WrongPassword`:
+    ///   パスワードが間違っている。
+    pub fn verify_password(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        srcs: &[String],
+        password: Option<&str>,
+    ) -> Result<()> {
+        if *kind != ArchiveKind::Zip {
+            return Ok(());
+        }
+        let file = File::open(archive)?;
+        let mut zip = ZipArchive::new(file)?;
+        for i in self.zip_targets(&mut zip, srcs)? {
+            if zip.by_index_raw(i)?.encrypted() {
+                self.open_zip_entry(&mut zip, i, password)?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// バイト列をパス文字列にデコードする。
+    ///
+    /// 記述を統一するため、相対パスの場合は `./` は削除する。
+    ///
+    /// # Arguments
+    /// * `raw` - デコードするバイト列
+    ///
+    /// # Returns
+    /// デコードされたパス文字列
+    fn decode_path(&self, raw: &[u8]) -> String {
+        let s = decode_string(raw);
+        if s.starts_with("./") {
+            return s.strip_prefix("./").unwrap().to_owned();
+        }
+        s
+    }
+
+    /// 指定したパスがソースパスにマッチするか否かを確認する。
+    ///
+    /// 要するに、そのパスがコピーの対象かどうかを判断する。
+    ///
+    /// # Arguments
+    /// * `raw` - パスのバイト列
+    /// * `srcs` - コピーするパスの配列
+    fn is_match(&self, raw: &[u8], srcs: &[String]) -> bool {
+        // ソースパスはスラッシュ始まりなため、それに合わせる。
+        let path = format!("/{}", self.decode_path(raw));
+        // 以下の場合、マッチしているとみなす。
+        // - ソースパスと完全一致している
+        // - ソースパスから始まっている (＝ソースパスの下層にある)
+        srcs.iter()
+            .any(|s| s == &path || path.starts_with(&format!("{s}/")))
+    }
+
+    /// アーカイブ内のパスをカレントディレクトリからの相対パスに変換する。
+    ///
+    /// 得られたパスはコピー先のパスとして使用される。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `path` - アーカイブ内のパス
+    /// * `cwd` - 基準となるディレクトリ
+    pub fn relative_path(
+        archive: &str,
+        path: &str,
+        cwd: &str,
+    ) -> Option<String> {
+        // アーカイブ内基準のカレントディレクトリを取得する。
+        // archive: /Users/xxxx/Desktop/archive.zip
+        // cwd: /Users/xxxx/Desktop/archive.zip/rust/util/ascii
+        // -> rust/util/ascii
+        let root = cwd
+            .strip_prefix(archive)
+            .map(|r| r.strip_prefix("/").unwrap_or(r))?;
+
+        // 対象エントリの相対パスを取得する。
+        // root: rust/util/ascii
+        // path: rust/util/ascii/tests/expected/ascii.txt
+        // -> tests/expected/ascii.txt
+        let path = path
+            .strip_prefix(root)
+            .map(|r| r.strip_prefix("/").unwrap_or(r))?;
+
+        Some(path.to_owned())
+    }
+
+    /// 仮想ディレクトリ内のエントリを、実ディレクトリにコピーする。
+    ///
+    /// # Arguments
+    /// * `entry` - コピーするエントリのリーダー
+    /// * `archive` - アーカイブファイルのパス
+    /// * `raw` - コピーするエントリのパスのバイト列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされた場合はそのパス、それ以外は空文字列
+    fn extract_entry<R: Read + ?Sized>(
+        &self,
+        entry: &mut R,
+        archive: &str,
+        raw: &[u8],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<String> {
+        self.job.check()?;
+        let res = self.write_entry(entry, archive, raw, dest, cwd);
+        if res.is_ok() {
+            self.job.add_files(1);
+        }
+        res
+    }
+
+    /// エントリを実ディレクトリに書き出す。
+    ///
+    /// # Arguments
+    /// * `entry` - コピーするエントリのリーダー
+    /// * `archive` - アーカイブファイルのパス
+    /// * `raw` - コピーするエントリのパスのバイト列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされた場合はそのパス、それ以外は空文字列
+    fn write_entry<R: Read + ?Sized>(
+        &self,
+        entry: &mut R,
+        archive: &str,
+        raw: &[u8],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<String> {
+        let path = self.decode_path(raw);
+        let Some(path) = Self::relative_path(archive, &path, cwd) else {
+            bail!("Invalid path: {path}");
+        };
+        let dst = Path::new(dest).join(&path);
+        // ディレクトリパスなら、すべての階層のディレクトリを作成して抜ける。
+        if path.ends_with('/') {
+            create_dir_all(&dst)?;
+            return Ok("".to_owned());
+        }
+        // ファイルパスなら、親ディレクトリをすべて作成しておく。
+        // dest: /Users/xxxx/Desktop
+        // dst: /Users/xxxx/Desktop/tests/expected/ascii.txt
+        // -> /Users/xxxx/Desktop/tests/expected まで作成。
+        if let Some(p) = dst.parent() {
+            create_dir_all(p)?;
+        }
+        // すでに存在する場合は上書きせずにそのパスを返す。
+        // これはスキップされたパスを意味する。
+        if dst.exists() {
+            return Ok(dst.to_string_lossy().to_string());
+        }
+        // ファイルを書き出す。(＝コピー)
+        // 読み込みに失敗した場合は、書きかけのファイルを残さない。
+        let mut out = File::create(&dst)?;
+        let res = match self.limit {
+            Some(limit) => copy(&mut Read::take(entry, limit), &mut out),
+            None => copy(entry, &mut out),
+        };
+        if let Err(err) = res {
+            let _ = remove_file(&dst);
+            return Err(err.into());
+        }
+        Ok("".to_owned())
+    }
+
+    /// zip アーカイブ内のコピー対象のエントリのインデックスを取得する。
+    ///
+    /// # Arguments
+    /// * `zip` - ZipArchive の可変参照
+    /// * `srcs` - コピーするパスの配列
+    fn zip_targets<R: Read + Seek>(
+        &self,
+        zip: &mut ZipArchive<R>,
+        srcs: &[String],
+    ) -> Result<Vec<usize>> {
+        let mut targets = Vec::new();
+        for i in 0..zip.len() {
+            if self.is_match(zip.by_index_raw(i)?.name_raw(), srcs) {
+                targets.push(i);
+            }
+        }
+        Ok(targets)
+    }
+
+    /// zip のエントリを開く。
+    ///
+    /// 暗号化されたエントリ (ZipCrypto, AES) はパスワードで復号する。
+    ///
+    /// # Arguments
+    /// * `zip` - ZipArchive の可変参照
+    /// * `index` - エントリのインデックス
+    /// * `password` - 暗号化されたエントリを復号するパスワード
+    ///
+    /// # Errors
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   暗号化されているが、パスワードが指定されていない。
+    /// - `VirtualDirError::WrongPassword`:
+    ///   パスワードが間違っている。
+    fn open_zip_entry<'z, R: Read + Seek>(
+        &self,
+        zip: &'z mut ZipArchive<R>,
+        index: usize,
+        password: Option<&str>,
+    ) -> Result<ZipFile<'z>> {
+        let (path, encrypted) = {
+            let entry = zip.by_index_raw(index)?;
+            let path = format!("/{}", self.decode_path(entry.name_raw()));
+            (path, entry.encrypted())
+        };
+        let entry = match (encrypted, password) {
+            (false, _) => zip.by_index(index)?,
+            (true, None) => bail!(VirtualDirError::PasswordRequired(path)),
+            (true, Some(pw)) => {
+                match zip.by_index_decrypt(index, pw.as_bytes()) {
+                    Err(ZipError::InvalidPassword) => {
+                        bail!(VirtualDirError::WrongPassword(path))
+                    }
+                    res => res?,
+                }
+            }
+        };
+        Ok(entry)
+    }
+
+    /// zip アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    /// * `password` - 暗号化されたエントリを復号するパスワード
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    ///
+    /// # Errors
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   暗号化されたエントリがあるが、パスワードが指定されていない。
+    /// - `VirtualDirError::WrongPassword`:
+    ///   パスワードが間違っている。
+    fn copy_zip_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+        password: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let mut skipped = Vec::new();
+        let file = File::open(archive)?;
+        let mut zip = ZipArchive::new(file)?;
+        let targets = self.zip_targets(&mut zip, srcs)?;
+        let mut bytes = 0;
+        for &i in &targets {
+            bytes += zip.by_index_raw(i)?.size();
+        }
+        self.job.set_total(bytes, targets.len() as u64);
+
+        for i in targets {
+            let entry = self.open_zip_entry(&mut zip, i, password)?;
+            let (raw, encrypted) =
+                (entry.name_raw().to_owned(), entry.encrypted());
+            let mut reader = ProgressReader::new(entry, self.job);
+            let res =
+                match self.extract_entry(&mut reader, archive, &raw, dest, cwd)
+                {
+                    Ok(res) => res,
+                    // ZipCrypto のパスワード検証は完全ではないため、
+                    // 間違ったパスワードでも通過することがある。
+                    // その場合は展開時にチェックサムの不一致として検出される。
+                    Err(err) if encrypted && self.is_invalid_data(&err) => {
+                        let path = format!("/{}", self.decode_path(&raw));
+                        bail!(VirtualDirError::WrongPassword(path))
+                    }
+                    Err(err) => return Err(err),
+                };
+            if !res.is_empty() {
+                skipped.push(res);
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// エラーが不正なデータの読み込みによるものか否かを確認する。
+    ///
+    /// # Arguments
+    /// * `err` - 確認するエラー
+    fn is_invalid_data(&self, err: &anyhow::Error) -> bool {
+        err.downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == ErrorKind::InvalidData)
+    }
+
+    /// tar/tgz アーカイブからエントリをコピーする処理の共通ロジック部分。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `tar` - tar インスタンス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_tarball_entries<R: Read>(
+        &self,
+        archive: &str,
+        tar: &mut tar::Archive<R>,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let mut skipped = Vec::new();
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let mut raw = entry.header().path_bytes().into_owned();
+            let str_p = String::from_utf8_lossy(&raw);
+            // USTAR ではファイル名が 100 バイトに制限されている。
+            // 途中で切れていそうなら path() にフォールバックする。
+            if (raw.len() >= 100 || str_p.contains('\0'))
+                && let Ok(path) = entry.path()
+            {
+                raw = path.into_owned().into_os_string().into_vec();
+            }
+            if !self.is_match(&raw, srcs) {
+                continue;
+            }
+            let res =
+                self.extract_entry(&mut entry, archive, &raw, dest, cwd)?;
+            if !res.is_empty() {
+                skipped.push(res);
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// tar 系のアーカイブファイルを、進捗を報告するリーダーとして開く。
+    ///
+    /// 読み込んだバイト数を進捗とするため、総バイト数はファイルサイズとなる。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    fn open_tarball(
+        &self,
+        archive: &str,
+    ) -> Result<BufReader<ProgressReader<'a, File>>> {
+        let file = File::open(archive)?;
+        self.job.set_total(file.metadata()?.len(), 0);
+        Ok(BufReader::new(ProgressReader::new(file, self.job)))
+    }
+
+    /// tar アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_tar_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let reader = self.open_tarball(archive)?;
+        let mut tar = tar::Archive::new(reader);
+        let skipped =
+            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
+        Ok(skipped)
+    }
+
+    /// tgz アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_tgz_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let reader = self.open_tarball(archive)?;
+        let decoder = GzDecoder::new(reader);
+        let mut tar = tar::Archive::new(decoder);
+        let skipped =
+            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
+        Ok(skipped)
+    }
+
+    /// txz アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_txz_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let reader = self.open_tarball(archive)?;
+        let decoder = XzDecoder::new(reader);
+        let mut tar = tar::Archive::new(decoder);
+        let skipped =
+            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
+        Ok(skipped)
+    }
+
+    /// tbz アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_tbz_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let reader = self.open_tarball(archive)?;
+        let decoder = BzDecoder::new(reader);
+        let mut tar = tar::Archive::new(decoder);
+        let skipped =
+            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
+        Ok(skipped)
+    }
+
+    /// tzst アーカイブからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_tzst_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let reader = self.open_tarball(archive)?;
+        let decoder = ZstdDecoder::with_buffer(reader)?;
+        let mut tar = tar::Archive::new(decoder);
+        let skipped =
+            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
+        Ok(skipped)
+    }
+
+    /// 7z のエントリのパスをバイト列で取得する。
+    ///
+    /// 他のアーカイブ形式に合わせて、区切り文字はスラッシュに統一し、
+    /// ディレクトリの場合は末尾にスラッシュを付ける。
+    ///
+    /// # Arguments
+    /// * `entry` - SevenZArchiveEntry の参照
+    fn seven_zip_raw(&self, entry: &SevenZArchiveEntry) -> Vec<u8> {
+        let mut path = entry.name().replace('\\', "/");
+        if entry.is_directory() && !path.ends_with('/') {
+            path.push('/');
+        }
+        path.into_bytes()
+    }
+
+    /// 7z アーカイブからエントリをコピーする。
+    ///
+    /// 7z はソリッドブロック単位で圧縮されており、
+    /// ブロック内のエントリは先頭から順に展開しなければならない。
+    /// そのため、コピー対象を含むブロックのみを一度だけ展開し、
+    /// 最後の対象エントリを書き出した時点でそのブロックの展開を打ち切る。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_7z_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let mut skipped = Vec::new();
+        let mut file = File::open(archive)?;
+        let len = file.metadata()?.len();
+        let sz = sevenz_rust::Archive::read(&mut file, len, &[])?;
+
+        // コピー対象のエントリのパスを求めておく。対象外は None となる。
+        let raws = sz
+            .files
+            .iter()
+            .map(|e| {
+                let raw = self.seven_zip_raw(e);
+                self.is_match(&raw, srcs).then_some(raw)
+            })
+            .collect::<Vec<_>>();
+
+        // 展開するブロックの範囲 (先頭と最後の対象エントリ) を求めておく。
+        // コピー対象を含まないブロックは None となり、展開しない。
+        let ranges = sz
+            .folders
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let first = sz.stream_map.folder_first_file_index[i];
+                let count = block.num_unpack_sub_streams;
+                (first..first + count)
+                    .rev()
+                    .find(|&j| sz.files[j].has_stream && raws[j].is_some())
+                    .map(|last| (first, last))
+            })
+            .collect::<Vec<_>>();
+
+        // 対象外のエントリも読み進めるため、その分も総バイト数に含める。
+        let bytes = ranges
+            .iter()
+            .flatten()
+            .flat_map(|&(first, last)| &sz.files[first..=last])
+            .map(|e| e.size)
+            .sum();
+        let files = raws.iter().filter(|r| r.is_some()).count();
+        self.job.set_total(bytes, files as u64);
+
+        for (i, range) in ranges.into_iter().enumerate() {
+            let Some((first, last)) = range else {
+                continue;
+            };
+            let mut index = first;
+            let mut res = Ok(());
+            let decoder = BlockDecoder::new(i, &sz, &[], &mut file);
+            decoder.for_each_entries(&mut |entry, reader| {
+                let j = index;
+                index += 1;
+                let mut reader = ProgressReader::new(reader, self.job);
+                match &raws[j] {
+                    Some(raw) if entry.has_stream => {
+                        match self.extract_entry(
+                            &mut reader,
+                            archive,
+                            raw,
+                            dest,
+                            cwd,
+                        ) {
+                            Ok(p) if !p.is_empty() => skipped.push(p),
+                            Ok(_) => {}
+                            Err(err) => {
+                                res = Err(err);
+                                return Ok(false);
+                            }
+                        }
+                        // 上限までしか読んでいない場合も、
+                        // 後続を展開するために読み進める。
+                        if j < last {
+                            copy(&mut reader, &mut sink())?;
+                        }
+                    }
+                    // 対象外のエントリも、後続を展開するために読み進める。
+                    _ => {
+                        copy(&mut reader, &mut sink())?;
+                    }
+                }
+                Ok(j < last)
+            })?;
+            res?;
+        }
+
+        // ストリームを持たないエントリ (ディレクトリや空ファイル) は
+        // どのブロックにも含まれないため、最後にまとめて作成する。
+        for (entry, raw) in sz.files.iter().zip(&raws) {
+            let Some(raw) = raw.as_ref().filter(|_| !entry.has_stream) else {
+                continue;
+            };
+            let res =
+                self.extract_entry(&mut empty(), archive, raw, dest, cwd)?;
+            if !res.is_empty() {
+                skipped.push(res);
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// ISO イメージからエントリをコピーする。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `srcs` - コピーするパスの配列
+    /// * `dest` - 展開先ディレクトリ
+    /// * `cwd` - 基準となるディレクトリ
+    ///
+    /// # Returns
+    /// スキップされたパスの配列
+    fn copy_iso_entries(
+        &self,
+        archive: &str,
+        srcs: &[String],
+        dest: &str,
+        cwd: &str,
+    ) -> Result<Vec<String>> {
+        let mut skipped = Vec::new();
+        let iso = Iso::new(archive, "")?;
+        let records = iso
+            .records()
+            .iter()
+            .filter(|r| self.is_match(&r.path, srcs))
+            .collect::<Vec<_>>();
+        let bytes = records.iter().map(|r| r.size).sum();
+        self.job.set_total(bytes, records.len() as u64);
+
+        for record in records {
+            let mut reader = ProgressReader::new(iso.open(record), self.job);
+            let res = self.extract_entry(
+                &mut reader,
+                archive,
+                &record.path,
+                dest,
+                cwd,
+            )?;
+            if !res.is_empty() {
+                skipped.push(res);
+            }
+        }
+        Ok(skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{errors::FsError, test_helpers::assert_err};
+
+    use std::fs;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_progress() -> Result<()> {
+        let list = [
+            (ArchiveKind::Zip, "dir-entries.zip"),
+            (ArchiveKind::SevenZip, "dir-entries.7z"),
+            (ArchiveKind::Iso, "dir-entries.iso"),
+        ];
+        for (kind, name) in list {
+            let dest = tempdir()?.keep().to_string_lossy().to_string();
+            let archive = format!("./tests/archives/{name}");
+            let srcs = ["/2 movies".to_owned()];
+            let job = ProgressJob::new();
+            let skipped = Extractor::new(&job)
+                .extract(&kind, &archive, &srcs, &dest, &archive, None)?;
+            assert!(skipped.is_empty());
+            assert_eq!(job.percent(), 100);
+            assert!(
+                Path::new(&format!("{dest}/2 movies/1 text.txt")).is_file()
+            );
+            fs::remove_dir_all(&dest)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_aborted() -> Result<()> {
+        let list = [
+            (ArchiveKind::Zip, "dir-entries.zip"),
+            (ArchiveKind::Tgz, "dir-entries.tgz"),
+            (ArchiveKind::SevenZip, "dir-entries.7z"),
+            (ArchiveKind::Iso, "dir-entries.iso"),
+        ];
+        for (kind, name) in list {
+            let dest = tempdir()?.keep().to_string_lossy().to_string();
+            let archive = format!("./tests/archives/{name}");
+            let srcs = ["/2 movies".to_owned()];
+            let job = ProgressJob::new();
+            job.abort();
+
+            // 中止した場合は何も展開されない。
+            let res = Extractor::new(&job)
+                .extract(&kind, &archive, &srcs, &dest, &archive, None);
+            assert_err(&res.unwrap_err(), &FsError::Aborted);
+            assert_eq!(fs::read_dir(&dest)?.count(), 0);
+            fs::remove_dir_all(&dest)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    helpers::{decode_string, is_text},
+    misc::{ProgressJob, SearchQuery},
+    models::{EntryKind, GrepMatch},
+};
+
+use anyhow::Result;
+use regex::Regex;
+use std::{fs, path::Path};
+use unicode_normalization::UnicodeNormalization as _;
+
+/// 検索対象とするファイルサイズの上限 (バイト)。
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// テキストか否かの判定に使用する、ファイル先頭のバイト数。
+const INSPECT_SIZE: usize = 4096;
+
+/// 行の内容として返す最大文字数。
+const MAX_SNIPPET: usize = 200;
+
+/// 長い行を切り詰める際に、マッチした位置より前に残す文字数。
+const SNIPPET_CONTEXT: usize = 40;
+
+/// ディレクトリツリー内のファイルの内容を検索する構造体。
+///
+/// ファイルの文字コードはプレビューと同様に自動判定されるため、
+/// Shift_JIS や EUC-JP のファイルも検索できる。
+/// バイナリファイルと巨大なファイルは対象外。
+/// マッチした行は GrepMatch として ProgressJob に逐次追加される。
+///
+/// # Fields
+/// * `query` - 検索対象とするファイルの条件
+/// * `regex` - 行にマッチさせる正規表現
+pub struct Grepper {
+    query: SearchQuery,
+    regex: Regex,
+}
+
+impl Grepper {
+    /// 新しい Grepper インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `query` - 検索対象とするファイルの条件
+    ///   エントリの種類は常にファイルとなる。
+    /// * `regex` - 行にマッチさせる正規表現
+    pub fn new(query: SearchQuery, regex: Regex) -> Self {
+        let query = SearchQuery {
+            kind: Some(EntryKind::File),
+            ..query
+        };
+        Self { query, regex }
+    }
+
+    /// 指定したディレクトリの配下のファイルの内容を検索する。
+    ///
+    /// # Arguments
+    /// * `root` - 検索の起点となるディレクトリ
+    /// * `job` - 進捗状況
+    ///
+    /// # Returns
+    /// マッチした行の数と、マッチした行を含むファイルの数のタプル
+    ///
+    /// # Errors
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn grep(&self, root: &Path, job: &ProgressJob) -> Result<(u64, u64)> {
+        let (mut lines, mut files) = (0, 0);
+        self.query.walk(root, job, |path, rel| {
+            let n = self.grep_file(path, rel, job)?;
+            lines += n;
+            files += u64::from(n > 0);
+            Ok(())
+        })?;
+        Ok((lines, files))
+    }
+
+    /// ひとつのファイルの内容を検索する。
+    ///
+    /// 読み込めないファイルやテキストでないファイルは無視する。
+    ///
+    /// # Arguments
+    /// * `path` - ファイルのパス
+    /// * `rel` - 検索の起点からの相対パス
+    /// * `job` - 進捗状況
+    ///
+    /// # Returns
+    /// マッチした行の数
+    fn grep_file(
+        &self,
+        path: &Path,
+        rel: &Path,
+        job: &ProgressJob,
+    ) -> Result<u64> {
+        let Ok(meta) = fs::metadata(path) else {
+            return Ok(0);
+        };
+        if meta.len() > MAX_FILE_SIZE {
+            return Ok(0);
+        }
+        let Ok(bytes) = fs::read(path) else {
+            return Ok(0);
+        };
+        if !is_text(&bytes[..bytes.len().min(INSPECT_SIZE)]) {
+            return Ok(0);
+        }
+        let text = decode_string(&bytes);
+        let rel = rel.to_string_lossy().nfc().to_string();
+        let mut count = 0;
+        for (i, line) in text.lines().enumerate() {
+            job.check()?;
+            let Some(m) = self.regex.find(line) else {
+                continue;
+            };
+            let item = GrepMatch {
+                path: rel.clone(),
+                line: i + 1,
+                text: snippet(line, m.start()),
+            };
+            job.emit(serde_json::to_value(item)?);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// 行の内容を、返す長さに切り詰める。
+///
+/// # Arguments
+/// * `line` - 行の内容
+/// * `start` - マッチした位置 (バイト単位)
+fn snippet(line: &str, start: usize) -> String {
+    let line = line.trim_end_matches('\r');
+    if line.chars().count() <= MAX_SNIPPET {
+        return line.to_owned();
+    }
+    let start = start.min(line.len());
+    let skip = line[..start]
+        .chars()
+        .count()
+        .saturating_sub(SNIPPET_CONTEXT);
+    line.chars().skip(skip).take(MAX_SNIPPET).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::FsError,
+        test_helpers::{assert_err, setup_resources, teardown_resources},
+    };
+
+    use encoding_rs::{EUC_JP, SHIFT_JIS};
+    use serde_json::Value;
+
+    use super::*;
+
+    fn grep(path: &str, pattern: &str) -> Result<Vec<Value>> {
+        let job = ProgressJob::new();
+        let regex = SearchQuery::regex(pattern, false)?;
+        let grepper = Grepper::new(SearchQuery::default(), regex);
+        let (lines, _) = grepper.grep(Path::new(path), &job)?;
+        let mut items = job.take_items();
+        items.sort_by_key(|v| v["path"].as_str().unwrap().to_owned());
+        assert_eq!(items.len() as u64, lines);
+        Ok(items)
+    }
+
+    #[tokio::test]
+    async fn test_grep() -> Result<()> {
+        let path = setup_resources("foo\nbar\r\nfoobar").await?;
+        fs::write(format!("{path}/test1/test1.txt"), "xfoo")?;
+        fs::write(format!("{path}/test2/bin"), b"foo\0bar")?;
+        let items = grep(&path, "foo")?;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0]["path"], "test.txt");
+        assert_eq!(items[0]["line"], 1);
+        assert_eq!(items[1]["line"], 3);
+        assert_eq!(items[1]["text"], "foobar");
+        assert_eq!(items[2]["path"], "test1/test1.txt");
+        let items = grep(&path, "bar$")?;
+        assert_eq!(items[0]["line"], 2);
+        assert_eq!(items[0]["text"], "bar");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grep_encoding() -> Result<()> {
+        let path = setup_resources("").await?;
+        let (sjis, _, _) = SHIFT_JIS.encode("一行目\n日本語のテキストです");
+        fs::write(format!("{path}/test1/sjis.txt"), sjis)?;
+        let (euc, _, _) = EUC_JP.encode("テキストファイル");
+        fs::write(format!("{path}/test2/euc.txt"), euc)?;
+        let items = grep(&path, "テキスト")?;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["path"], "test1/sjis.txt");
+        assert_eq!(items[0]["line"], 2);
+        assert_eq!(items[0]["text"], "日本語のテキストです");
+        assert_eq!(items[1]["text"], "テキストファイル");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grep_abort() -> Result<()> {
+        let path = setup_resources("foo").await?;
+        let job = ProgressJob::new();
+        job.abort();
+        let regex = SearchQuery::regex("foo", false)?;
+        let grepper = Grepper::new(SearchQuery::default(), regex);
+        let res = grepper.grep(Path::new(&path), &job);
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("foo\r", 0), "foo");
+        let line = format!("{}foo{}", "あ".repeat(100), "い".repeat(200));
+        let s = snippet(&line, "あ".repeat(100).len());
+        assert_eq!(s.chars().count(), MAX_SNIPPET);
+        assert!(s.starts_with(&format!("{}foo", "あ".repeat(SNIPPET_CONTEXT))));
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::Path;
+use syntect::{
+    html::{ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// ハイライトするテキストの上限 (バイト)。
+/// これを超える場合はハイライトせず、プレーンテキストとして扱う。
+const MAX_TEXT_SIZE: usize = 256 * 1024;
+
+/// ハイライトの要素に付与する CSS クラス名のプレフィックス。
+/// 例： `<span class="hl-keyword hl-control hl-rust">`
+const CLASS_PREFIX: &str = "hl-";
+
+/// ソースコードをシンタックスハイライトした HTML に変換する構造体。
+///
+/// 色は直接指定せず CSS クラスのみを付与するため、
+/// `/config/css` で配信されるスタイルシートでテーマを変更できる。
+///
+/// # Fields
+/// * `syntax_set` - 組み込みの構文定義
+pub struct Highlighter {
+    syntax_set: SyntaxSet,
+}
+
+impl Highlighter {
+    /// 新しい Highlighter インスタンスを作成する。
+    ///
+    /// 構文定義の読み込みには時間がかかるため、
+    /// シングルトンとして使用される。
+    pub fn new() -> Self {
+        Self {
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+        }
+    }
+
+    /// テキストをシンタックスハイライトした HTML に変換する。
+    ///
+    /// 構文は以下の順で判別する。
+    /// 1. ユーザーのカスタム MIME タイプ (例： text/x-python -> python)
+    /// 2. ファイル名 (例： Makefile) と拡張子
+    /// 3. 先頭行 (シバンなど)
+    ///
+    /// # Arguments
+    /// * `text` - 変換するテキスト
+    /// * `path` - テキストのファイルパス
+    /// * `mime` - ユーザーのカスタム MIME タイプ
+    ///
+    /// # Returns
+    /// HTML エスケープ済みの HTML
+    /// 構文を判別できない、またはテキストが大きすぎる場合は None
+    pub fn highlight(
+        &self,
+        text: &str,
+        path: &Path,
+        mime: Option<&str>,
+    ) -> Option<String> {
+        if text.len() > MAX_TEXT_SIZE {
+            return None;
+        }
+        let syntax = self.find_syntax(text, path, mime)?;
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            &self.syntax_set,
+            ClassStyle::SpacedPrefixed {
+                prefix: CLASS_PREFIX,
+            },
+        );
+        for line in LinesWithEndings::from(text) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(generator.finalize())
+    }
+
+    /// テキストの構文を判別する。
+    ///
+    /// プレーンテキストと判別された場合は None を返す。
+    ///
+    /// # Arguments
+    /// * `text` - 判別するテキスト
+    /// * `path` - テキストのファイルパス
+    /// * `mime` - ユーザーのカスタム MIME タイプ
+    fn find_syntax(
+        &self,
+        text: &str,
+        path: &Path,
+        mime: Option<&str>,
+    ) -> Option<&SyntaxReference> {
+        let ss = &self.syntax_set;
+        let by_mime = || {
+            // サブタイプの x- などを除いた部分を、構文名や拡張子として探す。
+            let (_, subtype) = mime?.split_once('/')?;
+            let token = subtype.trim_start_matches("x-");
+            let token = token.split(['+', ';']).next()?;
+            ss.find_syntax_by_token(token)
+        };
+        let by_name = || {
+            let name = path.file_name()?.to_str()?;
+            ss.find_syntax_by_extension(name).or_else(|| {
+                let ext = path.extension()?.to_str()?;
+                ss.find_syntax_by_extension(ext)
+            })
+        };
+        let by_first_line = || {
+            let line = text.lines().next()?;
+            ss.find_syntax_by_first_line(line)
+        };
+        let syntax = by_mime().or_else(by_name).or_else(by_first_line)?;
+        (syntax.name != ss.find_syntax_plain_text().name).then_some(syntax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let highlighter = Highlighter::new();
+
+        // 拡張子から判別する。
+        let html = highlighter
+            .highlight("fn main() {}\n", Path::new("/a/main.rs"), None)
+            .unwrap();
+        assert!(html.contains(r#"<span class="hl-source hl-rust">"#));
+        assert!(html.contains(r#"class="hl-storage hl-type hl-function"#));
+
+        // ファイル名から判別する。
+        let html = highlighter
+            .highlight("all:\n", Path::new("/a/Makefile"), None)
+            .unwrap();
+        assert!(html.contains("hl-makefile"));
+
+        // シバンから判別する。
+        let text = "#!/usr/bin/env python3\nprint('<a>')\n";
+        let html = highlighter
+            .highlight(text, Path::new("/a/script"), None)
+            .unwrap();
+        assert!(html.contains("hl-python"));
+        assert!(html.contains("&lt;a&gt;"));
+
+        // ユーザーのカスタム MIME タイプを優先する。
+        let html = highlighter
+            .highlight("{}\n", Path::new("/a/foo.txt"), Some("text/x-json"))
+            .unwrap();
+        assert!(html.contains("hl-json"));
+    }
+
+    #[test]
+    fn test_highlight_plain() {
+        let highlighter = Highlighter::new();
+
+        // 構文を判別できない。
+        let path = Path::new("/a/readme");
+        assert!(highlighter.highlight("hello\n", path, None).is_none());
+        let path = Path::new("/a/readme.txt");
+        assert!(highlighter.highlight("hello\n", path, None).is_none());
+
+        // 大きすぎる。
+        let text = "let a = 1;\n".repeat(MAX_TEXT_SIZE / 10);
+        let path = Path::new("/a/main.rs");
+        assert!(highlighter.highlight(&text, path, None).is_none());
+    }
+}
@@ -115,23 +115,33 @@ impl Ls {
         let mut entries: Vec<_> = fs::read_dir(path)?.flatten().collect();
         entries.sort_by_key(|e| e.file_name());
         for entry in entries {
-            let mut ent = self.entry_skeleton(&entry.file_name());
-            let Ok(meta) = entry.metadata() else {
-                res.push(ent);
-                continue;
-            };
-            ent.perm = perm_string_from_meta(&meta);
-            ent.size = ls_style_size(meta.len());
-            let dt = Local.timestamp_opt(meta.ctime(), 0).unwrap();
-            ent.time = dt.format(&self.time_style).to_string();
-            if meta.is_symlink() {
-                ent.link = self.resolve_symlink(&entry.path());
-            }
-            res.push(ent);
+            res.push(self.entry(&entry.path()));
         }
         Ok(res)
     }
 
+    /// 単一のエントリ情報を取得する。
+    ///
+    /// シンボリックリンクは辿らない。
+    /// メタデータを取得できなかった場合は、名前以外デフォルト値となる。
+    ///
+    /// # Arguments
+    /// * `path` - エントリのパス
+    pub fn entry(&self, path: &Path) -> Entry {
+        let mut ent = self.entry_skeleton(path.file_name().unwrap_or_default());
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return ent;
+        };
+        ent.perm = perm_string_from_meta(&meta);
+        ent.size = ls_style_size(meta.len());
+        let dt = Local.timestamp_opt(meta.ctime(), 0).unwrap();
+        ent.time = dt.format(&self.time_style).to_string();
+        if meta.is_symlink() {
+            ent.link = self.resolve_symlink(path);
+        }
+        ent
+    }
+
     /// ディレクトリの署名を生成する。
     ///
     /// ここで言う署名とは、各エントリの変更時刻を連結した文字列。
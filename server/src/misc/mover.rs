@@ -0,0 +1,354 @@
+use crate::{
+    errors::FsError,
+    helpers::temp_path,
+    misc::{
+        Copier, ProgressJob, is_replacement, replace_entry, resolve_conflict,
+    },
+    models::ConflictPolicy,
+};
+
+use anyhow::{Result, bail};
+use std::{
+    fs::{self, Metadata},
+    io::{self, ErrorKind},
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+};
+
+/// ファイルやディレクトリツリーを移動する構造体。
+///
+/// 移動元と移動先が同じデバイス上にある場合は `rename` で移動し、
+/// 異なるデバイスの場合はコピーした後に移動元を削除する。
+/// 進捗状況は ProgressJob に報告される。
+///
+/// # Fields
+/// * `policy` - 移動先に同名のエントリが存在する場合の処理方針
+/// * `job` - 進捗状況
+pub struct Mover<'a> {
+    policy: ConflictPolicy,
+    job: &'a ProgressJob,
+}
+
+impl<'a> Mover<'a> {
+    /// 新しい Mover インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `policy` - 移動先に同名のエントリが存在する場合の処理方針
+    /// * `job` - 進捗状況
+    pub fn new(policy: ConflictPolicy, job: &'a ProgressJob) -> Self {
+        Self { policy, job }
+    }
+
+    /// エントリを指定したディレクトリの直下に移動する。
+    ///
+    /// 移動元と同じディレクトリが指定された場合は何もしない。
+    ///
+    /// # Arguments
+    /// * `src` - 移動元のパス
+    /// * `dest` - 移動先のディレクトリ
+    ///
+    /// # Returns
+    /// 移動した場合は移動先のパス、スキップした場合は None
+    ///
+    /// # Errors
+    /// - `FsError::IntoItself`:
+    ///   ディレクトリを自身の配下に移動しようとした。
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn mv(&mut self, src: &Path, dest: &Path) -> Result<Option<PathBuf>> {
+        let Some(name) = src.file_name() else {
+            bail!("Invalid path: {}", src.display());
+        };
+        if dest.starts_with(src) && src.is_dir() {
+            bail!(FsError::IntoItself(src.to_string_lossy().to_string()));
+        }
+        if src.parent() == Some(dest) {
+            self.skip(src)?;
+            return Ok(None);
+        }
+        self.move_entry(src, &dest.join(name))
+    }
+
+    /// エントリを移動する。
+    ///
+    /// 移動先に同名のディレクトリが存在してマージする場合は、
+    /// 配下のエントリを再帰的に移動し、空になった移動元を削除する。
+    ///
+    /// # Arguments
+    /// * `src` - 移動元のパス
+    /// * `dst` - 移動先のパス
+    ///
+    /// # Returns
+    /// 移動した場合は移動先のパス、スキップした場合は None
+    pub fn move_entry(
+        &mut self,
+        src: &Path,
+        dst: &Path,
+    ) -> Result<Option<PathBuf>> {
+        self.job.check()?;
+        let meta = fs::symlink_metadata(src)?;
+        let Some(dst) = resolve_conflict(self.policy, src, &meta, dst)? else {
+            self.skip(src)?;
+            return Ok(None);
+        };
+        if meta.is_dir() && dst.is_dir() {
+            self.merge_dir(src, &dst)?;
+        } else if self.same_device(&meta, &dst)? {
+            let (bytes, files) = Copier::measure(&[src])?;
+            let res = match is_replacement(&meta, &dst) {
+                true => replace_entry(src, &dst),
+                false => fs::rename(src, &dst).map_err(Into::into),
+            };
+            match res {
+                Ok(()) => {
+                    self.job.add_bytes(bytes);
+                    self.job.add_files(files);
+                }
+                // マウントポイントを跨ぐ場合など、
+                // デバイス番号が同じでも rename できないことがある。
+                Err(err)
+                    if err.downcast_ref::<io::Error>().is_some_and(|e| {
+                        e.kind() == ErrorKind::CrossesDevices
+                    }) =>
+                {
+                    self.move_across(src, &dst)?;
+                }
+                Err(err) => return Err(err),
+            }
+        } else {
+            self.move_across(src, &dst)?;
+        }
+        Ok(Some(dst))
+    }
+
+    /// ディレクトリの配下を既存のディレクトリにマージする。
+    ///
+    /// スキップしたエントリが残っている場合、移動元のディレクトリは削除しない。
+    ///
+    /// # Arguments
+    /// * `src` - 移動元のパス
+    /// * `dst` - 移動先のパス
+    fn merge_dir(&mut self, src: &Path, dst: &Path) -> Result<()> {
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            self.move_entry(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        if fs::read_dir(src)?.next().is_none() {
+            fs::remove_dir(src)?;
+        }
+        self.job.add_files(1);
+        Ok(())
+    }
+
+    /// コピーした後に移動元を削除することで、デバイスを跨いで移動する。
+    ///
+    /// 既存のエントリを上書きする場合は、一時的な名前でコピーしてから置き換える。
+    /// コピーが中止または失敗した場合は、コピー済みのエントリを削除し、
+    /// 移動元と既存のエントリには手を付けない。
+    ///
+    /// # Arguments
+    /// * `src` - 移動元のパス
+    /// * `dst` - 移動先のパス
+    fn move_across(&mut self, src: &Path, dst: &Path) -> Result<()> {
+        let meta = fs::symlink_metadata(src)?;
+        let replace = is_replacement(&meta, dst);
+        let tmp = if replace {
+            temp_path(dst)
+        } else {
+            dst.to_owned()
+        };
+        let mut copier = Copier::new(self.policy, self.job);
+        let res = copier.copy_entry(src, &tmp).and_then(|_| match replace {
+            true => replace_entry(&tmp, dst),
+            false => Ok(()),
+        });
+        if let Err(err) = res {
+            let _ = remove_entry(&tmp);
+            return Err(err);
+        }
+        remove_entry(src)
+    }
+
+    /// 移動元と移動先が同じデバイス上にあるか否かを判定する。
+    ///
+    /// # Arguments
+    /// * `meta` - 移動元のメタデータ
+    /// * `dst` - 移動先のパス
+    fn same_device(&self, meta: &Metadata, dst: &Path) -> Result<bool> {
+        let parent = dst.parent().unwrap_or(dst);
+        Ok(fs::metadata(parent)?.dev() == meta.dev())
+    }
+
+    /// スキップしたエントリを処理済みとして扱う。
+    ///
+    /// # Arguments
+    /// * `src` - 移動元のパス
+    fn skip(&self, src: &Path) -> Result<()> {
+        let (bytes, files) = Copier::measure(&[src])?;
+        self.job.add_bytes(bytes);
+        self.job.add_files(files);
+        Ok(())
+    }
+}
+
+/// エントリを削除する。
+///
+/// ディレクトリの場合は配下も含めて削除し、シンボリックリンクは辿らない。
+///
+/// # Arguments
+/// * `path` - 削除するパス
+pub fn remove_entry(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, count_entries, setup_resources, teardown_resources,
+    };
+
+    use std::{fs::File, sync::Arc, thread};
+
+    use super::*;
+
+    async fn setup() -> Result<(String, Arc<ProgressJob>)> {
+        let path = setup_resources("test").await?;
+        Ok((path, ProgressJob::new()))
+    }
+
+    #[tokio::test]
+    async fn test_mv_tree() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        job.set_total(4, 3);
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        let dst = mover.mv(&p.join("test1"), &p.join("test3"))?;
+        assert_eq!(dst, Some(p.join("test3/test1")));
+        mover.mv(&p.join("test.txt"), &p.join("test3"))?;
+        assert!(!p.join("test1").exists());
+        assert!(!p.join("test.txt").exists());
+        assert_eq!(count_entries(&[format!("{path}/test3")])?, 4);
+        assert_eq!(job.percent(), 100);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mv_into_itself() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        let res = mover.mv(&p.join("test1"), &p.join("test1"));
+        assert_err(&res.unwrap_err(), &FsError::IntoItself("".to_owned()));
+        assert_eq!(mover.mv(&p.join("test.txt"), p)?, None);
+        assert!(p.join("test.txt").is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mv_conflict() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let dst = p.join("test3/test.txt");
+        fs::write(&dst, "old")?;
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        assert_eq!(mover.mv(&p.join("test.txt"), &p.join("test3"))?, None);
+        assert_eq!(fs::read_to_string(&dst)?, "old");
+        assert!(p.join("test.txt").is_file());
+        let mut mover = Mover::new(ConflictPolicy::Overwrite, &job);
+        mover.mv(&p.join("test.txt"), &p.join("test3"))?;
+        assert_eq!(fs::read_to_string(&dst)?, "test");
+        assert!(!p.join("test.txt").exists());
+
+        // ファイルはディレクトリで置き換えられる。
+        fs::write(p.join("test3/test1"), "old")?;
+        mover.mv(&p.join("test1"), &p.join("test3"))?;
+        assert!(p.join("test3/test1/test1.txt").is_file());
+        assert!(!p.join("test1").exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mv_merge() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        fs::create_dir(p.join("test3/test1"))?;
+        fs::write(p.join("test3/test1/other.txt"), "")?;
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        mover.mv(&p.join("test1"), &p.join("test3"))?;
+        assert!(p.join("test3/test1/test1.txt").is_file());
+        assert!(p.join("test3/test1/other.txt").is_file());
+        assert!(!p.join("test1").exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_across() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        mover.move_across(&p.join("test1"), &p.join("test3/test1"))?;
+        assert!(p.join("test3/test1/test1.txt").is_file());
+        assert!(!p.join("test1").exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_across_rollback() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        File::create(p.join("test1/large"))?.set_len(64 * 1024 * 1024)?;
+        // 書き込みが始まったら中止する。
+        let job_ = job.clone();
+        let handle = thread::spawn(move || {
+            while job_.detail()["bytes"] == 0 {
+                thread::yield_now();
+            }
+            job_.abort();
+        });
+        let mut mover = Mover::new(ConflictPolicy::Skip, &job);
+        let res = mover.move_across(&p.join("test1"), &p.join("test3/test1"));
+        handle.join().unwrap();
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        assert!(!p.join("test3/test1").exists());
+        assert!(p.join("test1/large").is_file());
+        assert!(p.join("test1/test1.txt").is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_across_rollback_replace() -> Result<()> {
+        let (path, job) = setup().await?;
+        let p = Path::new(&path);
+        File::create(p.join("test1/large"))?.set_len(64 * 1024 * 1024)?;
+        fs::write(p.join("test3/test1"), "old")?;
+        // 書き込みが始まったら中止する。
+        let job_ = job.clone();
+        let handle = thread::spawn(move || {
+            while job_.detail()["bytes"] == 0 {
+                thread::yield_now();
+            }
+            job_.abort();
+        });
+        let mut mover = Mover::new(ConflictPolicy::Overwrite, &job);
+        let res = mover.move_across(&p.join("test1"), &p.join("test3/test1"));
+        handle.join().unwrap();
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        // 既存のエントリは元のまま残り、一時的なエントリは残らない。
+        assert_eq!(fs::read_to_string(p.join("test3/test1"))?, "old");
+        assert_eq!(fs::read_dir(p.join("test3"))?.count(), 1);
+        assert!(p.join("test1/large").is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
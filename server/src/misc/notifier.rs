@@ -0,0 +1,117 @@
+use anyhow::Result;
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    recommended_watcher,
+};
+use std::{path::Path, time::Duration};
+use tokio::{
+    sync::mpsc,
+    time::{Instant, timeout},
+};
+
+/// 最後のイベントからこの時間が経過したら変更を確定する。
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// イベントが途切れない場合でも、この時間が経過したら変更を確定する。
+const MAX_DELAY: Duration = Duration::from_millis(1000);
+
+/// OS のファイルシステム通知 (Linux であれば inotify) を扱う構造体。
+///
+/// 監視対象ディレクトリ直下の変更のみを検知する。
+/// 短時間に連続するイベントはひとつにまとめられる。
+///
+/// # Fields
+/// * `_watcher` - ファイルシステム監視オブジェクト
+///   破棄されると監視も終了するため、保持しておく必要がある。
+/// * `rx` - 変更イベントの受信チャネル
+pub struct Notifier {
+    _watcher: RecommendedWatcher,
+    rx: mpsc::UnboundedReceiver<()>,
+}
+
+impl Notifier {
+    /// 新しい Notifier インスタンスを作成し、監視を開始する。
+    ///
+    /// # Arguments
+    /// * `path` - 監視するディレクトリのパス
+    ///
+    /// # Errors
+    /// 通知機能が利用できない場合や、監視の登録に失敗した場合。
+    pub fn new(path: &str) -> Result<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            recommended_watcher(move |res: notify::Result<Event>| {
+                // 読み取りだけではエントリ一覧に影響しないため無視する。
+                // エラーの場合は、念のため変更ありとして扱う。
+                if let Ok(Event {
+                    kind: EventKind::Access(_),
+                    ..
+                }) = res
+                {
+                    return;
+                }
+                let _ = tx.send(());
+            })?;
+        watcher.watch(Path::new(path), RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// 変更が発生するまで待機する。
+    ///
+    /// 変更を検知した後、イベントが `DEBOUNCE` の間途切れるか、
+    /// 最初のイベントから `MAX_DELAY` が経過するまで待ってから返る。
+    ///
+    /// # Returns
+    /// 変更あり：true、監視が終了した：false
+    pub async fn changed(&mut self) -> bool {
+        if self.rx.recv().await.is_none() {
+            return false;
+        }
+        let deadline = Instant::now() + MAX_DELAY;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let wait = DEBOUNCE.min(deadline - now);
+            match timeout(wait, self.rx.recv()).await {
+                Ok(Some(())) => continue,
+                // 監視が終了していても、受信済みの変更は通知する。
+                Ok(None) | Err(_) => break,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{setup_resources, teardown_resources};
+
+    use tokio::fs;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_changed() -> Result<()> {
+        let path = setup_resources("").await?;
+        let mut notifier = Notifier::new(&path)?;
+        fs::write(format!("{path}/new1.txt"), "").await?;
+        fs::write(format!("{path}/new2.txt"), "").await?;
+        let res = timeout(Duration::from_secs(3), notifier.changed()).await;
+        assert_eq!(res, Ok(true));
+        // 連続したイベントはまとめられるため、次の変更は届かない。
+        let res = timeout(Duration::from_millis(300), notifier.changed()).await;
+        assert!(res.is_err());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_error() {
+        assert!(Notifier::new("/path/to/nowhere").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+use crate::{
+    errors::{FsError, VirtualDirError},
+    helpers::temp_path,
+    misc::{Copier, ProgressJob, ProgressReader},
+    models::ArchiveKind,
+};
+
+use anyhow::{Result, bail};
+use bzip2::write::BzEncoder;
+use chrono::{Datelike as _, Local, TimeZone as _, Timelike as _};
+use flate2::{Compression, write::GzEncoder};
+use rustix::fs::{CWD, RenameFlags, renameat_with};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Seek, Write},
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+};
+use tar::{EntryType, Header, HeaderMode};
+use unicode_normalization::UnicodeNormalization as _;
+use xz2::write::XzEncoder;
+use zip::{CompressionMethod, DateTime, ZipWriter, write::SimpleFileOptions};
+use zstd::stream::write::Encoder as ZstdEncoder;
+
+/// 圧縮レベルが指定されなかった場合の既定値。
+const DEFAULT_LEVEL: u32 = 6;
+
+/// ファイルやディレクトリツリーからアーカイブを作成する構造体。
+///
+/// パーミッションと更新日時を保持し、シンボリックリンクはリンクのまま格納する。
+/// 進捗状況は ProgressJob に報告される。
+///
+/// # Fields
+/// * `kind` - アーカイブの種類
+/// * `level` - 圧縮レベル (0-9)
+/// * `job` - 進捗状況
+pub struct Packer<'a> {
+    kind: ArchiveKind,
+    level: u32,
+    job: &'a ProgressJob,
+}
+
+impl<'a> Packer<'a> {
+    /// 新しい Packer インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `level` - 圧縮レベル (0-9)
+    ///   None の場合は既定値となる。tar では無視される。
+    /// * `job` - 進捗状況
+    pub fn new(
+        kind: ArchiveKind,
+        level: Option<u32>,
+        job: &'a ProgressJob,
+    ) -> Self {
+        Self {
+            kind,
+            level: level.unwrap_or(DEFAULT_LEVEL).min(9),
+            job,
+        }
+    }
+
+    /// エントリをまとめてアーカイブを作成する。
+    ///
+    /// 各エントリは名前をルートとして格納される。
+    /// アーカイブは同じディレクトリの一時ファイルに書き出され、
+    /// 完了後に既存のエントリを上書きしない方法で公開される。
+    /// 一時ファイルは最後に削除される。
+    ///
+    /// # Arguments
+    /// * `srcs` - 格納するエントリのパスの配列
+    /// * `dest` - 作成するアーカイブのパス
+    ///
+    /// # Errors
+    /// - `FsError::AlreadyExists`:
+    ///   作成するアーカイブがすでに存在する。
+    ///   書き出している間に作成された場合も含む。
+    /// - `FsError::IntoItself`:
+    ///   ディレクトリを自身の配下のアーカイブに格納しようとした。
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    /// - `VirtualDirError::UnsupportedArchive`:
+    ///   作成に対応していないアーカイブ形式である。
+    pub fn pack(&self, srcs: &[String], dest: &Path) -> Result<()> {
+        if matches!(self.kind, ArchiveKind::SevenZip | ArchiveKind::Iso) {
+            bail!(VirtualDirError::UnsupportedArchive);
+        }
+        if fs::symlink_metadata(dest).is_ok() {
+            let dest = dest.to_string_lossy().to_string();
+            bail!(FsError::AlreadyExists(dest));
+        }
+        for src in srcs {
+            if dest.starts_with(src) && Path::new(src).is_dir() {
+                bail!(FsError::IntoItself(src.to_owned()));
+            }
+        }
+        let (bytes, files) = Copier::measure(srcs)?;
+        self.job.set_total(bytes, files);
+
+        let tmp = temp_path(dest);
+        let res = self.write(srcs, &tmp).and_then(|_| publish(&tmp, dest));
+        let _ = fs::remove_file(&tmp);
+        if let Err(err) = res {
+            // 読み込み中に中止を検知した場合は io::Error に包まれているため、
+            // ここで改めて中止エラーとして返す。
+            self.job.check()?;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// アーカイブを書き出す。
+    ///
+    /// # Arguments
+    /// * `srcs` - 格納するエントリのパスの配列
+    /// * `path` - 書き出し先のパス
+    fn write(&self, srcs: &[String], path: &Path) -> Result<()> {
+        let out = BufWriter::new(File::create(path)?);
+        let srcs = srcs
+            .iter()
+            .map(|s| {
+                let src = Path::new(s);
+                let Some(name) = src.file_name() else {
+                    bail!("Invalid path: {s}");
+                };
+                Ok((src, name.to_string_lossy().nfc().to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let out = match self.kind {
+            ArchiveKind::Zip => {
+                let mut zip = ZipWriter::new(out);
+                for (src, name) in srcs {
+                    self.append_zip(&mut zip, src, &name)?;
+                }
+                zip.finish()?
+            }
+            ArchiveKind::Tar => {
+                let mut builder = tar::Builder::new(out);
+                for (src, name) in srcs {
+                    self.append_tar(&mut builder, src, &name)?;
+                }
+                builder.into_inner()?
+            }
+            ArchiveKind::Tgz => {
+                let level = Compression::new(self.level);
+                let mut builder = tar::Builder::new(GzEncoder::new(out, level));
+                for (src, name) in srcs {
+                    self.append_tar(&mut builder, src, &name)?;
+                }
+                builder.into_inner()?.finish()?
+            }
+            ArchiveKind::Txz => {
+                let encoder = XzEncoder::new(out, self.level);
+                let mut builder = tar::Builder::new(encoder);
+                for (src, name) in srcs {
+                    self.append_tar(&mut builder, src, &name)?;
+                }
+                builder.into_inner()?.finish()?
+            }
+            ArchiveKind::Tbz => {
+                // bzip2 の圧縮レベルは 1-9 のため、0 は 1 として扱う。
+                let level = bzip2::Compression::new(self.level.max(1));
+                let mut builder = tar::Builder::new(BzEncoder::new(out, level));
+                for (src, name) in srcs {
+                    self.append_tar(&mut builder, src, &name)?;
+                }
+                builder.into_inner()?.finish()?
+            }
+            ArchiveKind::Tzst => {
+                let encoder = ZstdEncoder::new(out, self.level as i32)?;
+                let mut builder = tar::Builder::new(encoder);
+                for (src, name) in srcs {
+                    self.append_tar(&mut builder, src, &name)?;
+                }
+                builder.into_inner()?.finish()?
+            }
+            ArchiveKind::SevenZip | ArchiveKind::Iso => {
+                bail!(VirtualDirError::UnsupportedArchive);
+            }
+        };
+        out.into_inner()?.sync_all()?;
+        Ok(())
+    }
+
+    /// エントリを zip アーカイブに追加する。
+    ///
+    /// ディレクトリの場合は配下のエントリも再帰的に追加する。
+    ///
+    /// # Arguments
+    /// * `zip` - ZipWriter
+    /// * `src` - 追加するエントリのパス
+    /// * `name` - アーカイブ内のパス
+    pub fn append_zip<W: Write + Seek>(
+        &self,
+        zip: &mut ZipWriter<W>,
+        src: &Path,
+        name: &str,
+    ) -> Result<()> {
+        self.job.check()?;
+        let meta = fs::symlink_metadata(src)?;
+        let options = SimpleFileOptions::default()
+            .unix_permissions(meta.mode() & 0o7777)
+            .last_modified_time(zip_time(meta.mtime()));
+        if meta.is_dir() {
+            zip.add_directory(format!("{name}/"), options)?;
+            self.job.add_files(1);
+            for (child, child_name) in children(src, name)? {
+                self.append_zip(zip, &child, &child_name)?;
+            }
+        } else if meta.is_symlink() {
+            let target = fs::read_link(src)?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+            self.job.add_files(1);
+        } else if meta.is_file() {
+            let options = options
+                .compression_method(CompressionMethod::Deflated)
+                .compression_level(Some(self.level.into()))
+                .large_file(meta.len() >= u32::MAX.into());
+            zip.start_file(name, options)?;
+            let mut reader = ProgressReader::new(File::open(src)?, self.job);
+            io::copy(&mut reader, zip)?;
+            self.job.add_files(1);
+        } else {
+            // FIFO やソケットなどの特殊ファイルは格納しない。
+            self.job.add_files(1);
+        }
+        Ok(())
+    }
+
+    /// エントリを tar アーカイブに追加する。
+    ///
+    /// ディレクトリの場合は配下のエントリも再帰的に追加する。
+    ///
+    /// # Arguments
+    /// * `builder` - tar::Builder
+    /// * `src` - 追加するエントリのパス
+    /// * `name` - アーカイブ内のパス
+    pub fn append_tar<W: Write>(
+        &self,
+        builder: &mut tar::Builder<W>,
+        src: &Path,
+        name: &str,
+    ) -> Result<()> {
+        self.job.check()?;
+        let meta = fs::symlink_metadata(src)?;
+        let mut header = Header::new_gnu();
+        header.set_metadata_in_mode(&meta, HeaderMode::Complete);
+        if meta.is_dir() {
+            builder.append_data(&mut header, name, io::empty())?;
+            self.job.add_files(1);
+            for (child, child_name) in children(src, name)? {
+                self.append_tar(builder, &child, &child_name)?;
+            }
+        } else if meta.is_symlink() {
+            header.set_entry_type(EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, name, fs::read_link(src)?)?;
+            self.job.add_files(1);
+        } else if meta.is_file() {
+            let reader = ProgressReader::new(File::open(src)?, self.job);
+            builder.append_data(&mut header, name, reader)?;
+            self.job.add_files(1);
+        } else {
+            // FIFO やソケットなどの特殊ファイルは格納しない。
+            self.job.add_files(1);
+        }
+        Ok(())
+    }
+}
+
+/// ディレクトリ直下のエントリを、アーカイブ内のパスと共に名前順で取得する。
+///
+/// # Arguments
+/// * `dir` - ディレクトリのパス
+/// * `name` - ディレクトリのアーカイブ内のパス
+fn children(dir: &Path, name: &str) -> Result<Vec<(PathBuf, String)>> {
+    let mut children = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    children.sort();
+    Ok(children
+        .into_iter()
+        .map(|child| {
+            let child_name = child.file_name().unwrap().to_string_lossy();
+            let child_name = format!("{name}/{}", child_name.nfc());
+            (child, child_name)
+        })
+        .collect())
+}
+
+/// UNIX 時間を zip の日時に変換する。
+///
+/// zip の日時はタイムゾーンを持たないため、ローカル時刻として扱う。
+/// 表現できない日時の場合はデフォルト値 (1980-01-01) となる。
+///
+/// # Arguments
+/// * `secs` - UNIX 時間
+fn zip_time(secs: i64) -> DateTime {
+    let Some(dt) = Local.timestamp_opt(secs, 0).single() else {
+        return DateTime::default();
+    };
+    DateTime::from_date_and_time(
+        dt.year().try_into().unwrap_or(0),
+        dt.month() as u8,
+        dt.day() as u8,
+        dt.hour() as u8,
+        dt.minute() as u8,
+        dt.second() as u8,
+    )
+    .unwrap_or_default()
+}
+
+/// 一時ファイルを、既存のエントリを上書きせずに公開する。
+///
+/// 書き出している間に作成されたエントリを上書きしないよう、
+/// hard link、RENAME_NOREPLACE 付きの rename、
+/// 空ファイルを排他的に作成してからの rename の順に試す。
+/// hard link を作れない vfat や一部の SMB/FUSE などへの書き出しに対応するため、
+/// AlreadyExists 以外のエラーの場合は次の方法で再試行する。
+///
+/// # Arguments
+/// * `tmp` - 公開する一時ファイルのパス
+/// * `dest` - 公開先のパス
+///
+/// # Errors
+/// - `FsError::AlreadyExists`:
+///   公開先がすでに存在する。
+fn publish(tmp: &Path, dest: &Path) -> Result<()> {
+    let steps: [fn(&Path, &Path) -> io::Result<()>; 3] = [
+        |tmp, dest| fs::hard_link(tmp, dest),
+        rename_noreplace,
+        rename_to_placeholder,
+    ];
+    let mut last = None;
+    for step in steps {
+        match step(tmp, dest) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                let dest = dest.to_string_lossy().to_string();
+                bail!(FsError::AlreadyExists(dest));
+            }
+            Err(err) => last = Some(err),
+        }
+    }
+    Err(last.unwrap().into())
+}
+
+/// 公開先が存在しない場合に限り rename する。
+///
+/// # Arguments
+/// * `tmp` - 公開する一時ファイルのパス
+/// * `dest` - 公開先のパス
+fn rename_noreplace(tmp: &Path, dest: &Path) -> io::Result<()> {
+    let flags = RenameFlags::NOREPLACE;
+    renameat_with(CWD, tmp, CWD, dest, flags).map_err(io::Error::from)
+}
+
+/// 空ファイルを排他的に作成し、それを一時ファイルで上書きする。
+///
+/// RENAME_NOREPLACE に対応していないファイルシステム向けの最終手段である。
+/// rename に失敗した場合、作成した空ファイルは削除する。
+///
+/// # Arguments
+/// * `tmp` - 公開する一時ファイルのパス
+/// * `dest` - 公開先のパス
+fn rename_to_placeholder(tmp: &Path, dest: &Path) -> io::Result<()> {
+    File::options().write(true).create_new(true).open(dest)?;
+    fs::rename(tmp, dest).inspect_err(|_| {
+        let _ = fs::remove_file(dest);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, setup_resources, teardown_resources,
+    };
+
+    use bzip2::read::BzDecoder;
+    use flate2::read::GzDecoder;
+    use std::{collections::HashSet, io::Read};
+    use xz2::read::XzDecoder;
+    use zip::ZipArchive;
+    use zstd::stream::read::Decoder as ZstdDecoder;
+
+    use super::*;
+
+    fn tar_names<R: Read>(reader: R) -> Result<HashSet<String>> {
+        let mut tar = tar::Archive::new(reader);
+        let mut names = HashSet::new();
+        for entry in tar.entries()? {
+            let entry = entry?;
+            names.insert(entry.path()?.to_string_lossy().to_string());
+        }
+        Ok(names)
+    }
+
+    #[tokio::test]
+    async fn test_pack() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let srcs = [
+            format!("{path}/test1"),
+            format!("{path}/test.txt"),
+            format!("{path}/test1.txt's link"),
+        ];
+        let expected = HashSet::from(
+            ["test1", "test1/test1.txt", "test.txt", "test1.txt's link"]
+                .map(str::to_owned),
+        );
+        let kinds = [
+            ArchiveKind::Zip,
+            ArchiveKind::Tar,
+            ArchiveKind::Tgz,
+            ArchiveKind::Txz,
+            ArchiveKind::Tbz,
+            ArchiveKind::Tzst,
+        ];
+        for kind in kinds {
+            let job = ProgressJob::new();
+            let dest = format!("{path}/test3/out");
+            Packer::new(kind, Some(9), &job).pack(&srcs, Path::new(&dest))?;
+            assert_eq!(job.percent(), 100);
+            let file = File::open(&dest)?;
+            let names = match kind {
+                ArchiveKind::Zip => {
+                    let mut zip = ZipArchive::new(file)?;
+                    let link = zip.by_name("test1.txt's link")?;
+                    assert!(link.is_symlink());
+                    drop(link);
+                    zip.file_names()
+                        .map(|n| n.trim_end_matches('/').to_owned())
+                        .collect()
+                }
+                ArchiveKind::Tar => tar_names(file)?,
+                ArchiveKind::Tgz => tar_names(GzDecoder::new(file))?,
+                ArchiveKind::Txz => tar_names(XzDecoder::new(file))?,
+                ArchiveKind::Tbz => tar_names(BzDecoder::new(file))?,
+                ArchiveKind::Tzst => tar_names(ZstdDecoder::new(file)?)?,
+                ArchiveKind::SevenZip | ArchiveKind::Iso => unreachable!(),
+            };
+            assert_eq!(names, expected);
+            fs::remove_file(&dest)?;
+        }
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pack_error() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let srcs = [format!("{path}/test1")];
+        let job = ProgressJob::new();
+        let packer = Packer::new(ArchiveKind::Zip, None, &job);
+        let dest = format!("{path}/test.txt");
+        let res = packer.pack(&srcs, Path::new(&dest));
+        assert_err(&res.unwrap_err(), &FsError::AlreadyExists("".into()));
+        let dest = format!("{path}/test1/out.zip");
+        let res = packer.pack(&srcs, Path::new(&dest));
+        assert_err(&res.unwrap_err(), &FsError::IntoItself("".into()));
+
+        // 中止した場合は作りかけのアーカイブが残らない。
+        job.abort();
+        let dest = format!("{path}/test3/out.zip");
+        let res = packer.pack(&srcs, Path::new(&dest));
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        assert_eq!(fs::read_dir(format!("{path}/test3"))?.count(), 0);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_publish_fallback() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let p = Path::new(&path);
+        let exists = p.join("test.txt");
+
+        // hard link を使えない場合の代替手段でも、既存のエントリは上書きしない。
+        let steps: [fn(&Path, &Path) -> io::Result<()>; 2] =
+            [rename_noreplace, rename_to_placeholder];
+        for (i, step) in steps.into_iter().enumerate() {
+            let tmp = p.join(format!("tmp{i}"));
+            let dest = p.join(format!("out{i}.zip"));
+            fs::write(&tmp, "archive")?;
+            let err = step(&tmp, &exists).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+            assert_eq!(fs::read_to_string(&exists)?, "test");
+            step(&tmp, &dest)?;
+            assert_eq!(fs::read_to_string(&dest)?, "archive");
+            assert!(!tmp.exists());
+        }
+
+        // 公開先が存在する場合は AlreadyExists になり、一時ファイルは残る。
+        let tmp = p.join("tmp");
+        fs::write(&tmp, "archive")?;
+        let res = publish(&tmp, &exists);
+        assert_err(&res.unwrap_err(), &FsError::AlreadyExists("".into()));
+        assert_eq!(fs::read_to_string(&exists)?, "test");
+        assert!(tmp.exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
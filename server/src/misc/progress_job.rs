@@ -0,0 +1,436 @@
+use crate::{
+    errors::FsError,
+    misc::SenderTrait,
+    models::{DisposeType, TaskControl, TaskStatus},
+};
+
+use anyhow::{Result, anyhow, bail};
+use serde_json::{Value, json};
+use std::{
+    future::Future,
+    io::{self, Read},
+    pin::Pin,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::spawn_blocking,
+    time::{Duration, interval},
+};
+use uuid::Uuid;
+
+/// 読み込んだバイト数を ProgressJob に報告するリーダー。
+///
+/// 中止されていた場合は読み込みをエラーにする。
+///
+/// # Fields
+/// * `inner` - 元のリーダー
+/// * `job` - 進捗状況
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    job: &'a ProgressJob,
+}
+
+impl<'a, R> ProgressReader<'a, R> {
+    /// 新しい ProgressReader インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `inner` - 元のリーダー
+    /// * `job` - 進捗状況
+    pub fn new(inner: R, job: &'a ProgressJob) -> Self {
+        Self { inner, job }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.job.check().is_err() {
+            return Err(io::Error::other(FsError::Aborted));
+        }
+        let n = self.inner.read(buf)?;
+        self.job.add_bytes(n as u64);
+        Ok(n)
+    }
+}
+
+/// Rust で実装された ProgressTask の進捗状況を扱う構造体。
+///
+/// 処理本体はブロッキングスレッドで実行され、
+/// 進捗状況はこの構造体を介して非同期タスクと共有される。
+///
+/// # Fields
+/// * `total_bytes` - 処理対象の総バイト数
+/// * `bytes` - 処理済みのバイト数
+/// * `total_files` - 処理対象のエントリ総数
+/// * `files` - 処理済みのエントリ数
+/// * `aborted` - 中止されたか否か
+/// * `items` - 未送信の途中経過のデータ
+#[derive(Default)]
+pub struct ProgressJob {
+    total_bytes: AtomicU64,
+    bytes: AtomicU64,
+    total_files: AtomicU64,
+    files: AtomicU64,
+    aborted: AtomicBool,
+    items: Mutex<Vec<Value>>,
+}
+
+impl ProgressJob {
+    /// 新しい ProgressJob インスタンスを作成する。
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// 処理対象の総量を設定する。
+    ///
+    /// # Arguments
+    /// * `bytes` - 総バイト数
+    /// * `files` - エントリ総数
+    pub fn set_total(&self, bytes: u64, files: u64) {
+        self.total_bytes.store(bytes, Ordering::Relaxed);
+        self.total_files.store(files, Ordering::Relaxed);
+    }
+
+    /// 処理済みのバイト数を加算する。
+    ///
+    /// # Arguments
+    /// * `n` - 加算するバイト数
+    pub fn add_bytes(&self, n: u64) {
+        self.bytes.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// 処理済みのエントリ数を加算する。
+    ///
+    /// # Arguments
+    /// * `n` - 加算するエントリ数
+    pub fn add_files(&self, n: u64) {
+        self.files.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// 途中経過のデータを追加する。
+    ///
+    /// 追加したデータは、進捗と同じタイミングでまとめて送信される。
+    ///
+    /// # Arguments
+    /// * `item` - 追加するデータ
+    pub fn emit(&self, item: Value) {
+        self.lock_items().push(item);
+    }
+
+    /// 未送信の途中経過のデータをすべて取り出す。
+    ///
+    /// # Returns
+    /// データの配列
+    pub fn take_items(&self) -> Vec<Value> {
+        std::mem::take(&mut *self.lock_items())
+    }
+
+    /// 未送信の途中経過のデータをロックする。
+    ///
+    /// データは追加と取り出ししか行わず不整合が生じないため、
+    /// ロックがポイズンされていてもそのまま使用する。
+    fn lock_items(&self) -> MutexGuard<'_, Vec<Value>> {
+        self.items.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 途中経過のデータがあれば送信する。
+    ///
+    /// # Arguments
+    /// * `sender` - WebSocket メッセージを送信する構造体
+    /// * `pid` - プロセス ID
+    async fn flush(&self, sender: &Arc<dyn SenderTrait>, pid: &str) {
+        let items = self.take_items();
+        if !items.is_empty() {
+            let _ = sender.progress_data(pid, &Value::from(items)).await;
+        }
+    }
+
+    /// 処理を中止する。
+    ///
+    /// 処理本体は `check` で中止を検知して自ら終了する必要がある。
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::Relaxed);
+    }
+
+    /// 中止されていないかを確認する。
+    ///
+    /// # Errors
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn check(&self) -> Result<()> {
+        if self.aborted.load(Ordering::Relaxed) {
+            bail!(FsError::Aborted);
+        }
+        Ok(())
+    }
+
+    /// 進捗率を取得する。
+    ///
+    /// 総バイト数が分かっていればバイト数で、
+    /// そうでなければエントリ数で算出する。
+    ///
+    /// # Returns
+    /// 進捗率 (0-100)
+    pub fn percent(&self) -> usize {
+        let total_bytes = self.total_bytes.load(Ordering::Relaxed);
+        let (done, total) = if total_bytes > 0 {
+            (self.bytes.load(Ordering::Relaxed), total_bytes)
+        } else {
+            let files = self.files.load(Ordering::Relaxed);
+            (files, self.total_files.load(Ordering::Relaxed))
+        };
+        if total == 0 {
+            return 0;
+        }
+        ((done.min(total) as f64 / total as f64) * 100.0) as usize
+    }
+
+    /// 進捗の詳細を JSON 形式で取得する。
+    ///
+    /// # Returns
+    /// 処理済みおよび総量のバイト数とエントリ数
+    pub fn detail(&self) -> Value {
+        json!({
+            "bytes": self.bytes.load(Ordering::Relaxed),
+            "totalBytes": self.total_bytes.load(Ordering::Relaxed),
+            "files": self.files.load(Ordering::Relaxed),
+            "totalFiles": self.total_files.load(Ordering::Relaxed),
+        })
+    }
+
+    /// 処理本体をブロッキングスレッドで実行し、進捗を定期的に送信する。
+    ///
+    /// 進捗と途中経過のデータは 1 秒ごとに送信され、
+    /// 完了時には残りのデータと結果が送信される。
+    ///
+    /// 処理本体は開始通知を受け取るまで開始しない。
+    /// TaskManager への登録前に完了してしまうと、
+    /// 終了メッセージが PROGRESS_TASK より先に届いてしまうため。
+    /// 開始通知の送信チャネルが破棄された場合は、待たずに開始する。
+    ///
+    /// # Arguments
+    /// * `sender` - WebSocket メッセージを送信する構造体
+    /// * `tx` - タスク制御メッセージの送信チャネル
+    /// * `work` - 処理本体
+    ///   戻り値は処理結果として完了時に送信される。
+    ///
+    /// # Returns
+    /// プロセス ID、中断処理関数、開始通知の送信チャネルのタプル
+    pub fn spawn<F>(
+        self: &Arc<Self>,
+        sender: Arc<dyn SenderTrait>,
+        tx: mpsc::Sender<TaskControl>,
+        work: F,
+    ) -> (String, DisposeType, oneshot::Sender<()>)
+    where
+        F: FnOnce(&ProgressJob) -> Result<Value> + Send + 'static,
+    {
+        let pid = Uuid::new_v4().to_string();
+        let pid_ = pid.clone();
+        let job = self.clone();
+        let sender_ = sender.clone();
+        let (start_tx, start_rx) = oneshot::channel::<()>();
+
+        // メイン処理の非同期タスク
+        let handle = tokio::spawn(async move {
+            let _ = start_rx.await;
+            let job_ = job.clone();
+            let mut work = spawn_blocking(move || work(&job_));
+            let mut intv = interval(Duration::from_secs(1));
+
+            // 1 秒以内に終わるなら progress を発行してほしくないため、
+            // 最初の tick() を消化しておく。
+            intv.tick().await;
+
+            // 確実に進捗率を送信するため `biased;` を指定する。
+            let res = loop {
+                tokio::select! {
+                    biased;
+                    _ = intv.tick() => {
+                        let (num, detail) = (job.percent(), job.detail());
+                        let _ = sender_.progress(&pid_, num, &detail).await;
+                        job.flush(&sender_, &pid_).await;
+                    }
+                    res = &mut work => break res,
+                }
+            };
+            job.flush(&sender_, &pid_).await;
+
+            match res {
+                Ok(Ok(result)) => {
+                    let _ = sender_.progress_end(&pid_, &result).await;
+                }
+                Ok(Err(err)) => {
+                    let _ = sender_.progress_error(&pid_, &err).await;
+                }
+                // 処理本体が panic した場合。
+                Err(err) => {
+                    let err = anyhow!(err.to_string());
+                    let _ = sender_.progress_error(&pid_, &err).await;
+                }
+            }
+
+            // 終了メッセージを TaskManager に送信する。
+            let ctrl = TaskControl {
+                pid: pid_,
+                status: TaskStatus::End,
+            };
+            let _ = tx.send(ctrl).await;
+        });
+
+        // 中断処理関数を生成する。
+        // 処理本体に中止を通知し、メイン処理を中止して中断メッセージを送信する。
+        // ブロッキングスレッドは強制終了できないため、
+        // 処理本体は中止を検知した時点で自ら後始末をして終了する。
+        let pid_ = pid.clone();
+        let job = self.clone();
+        let dispose = move || {
+            Box::pin(async move {
+                job.abort();
+                handle.abort();
+                let _ = sender.progress_abort(&pid_).await;
+            }) as Pin<Box<dyn Future<Output = ()> + Send>>
+        };
+
+        (pid, Box::new(dispose), start_tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        misc::MockSenderTrait,
+        test_helpers::{assert_err, setup_sender, sleep},
+    };
+
+    use super::*;
+
+    fn run(
+        sender: MockSenderTrait,
+        work: impl FnOnce(&ProgressJob) -> Result<Value> + Send + 'static,
+    ) -> (Arc<ProgressJob>, DisposeType, mpsc::Receiver<TaskControl>) {
+        let (tx, rx) = mpsc::channel::<TaskControl>(10);
+        let job = ProgressJob::new();
+        let (_, dispose, _) = job.spawn(Arc::new(sender), tx, work);
+        (job, dispose, rx)
+    }
+
+    #[test]
+    fn test_percent() {
+        let job = ProgressJob::new();
+        assert_eq!(job.percent(), 0);
+        job.set_total(0, 4);
+        job.add_files(1);
+        assert_eq!(job.percent(), 25);
+        job.set_total(200, 4);
+        job.add_bytes(150);
+        assert_eq!(job.percent(), 75);
+        let detail = job.detail();
+        assert_eq!(detail["bytes"], 150);
+        assert_eq!(detail["totalFiles"], 4);
+    }
+
+    #[test]
+    fn test_check() {
+        let job = ProgressJob::new();
+        assert!(job.check().is_ok());
+        job.abort();
+        assert_err(&job.check().unwrap_err(), &FsError::Aborted);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_success() -> Result<()> {
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let (_, _, mut rx) = run(sender, |_| Ok(Value::Null));
+        let ctrl = rx.recv().await.unwrap();
+        assert!(ctrl.status == TaskStatus::End);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn_error() -> Result<()> {
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_error()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let (_, _, mut rx) = run(sender, |_| bail!("error"));
+        let ctrl = rx.recv().await.unwrap();
+        assert!(ctrl.status == TaskStatus::End);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn_progress() -> Result<()> {
+        let mut sender = setup_sender();
+        sender
+            .expect_progress()
+            .times(1)
+            .returning(|_, num, detail| {
+                assert_eq!(num, 50);
+                assert_eq!(detail["bytes"], 5);
+                Ok(())
+            });
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let (_, _, mut rx) = run(sender, |job| {
+            job.set_total(10, 1);
+            job.add_bytes(5);
+            std::thread::sleep(std::time::Duration::from_millis(1500));
+            Ok(Value::Null)
+        });
+        rx.recv().await.unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn_data() -> Result<()> {
+        let mut sender = setup_sender();
+        sender.expect_progress_data().times(1).returning(|_, data| {
+            assert_eq!(data, &json!([1, 2]));
+            Ok(())
+        });
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let (job, _, mut rx) = run(sender, |job| {
+            job.emit(json!(1));
+            job.emit(json!(2));
+            Ok(Value::Null)
+        });
+        rx.recv().await.unwrap();
+        assert!(job.take_items().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spawn_abort() -> Result<()> {
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_abort()
+            .times(1)
+            .returning(|_| Ok(()));
+        let (job, dispose, mut rx) = run(sender, |job| {
+            loop {
+                job.check()?;
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        });
+        sleep(50).await;
+        dispose().await;
+        assert!(job.check().is_err());
+        sleep(50).await;
+        assert!(rx.try_recv().is_err());
+        Ok(())
+    }
+}
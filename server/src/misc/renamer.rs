@@ -0,0 +1,423 @@
+use crate::{errors::RenameError, models::RenameItem};
+
+use anyhow::{Result, bail};
+use regex::Regex;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    os::unix::fs::MetadataExt as _,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use uuid::Uuid;
+
+/// テンプレートのプレースホルダーにマッチする正規表現。
+///
+/// `{name}`, `{ext}`, `{n}` および桁数指定付きの `{n:03}` など。
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{(name|ext|n)(?::(\d+))?\}").unwrap());
+
+/// 変更後の名前を決定する規則。
+///
+/// # Variants
+/// * `Regex` - 正規表現による置換
+///   エントリ名のうち、マッチしたすべての部分を置換する。
+///   置換文字列では `$1` や `${name}` でキャプチャを参照できる。
+/// * `Template` - テンプレートによる生成
+///   `{name}` は拡張子を除いた名前、`{ext}` は拡張子、
+///   `{n}` は `start` から始まる連番に置き換えられる。
+///   `{n:03}` のように桁数を指定すると 0 埋めされる。
+/// * `Mapping` - 変更前のパスと変更後の名前の対応表
+pub enum RenameRule {
+    Regex { regex: Regex, replacement: String },
+    Template { template: String, start: u64 },
+    Mapping(HashMap<String, String>),
+}
+
+impl RenameRule {
+    /// 正規表現による置換の規則を作成する。
+    ///
+    /// # Arguments
+    /// * `pattern` - 正規表現
+    /// * `replacement` - 置換文字列
+    ///
+    /// # Errors
+    /// - `RenameError::InvalidPattern`:
+    ///   正規表現が不正である。
+    pub fn regex(pattern: &str, replacement: &str) -> Result<Self> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(Self::Regex {
+                regex,
+                replacement: replacement.to_owned(),
+            }),
+            Err(err) => bail!(RenameError::InvalidPattern(err.to_string())),
+        }
+    }
+}
+
+/// 複数のエントリを一括でリネームする構造体。
+///
+/// # Fields
+/// * `rule` - 変更後の名前を決定する規則
+pub struct Renamer {
+    rule: RenameRule,
+}
+
+impl Renamer {
+    /// 新しい Renamer インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `rule` - 変更後の名前を決定する規則
+    pub fn new(rule: RenameRule) -> Self {
+        Self { rule }
+    }
+
+    /// リネームの計画を立てる。
+    ///
+    /// 名前が変わらないエントリは計画に含めない。
+    /// 実際のリネームは行わないため、ドライランの結果としても使用できる。
+    ///
+    /// # Arguments
+    /// * `sources` - 対象エントリの絶対パスの配列
+    ///   `RenameRule::Mapping` の場合は対応表のキーが対象となり、無視される。
+    ///
+    /// # Returns
+    /// リネームの計画
+    ///
+    /// # Errors
+    /// - `RenameError::InvalidPattern`:
+    ///   テンプレートの桁数指定が不正である。
+    /// - `RenameError::InvalidName`:
+    ///   変更後の名前がファイル名として不正である。
+    /// - `RenameError::Collision`:
+    ///   変更後の名前が、他の変更後の名前や既存のエントリと衝突する。
+    pub fn plan(&self, sources: &[String]) -> Result<Vec<RenameItem>> {
+        let sources = match &self.rule {
+            RenameRule::Mapping(map) => {
+                let mut keys: Vec<_> = map.keys().cloned().collect();
+                keys.sort();
+                keys
+            }
+            _ => sources.to_vec(),
+        };
+        let mut seen = HashSet::new();
+        let mut items = vec![];
+        for (i, src) in sources.iter().enumerate() {
+            if !seen.insert(src) {
+                continue;
+            }
+            let from = Path::new(src);
+            let name = from.file_name().unwrap_or_default().to_string_lossy();
+            let new_name = self.new_name(src, &name, i as u64)?;
+            if !is_valid_name(&new_name) {
+                bail!(RenameError::InvalidName(new_name));
+            }
+            let to = from.with_file_name(&new_name);
+            if to != from {
+                items.push((from.to_path_buf(), to));
+            }
+        }
+        check_collisions(&items)?;
+        let items = items
+            .into_iter()
+            .map(|(from, to)| RenameItem {
+                from: from.to_string_lossy().to_string(),
+                to: to.to_string_lossy().to_string(),
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// 計画に従ってリネームする。
+    ///
+    /// 変更後の名前が他の対象エントリの変更前の名前と重なる場合
+    /// (`a -> b`, `b -> a` のような循環を含む) は、
+    /// 一旦すべてを一時的な名前に変更してから最終的な名前に変更する。
+    /// 途中で失敗した場合は、それまでのリネームを元に戻す。
+    ///
+    /// # Arguments
+    /// * `items` - リネームの計画
+    pub fn apply(items: &[RenameItem]) -> Result<()> {
+        let froms: HashSet<_> = items.iter().map(|i| &i.from).collect();
+        let two_phase = items.iter().any(|i| froms.contains(&i.to));
+        let mut done = vec![];
+        let res = if two_phase {
+            Self::apply_two_phase(items, &mut done)
+        } else {
+            items.iter().try_for_each(|item| {
+                step(Path::new(&item.from), Path::new(&item.to), &mut done)
+            })
+        };
+        if res.is_err() {
+            for (from, to) in done.iter().rev() {
+                let _ = fs::rename(to, from);
+            }
+        }
+        res
+    }
+
+    /// 一時的な名前を経由してリネームする。
+    ///
+    /// # Arguments
+    /// * `items` - リネームの計画
+    /// * `done` - 完了したリネームの記録
+    fn apply_two_phase(
+        items: &[RenameItem],
+        done: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<()> {
+        let mut tmps = vec![];
+        for item in items {
+            let from = Path::new(&item.from);
+            let tmp =
+                from.with_file_name(format!(".{}.rename", Uuid::new_v4()));
+            step(from, &tmp, done)?;
+            tmps.push(tmp);
+        }
+        for (item, tmp) in items.iter().zip(tmps) {
+            step(&tmp, Path::new(&item.to), done)?;
+        }
+        Ok(())
+    }
+
+    /// 規則に従って変更後の名前を生成する。
+    ///
+    /// # Arguments
+    /// * `src` - 対象エントリの絶対パス
+    /// * `name` - 対象エントリの名前
+    /// * `index` - 対象エントリの順番 (0 始まり)
+    fn new_name(&self, src: &str, name: &str, index: u64) -> Result<String> {
+        let res = match &self.rule {
+            RenameRule::Regex { regex, replacement } => {
+                regex.replace_all(name, replacement.as_str()).to_string()
+            }
+            RenameRule::Template { template, start } => {
+                render(template, name, start + index)?
+            }
+            RenameRule::Mapping(map) => map[src].clone(),
+        };
+        Ok(res)
+    }
+}
+
+/// テンプレートから名前を生成する。
+///
+/// 拡張子がない場合、`{ext}` 直前の `.` は取り除かれる。
+///
+/// # Arguments
+/// * `template` - テンプレート
+/// * `name` - 対象エントリの名前
+/// * `n` - 連番
+fn render(template: &str, name: &str, n: u64) -> Result<String> {
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+    let mut res = String::new();
+    let mut last = 0;
+    for caps in PLACEHOLDER.captures_iter(template) {
+        let m = caps.get(0).unwrap();
+        res.push_str(&template[last..m.start()]);
+        last = m.end();
+        match &caps[1] {
+            "name" => res.push_str(&stem),
+            "ext" if ext.is_empty() => {
+                if res.ends_with('.') {
+                    res.pop();
+                }
+            }
+            "ext" => res.push_str(&ext),
+            _ => {
+                let width = match caps.get(2) {
+                    Some(w) => w.as_str().parse::<usize>().map_err(|_| {
+                        RenameError::InvalidPattern(m.as_str().to_owned())
+                    })?,
+                    None => 0,
+                };
+                res.push_str(&format!("{n:0width$}"));
+            }
+        }
+    }
+    res.push_str(&template[last..]);
+    Ok(res)
+}
+
+/// ファイル名として妥当か否かを判定する。
+///
+/// # Arguments
+/// * `name` - 判定する名前
+fn is_valid_name(name: &str) -> bool {
+    !(name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains('\0'))
+}
+
+/// 変更後のパスの衝突を検出する。
+///
+/// 変更後のパスが重複している場合や、対象外の既存エントリと重なる場合は衝突とする。
+/// 大文字小文字を区別しないファイルシステムで、
+/// 大文字小文字だけを変える場合は同一のエントリとなるため衝突としない。
+///
+/// # Arguments
+/// * `items` - 変更前と変更後のパスの組の配列
+fn check_collisions(items: &[(PathBuf, PathBuf)]) -> Result<()> {
+    let froms: HashSet<_> = items.iter().map(|(from, _)| from).collect();
+    let mut tos = HashSet::new();
+    for (from, to) in items {
+        let collision = !tos.insert(to)
+            || (!froms.contains(to)
+                && fs::symlink_metadata(to).is_ok()
+                && !is_same_entry(from, to));
+        if collision {
+            bail!(RenameError::Collision(to.to_string_lossy().to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// 2 つのパスが同じエントリを指しているか否かを判定する。
+///
+/// # Arguments
+/// * `a` - パス
+/// * `b` - パス
+fn is_same_entry(a: &Path, b: &Path) -> bool {
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// リネームして記録する。
+///
+/// # Arguments
+/// * `from` - 変更前のパス
+/// * `to` - 変更後のパス
+/// * `done` - 完了したリネームの記録
+fn step(
+    from: &Path,
+    to: &Path,
+    done: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    fs::rename(from, to)?;
+    done.push((from.to_path_buf(), to.to_path_buf()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_err, setup_resources, teardown_resources,
+    };
+
+    use super::*;
+
+    fn sources(path: &str, names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| format!("{path}/{n}")).collect()
+    }
+
+    fn names(items: &[RenameItem]) -> Vec<String> {
+        items
+            .iter()
+            .map(|i| Path::new(&i.to).file_name().unwrap())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        assert_eq!(render("{name}_{n:03}.{ext}", "foo.txt", 7)?, "foo_007.txt");
+        assert_eq!(render("{name}_{n}.{ext}", "foo", 12)?, "foo_12");
+        assert_eq!(render("{n:2}-{name}.{ext}", "a.tar.gz", 1)?, "01-a.tar.gz");
+        assert_eq!(render("{foo}{name}", ".bashrc", 1)?, "{foo}.bashrc");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_regex() -> Result<()> {
+        let path = setup_resources("").await?;
+        let rule = RenameRule::regex(r"^test(\d)", "file_$1")?;
+        let srcs = sources(&path, &["test1", "test2", "test.txt"]);
+        let items = Renamer::new(rule).plan(&srcs)?;
+        // 名前が変わらないエントリは含まれない。
+        assert_eq!(names(&items), ["file_1", "file_2"]);
+        assert_eq!(items[0].from, format!("{path}/test1"));
+        let res = RenameRule::regex("(", "");
+        assert_err(
+            &res.err().unwrap(),
+            &RenameError::InvalidPattern("".into()),
+        );
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_template() -> Result<()> {
+        let path = setup_resources("").await?;
+        let rule = RenameRule::Template {
+            template: "{name}_{n:02}.{ext}".to_owned(),
+            start: 1,
+        };
+        let srcs = sources(&path, &["test.txt", "test1"]);
+        let items = Renamer::new(rule).plan(&srcs)?;
+        assert_eq!(names(&items), ["test_01.txt", "test1_02"]);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_plan_error() -> Result<()> {
+        let path = setup_resources("").await?;
+        // 変更後の名前が重複している。
+        let rule = RenameRule::regex(r"\d", "")?;
+        let srcs = sources(&path, &["test1", "test2"]);
+        let res = Renamer::new(rule).plan(&srcs);
+        assert_err(&res.unwrap_err(), &RenameError::Collision("".into()));
+        // 対象外の既存エントリと衝突する。
+        let rule = RenameRule::regex("^test1$", "test2")?;
+        let res = Renamer::new(rule).plan(&sources(&path, &["test1"]));
+        assert_err(&res.unwrap_err(), &RenameError::Collision("".into()));
+        // ファイル名として不正である。
+        let rule = RenameRule::regex("^test1$", "a/b")?;
+        let res = Renamer::new(rule).plan(&sources(&path, &["test1"]));
+        assert_err(&res.unwrap_err(), &RenameError::InvalidName("".into()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_cycle() -> Result<()> {
+        let path = setup_resources("").await?;
+        let map = HashMap::from([
+            (format!("{path}/test1"), "test2".to_owned()),
+            (format!("{path}/test2"), "test3".to_owned()),
+            (format!("{path}/test3"), "test1".to_owned()),
+        ]);
+        let items = Renamer::new(RenameRule::Mapping(map)).plan(&[])?;
+        Renamer::apply(&items)?;
+        assert!(Path::new(&format!("{path}/test2/test1.txt")).is_file());
+        assert!(Path::new(&format!("{path}/test3/test2.txt")).is_file());
+        assert!(Path::new(&format!("{path}/test1")).is_dir());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_rollback() -> Result<()> {
+        let path = setup_resources("").await?;
+        let items = vec![
+            RenameItem {
+                from: format!("{path}/test1"),
+                to: format!("{path}/new1"),
+            },
+            RenameItem {
+                from: format!("{path}/nowhere"),
+                to: format!("{path}/new2"),
+            },
+        ];
+        assert!(Renamer::apply(&items).is_err());
+        assert!(Path::new(&format!("{path}/test1")).is_dir());
+        assert!(!Path::new(&format!("{path}/new1")).exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
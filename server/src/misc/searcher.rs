@@ -0,0 +1,356 @@
+use crate::{
+    errors::SearchError,
+    misc::{Ls, ProgressJob},
+    models::EntryKind,
+};
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
+use regex::{Regex, RegexBuilder};
+use std::{
+    fs::Metadata,
+    os::unix::fs::{MetadataExt as _, PermissionsExt as _},
+    path::Path,
+};
+use unicode_normalization::UnicodeNormalization as _;
+
+/// ファイル検索の条件を表す構造体。
+///
+/// 指定された条件をすべて満たすエントリがマッチする。
+///
+/// # Fields
+/// * `glob` - エントリ名にマッチさせる glob パターン
+/// * `regex` - エントリ名にマッチさせる正規表現
+/// * `kind` - エントリの種類
+/// * `min_size` - 最小サイズ (バイト)
+/// * `max_size` - 最大サイズ (バイト)
+/// * `min_mtime` - 最終変更日時の下限 (UNIX 時間)
+/// * `max_mtime` - 最終変更日時の上限 (UNIX 時間)
+/// * `perm` - 立っているべきパーミッションビット
+/// * `gitignore` - `.gitignore` で除外されたエントリを検索しないか否か
+/// * `max_depth` - 検索する階層の深さの上限
+#[derive(Default)]
+pub struct SearchQuery {
+    pub glob: Option<GlobMatcher>,
+    pub regex: Option<Regex>,
+    pub kind: Option<EntryKind>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub min_mtime: Option<i64>,
+    pub max_mtime: Option<i64>,
+    pub perm: Option<u32>,
+    pub gitignore: bool,
+    pub max_depth: Option<usize>,
+}
+
+impl SearchQuery {
+    /// glob パターンをコンパイルする。
+    ///
+    /// # Arguments
+    /// * `pattern` - glob パターン
+    /// * `ignore_case` - 大文字小文字を区別しないか否か
+    ///
+    /// # Errors
+    /// - `SearchError::InvalidPattern`:
+    ///   パターンが不正である。
+    pub fn glob(pattern: &str, ignore_case: bool) -> Result<GlobMatcher> {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| SearchError::InvalidPattern(err.to_string()))?;
+        Ok(glob.compile_matcher())
+    }
+
+    /// 正規表現をコンパイルする。
+    ///
+    /// # Arguments
+    /// * `pattern` - 正規表現
+    /// * `ignore_case` - 大文字小文字を区別しないか否か
+    ///
+    /// # Errors
+    /// - `SearchError::InvalidPattern`:
+    ///   パターンが不正である。
+    pub fn regex(pattern: &str, ignore_case: bool) -> Result<Regex> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|err| SearchError::InvalidPattern(err.to_string()))?;
+        Ok(regex)
+    }
+
+    /// 指定したディレクトリの配下を走査し、マッチしたエントリを処理する。
+    ///
+    /// 起点のディレクトリ自身は対象外。
+    /// シンボリックリンクは辿らず、読み取れないエントリは無視する。
+    ///
+    /// # Arguments
+    /// * `root` - 走査の起点となるディレクトリ
+    /// * `job` - 進捗状況
+    /// * `f` - マッチしたエントリを処理する関数
+    ///   エントリのパスと、起点からの相対パスが渡される。
+    ///
+    /// # Errors
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn walk<F>(
+        &self,
+        root: &Path,
+        job: &ProgressJob,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Path, &Path) -> Result<()>,
+    {
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .standard_filters(false)
+            .follow_links(false)
+            .max_depth(self.max_depth);
+        if self.gitignore {
+            // Git リポジトリ外の `.gitignore` も尊重し、`.git` 自体も除外する。
+            builder
+                .git_ignore(true)
+                .git_global(true)
+                .git_exclude(true)
+                .parents(true)
+                .require_git(false)
+                .filter_entry(|e| e.file_name() != ".git");
+        }
+        for entry in builder.build() {
+            job.check()?;
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if entry.depth() == 0 {
+                continue;
+            }
+            job.add_files(1);
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().nfc().to_string();
+            if !self.is_match(&name, &meta) {
+                continue;
+            }
+            let rel = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            f(entry.path(), rel)?;
+        }
+        Ok(())
+    }
+
+    /// エントリが条件にマッチするか否かを判定する。
+    ///
+    /// # Arguments
+    /// * `name` - エントリ名
+    /// * `meta` - エントリのメタデータ (シンボリックリンクは辿らない)
+    fn is_match(&self, name: &str, meta: &Metadata) -> bool {
+        if self.glob.as_ref().is_some_and(|g| !g.is_match(name)) {
+            return false;
+        }
+        if self.regex.as_ref().is_some_and(|r| !r.is_match(name)) {
+            return false;
+        }
+        let kind = if meta.is_symlink() {
+            EntryKind::Symlink
+        } else if meta.is_dir() {
+            EntryKind::Dir
+        } else {
+            EntryKind::File
+        };
+        if self.kind.is_some_and(|k| k != kind) {
+            return false;
+        }
+        let size = meta.len();
+        if self.min_size.is_some_and(|n| size < n)
+            || self.max_size.is_some_and(|n| size > n)
+        {
+            return false;
+        }
+        let mtime = meta.mtime();
+        if self.min_mtime.is_some_and(|t| mtime < t)
+            || self.max_mtime.is_some_and(|t| mtime > t)
+        {
+            return false;
+        }
+        let mode = meta.permissions().mode();
+        self.perm.is_none_or(|p| mode & p == p)
+    }
+}
+
+/// ディレクトリツリーからエントリを検索する構造体。
+///
+/// マッチしたエントリは、検索の起点からの相対パスを名前とする Entry として
+/// ProgressJob に逐次追加される。
+/// 名前は起点を基準としたパスになるため、起点をカレントディレクトリとする
+/// 他のタスクにそのまま渡すことができる。
+///
+/// # Fields
+/// * `query` - 検索条件
+/// * `ls` - エントリ情報の取得に使用する Ls インスタンス
+pub struct Searcher {
+    query: SearchQuery,
+    ls: Ls,
+}
+
+impl Searcher {
+    /// 新しい Searcher インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `query` - 検索条件
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn new(query: SearchQuery, time_style: &str) -> Self {
+        Self {
+            query,
+            ls: Ls::new(time_style),
+        }
+    }
+
+    /// 指定したディレクトリの配下を検索する。
+    ///
+    /// マッチしたエントリは ProgressJob に追加される。
+    ///
+    /// # Arguments
+    /// * `root` - 検索の起点となるディレクトリ
+    /// * `job` - 進捗状況
+    ///
+    /// # Returns
+    /// マッチしたエントリの数
+    ///
+    /// # Errors
+    /// - `FsError::Aborted`:
+    ///   処理が中止された。
+    pub fn search(&self, root: &Path, job: &ProgressJob) -> Result<u64> {
+        let mut count = 0;
+        self.query.walk(root, job, |path, rel| {
+            let mut ent = self.ls.entry(path);
+            ent.name = rel.to_string_lossy().nfc().to_string();
+            job.emit(serde_json::to_value(ent)?);
+            count += 1;
+            Ok(())
+        })?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::FsError,
+        test_helpers::{assert_err, setup_resources, teardown_resources},
+    };
+
+    use std::fs;
+
+    use super::*;
+
+    fn search(path: &str, query: SearchQuery) -> Result<Vec<String>> {
+        let job = ProgressJob::new();
+        let searcher = Searcher::new(query, "%y/%m/%d %H:%M:%S");
+        let count = searcher.search(Path::new(path), &job)?;
+        let mut names = job
+            .take_items()
+            .iter()
+            .map(|v| v["name"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names.len() as u64, count);
+        Ok(names)
+    }
+
+    #[tokio::test]
+    async fn test_search_glob() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let query = SearchQuery {
+            glob: Some(SearchQuery::glob("*.TXT", true)?),
+            ..Default::default()
+        };
+        let names = search(&path, query)?;
+        assert_eq!(names, ["test.txt", "test1/test1.txt", "test2/test2.txt"]);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_regex_and_kind() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let query = SearchQuery {
+            regex: Some(SearchQuery::regex(r"^test\d", false)?),
+            kind: Some(EntryKind::Dir),
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test1", "test2", "test3"]);
+        let query = SearchQuery {
+            kind: Some(EntryKind::Symlink),
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test1.txt's link"]);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_size_mtime_perm() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let query = SearchQuery {
+            kind: Some(EntryKind::File),
+            min_size: Some(1),
+            max_size: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test.txt"]);
+        let meta = fs::metadata(format!("{path}/test.txt"))?;
+        let query = SearchQuery {
+            kind: Some(EntryKind::File),
+            min_mtime: Some(meta.mtime() + 1),
+            ..Default::default()
+        };
+        assert!(search(&path, query)?.is_empty());
+        let query = SearchQuery {
+            kind: Some(EntryKind::File),
+            perm: Some(0o002),
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test.txt"]);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_gitignore_and_depth() -> Result<()> {
+        let path = setup_resources("test").await?;
+        fs::write(format!("{path}/.gitignore"), "test2/\n")?;
+        let query = SearchQuery {
+            glob: Some(SearchQuery::glob("*.txt", false)?),
+            gitignore: true,
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test.txt", "test1/test1.txt"]);
+        let query = SearchQuery {
+            glob: Some(SearchQuery::glob("*.txt", false)?),
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(search(&path, query)?, ["test.txt"]);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_error() -> Result<()> {
+        let path = setup_resources("test").await?;
+        let res = SearchQuery::glob("[", false);
+        let err = SearchError::InvalidPattern("".to_owned());
+        assert_err(&res.unwrap_err(), &err);
+        let res = SearchQuery::regex("(", false);
+        assert_err(&res.unwrap_err(), &err);
+        let job = ProgressJob::new();
+        job.abort();
+        let searcher = Searcher::new(SearchQuery::default(), "%y");
+        let res = searcher.search(Path::new(&path), &job);
+        assert_err(&res.unwrap_err(), &FsError::Aborted);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
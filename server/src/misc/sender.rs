@@ -1,6 +1,7 @@
 use crate::{
     errors::{
-        BookmarkError, CommandError, SenderError, TaskError, VirtualDirError,
+        BookmarkError, CommandError, FsError, JournalError, RenameError,
+        SearchError, SenderError, TaskError, TrashError, VirtualDirError,
         WatchError,
     },
     traits::ErrorCode,
@@ -66,6 +67,12 @@ pub trait SenderTrait: Send + Sync {
     /// * `data` - ディレクトリ情報 (JSON 形式)
     async fn dir_update(&self, data: &Value) -> Result<()>;
 
+    /// ディレクトリの更新を差分として通知する。
+    ///
+    /// # Arguments
+    /// * `data` - ディレクトリ情報の差分 (JSON 形式)
+    async fn dir_diff(&self, data: &Value) -> Result<()>;
+
     /// ProgressTask の開始を通知する。
     ///
     /// # Arguments
@@ -78,13 +85,30 @@ pub trait SenderTrait: Send + Sync {
     /// # Arguments
     /// * `pid` - プロセス ID
     /// * `progress` - 進捗率 (0-100)
-    async fn progress(&self, pid: &str, progress: usize) -> Result<()>;
+    /// * `detail` - 進捗の詳細 (JSON 形式)
+    ///   処理済みのバイト数やファイル数など。詳細がない場合は null。
+    async fn progress(
+        &self,
+        pid: &str,
+        progress: usize,
+        detail: &Value,
+    ) -> Result<()>;
 
     /// ProgressTask の完了を通知する。
     ///
     /// # Arguments
     /// * `pid` - プロセス ID
-    async fn progress_end(&self, pid: &str) -> Result<()>;
+    /// * `result` - 処理結果 (JSON 形式)
+    ///   削除に失敗したエントリの一覧など。結果がない場合は null。
+    async fn progress_end(&self, pid: &str, result: &Value) -> Result<()>;
+
+    /// ProgressTask の途中経過のデータを送信する。
+    ///
+    /// # Arguments
+    /// * `pid` - プロセス ID
+    /// * `data` - 前回の送信以降に得られたデータの配列 (JSON 形式)
+    ///   検索にマッチしたエントリなど。
+    async fn progress_data(&self, pid: &str, data: &Value) -> Result<()>;
 
     /// ProgressTask のエラーを通知する。
     ///
@@ -157,25 +181,43 @@ impl SenderTrait for Sender {
         self.data("", "DIR_UPDATE", data).await
     }
 
+    async fn dir_diff(&self, data: &Value) -> Result<()> {
+        self.data("", "DIR_DIFF", data).await
+    }
+
     async fn progress_task(&self, cid: &str, pid: &str) -> Result<()> {
         let v = json!({ "pid": pid });
         self.data(cid, "PROGRESS_TASK", &v).await
     }
 
-    async fn progress(&self, pid: &str, progress: usize) -> Result<()> {
+    async fn progress(
+        &self,
+        pid: &str,
+        progress: usize,
+        detail: &Value,
+    ) -> Result<()> {
         let v = json!({
             "cid": "",
             "status": "PROGRESS",
-            "data": { "pid": pid, "progress": progress }
+            "data": { "pid": pid, "progress": progress, "detail": detail }
         });
         self.send(v).await
     }
 
-    async fn progress_end(&self, pid: &str) -> Result<()> {
+    async fn progress_end(&self, pid: &str, result: &Value) -> Result<()> {
         let v = json!({
             "cid": "",
             "status": "PROGRESS_END",
-            "data": { "pid": pid }
+            "data": { "pid": pid, "result": result }
+        });
+        self.send(v).await
+    }
+
+    async fn progress_data(&self, pid: &str, data: &Value) -> Result<()> {
+        let v = json!({
+            "cid": "",
+            "status": "PROGRESS_DATA",
+            "data": { "pid": pid, "data": data }
         });
         self.send(v).await
     }
@@ -272,8 +314,13 @@ fn err_code(err: &Error) -> &str {
         [
             BookmarkError,
             CommandError,
+            FsError,
+            JournalError,
+            RenameError,
+            SearchError,
             SenderError,
             TaskError,
+            TrashError,
             VirtualDirError,
             WatchError
         ]
@@ -0,0 +1,410 @@
+use crate::{
+    archives::{Iso, SevenZip, Tar, Tbz, Tgz, Txz, Tzst, Zip},
+    errors::VirtualDirError,
+    helpers::{ls_style_size, parent_entry},
+    managers::ArchiveIndexManager,
+    misc::Command,
+    models::{ArchiveKind, ArchiveLocation, Entry},
+    traits::Archive,
+};
+
+use anyhow::Result;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use unicode_normalization::UnicodeNormalization as _;
+
+/// 仮想ディレクトリ (アーカイブ内のディレクトリ) の一覧を作成する構造体。
+///
+/// アーカイブの走査結果は ArchiveIndexManager にキャッシュされ、
+/// 同じアーカイブ内の移動では再利用される。
+///
+/// # Fields
+/// * `archive_index_manager` - ArchiveIndexManager インスタンス
+/// * `time_style` - 日時のフォーマット文字列
+pub struct VirtualDir {
+    archive_index_manager: Arc<ArchiveIndexManager>,
+    time_style: String,
+}
+
+impl VirtualDir {
+    /// 新しい VirtualDir インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `archive_index_manager` - ArchiveIndexManager インスタンス
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn new(
+        archive_index_manager: Arc<ArchiveIndexManager>,
+        time_style: &str,
+    ) -> Self {
+        Self {
+            archive_index_manager,
+            time_style: time_style.to_owned(),
+        }
+    }
+
+    /// コマンド引数 `filters` をフィルタ用正規表現の配列に変換する。
+    ///
+    /// 不正な正規表現は無視される。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    ///
+    /// # Returns
+    /// 有効な正規表現がひとつもない場合は None
+    pub fn filters(cmd: &Command) -> Option<Vec<Regex>> {
+        cmd.arg_as_str_array("filters").and_then(|arr| {
+            let regexes: Vec<_> =
+                arr.into_iter().filter_map(|s| Regex::new(s).ok()).collect();
+            if regexes.is_empty() {
+                None
+            } else {
+                Some(regexes)
+            }
+        })
+    }
+
+    /// コマンド引数 `kind` からアーカイブの種類を取得する。
+    ///
+    /// 省略された場合は、アーカイブファイルの内容から判別する。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    /// * `archive` - アーカイブファイルのパス
+    ///
+    /// # Errors
+    /// - `VirtualDirError::UnsupportedArchive`:
+    ///   サポートされていないアーカイブ形式が指定された。
+    /// - `VirtualDirError::UnknownFormat`:
+    ///   アーカイブ形式を判別できない。
+    pub fn kind(cmd: &Command, archive: &str) -> Result<ArchiveKind> {
+        match cmd.arg_as_str("kind") {
+            Some(kind) => ArchiveKind::from_str(kind),
+            None => ArchiveKind::detect(archive),
+        }
+    }
+
+    /// 絶対パスをアーカイブ内のパスに変換する。
+    ///
+    /// 先頭のスラッシュは取り除かれ、NFC に正規化される。
+    /// アーカイブのルートは空文字列になる。
+    ///
+    /// # Arguments
+    /// * `archive` - アーカイブファイルのパス
+    /// * `path` - 変換する絶対パス
+    ///
+    /// # Returns
+    /// アーカイブ外のパスの場合は None
+    pub fn inner_path(archive: &str, path: &str) -> Option<String> {
+        let path = path.strip_prefix(archive)?;
+        if !path.is_empty() && !path.starts_with('/') {
+            return None;
+        }
+        let path = path.trim_matches('/');
+        Some(path.nfc().to_string())
+    }
+
+    /// アーカイブの種類に応じた Archive インスタンスを作成する。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn open(
+        kind: &ArchiveKind,
+        archive: &str,
+        time_style: &str,
+    ) -> Result<Box<dyn Archive>> {
+        let archive: Box<dyn Archive> = match kind {
+            ArchiveKind::Zip => Box::new(Zip::new(archive, time_style)?),
+            ArchiveKind::Tar => Box::new(Tar::new(archive, time_style)?),
+            ArchiveKind::Tgz => Box::new(Tgz::new(archive, time_style)?),
+            ArchiveKind::Txz => Box::new(Txz::new(archive, time_style)?),
+            ArchiveKind::Tbz => Box::new(Tbz::new(archive, time_style)?),
+            ArchiveKind::Tzst => Box::new(Tzst::new(archive, time_style)?),
+            ArchiveKind::SevenZip => {
+                Box::new(SevenZip::new(archive, time_style)?)
+            }
+            ArchiveKind::Iso => Box::new(Iso::new(archive, time_style)?),
+        };
+        Ok(archive)
+    }
+
+    /// 仮想ディレクトリの一覧を、cd コマンドと同じ形式で取得する。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `path` - 仮想ディレクトリの絶対パス
+    ///   例： /Users/xxxx/Desktop/archive.zip/movies/action
+    /// * `filters` - フィルタ用正規表現の配列
+    ///
+    /// # Returns
+    /// `path` と `entries` を持つ JSON
+    ///
+    /// # Errors
+    /// - `VirtualDirError::OutsideRoot`:
+    ///   アーカイブ外のパスが指定された。
+    pub fn listing(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        path: &str,
+        filters: &Option<Vec<Regex>>,
+    ) -> Result<Value> {
+        // `path` には
+        // アーカイブのファイルシステムパス＋仮想ディレクトリのパス
+        // が格納されている。
+        //
+        // 例： /Users/xxxx/Desktop/archive.zip/movies/action/
+        // ここからアーカイブのパスを取り除いて、仮想ディレクトリのパスを取得する。
+        // -> /movies/action/
+        let location = ArchiveLocation::new(*kind, archive);
+        self.nested_listing(&location, path, filters)
+    }
+
+    /// アーカイブ内のアーカイブも含めて、仮想ディレクトリの一覧を取得する。
+    ///
+    /// `path` から `location.root` を取り除いたものを、
+    /// `location.path` のアーカイブ内のパスとして扱う。
+    ///
+    /// # Arguments
+    /// * `location` - 一覧を取得するアーカイブの所在
+    /// * `path` - 仮想ディレクトリの絶対パス
+    ///   例： /Users/xxxx/Desktop/release.tgz/dist/app.zip/src
+    /// * `filters` - フィルタ用正規表現の配列
+    ///
+    /// # Returns
+    /// `path` と `entries` を持つ JSON
+    ///
+    /// # Errors
+    /// - `VirtualDirError::OutsideRoot`:
+    ///   アーカイブ外のパスが指定された。
+    pub fn nested_listing(
+        &self,
+        location: &ArchiveLocation,
+        path: &str,
+        filters: &Option<Vec<Regex>>,
+    ) -> Result<Value> {
+        let Some(cwd) = path.strip_prefix(&location.root) else {
+            return Err(VirtualDirError::OutsideRoot.into());
+        };
+        let entries =
+            self.entries(&location.kind, &location.path, cwd, filters)?;
+        Ok(json!({ "path": path, "entries": entries }))
+    }
+
+    /// 指定したパスの親ディレクトリのパスを取得する。
+    ///
+    /// パスがルートの場合、`/` を返す。
+    ///
+    /// # Arguments
+    /// * `path` - パス文字列
+    fn parent_path(&self, path: &str) -> String {
+        let mut p = Path::new(path)
+            .parent()
+            .and_then(|p| p.to_str())
+            .unwrap_or("")
+            .to_owned();
+        if p.is_empty() {
+            p += "/";
+        }
+        p
+    }
+
+    /// パスが有効か否かを検証する。
+    ///
+    /// # Arguments
+    /// * `path` - 検証するパス
+    /// * `cwd` - 基準となるディレクトリ
+    /// * `filters` - フィルタ用正規表現の配列
+    ///   Mac のリソースフォークなど、除外したいパスがある場合に指定する。
+    fn validate_path(
+        &self,
+        path: &str,
+        cwd: &str,
+        filters: &Option<Vec<Regex>>,
+    ) -> bool {
+        // カレントディレクトリ外のパスは無効とする。
+        if !path.starts_with(cwd) {
+            return false;
+        }
+        // フィルタが指定されていない場合は無条件で有効とする。
+        if filters.is_none() {
+            return true;
+        }
+        let regexes = filters.as_ref().unwrap();
+        let path = path.strip_prefix(cwd).unwrap();
+        // フィルタにマッチ「しない」場合に有効とする。
+        !regexes.iter().any(|r| r.is_match(path))
+    }
+
+    /// 空のディレクトリエントリを生成する。
+    ///
+    /// # Arguments
+    /// * `name` - ディレクトリ名
+    fn dir_entry(&self, name: &str) -> Entry {
+        Entry {
+            perm: "d---------".to_owned(),
+            name: name.to_owned(),
+            size: ls_style_size(0),
+            time: "--/--/-- --:--:--".to_owned(),
+            link: String::new(),
+            is_virtual: true,
+        }
+    }
+
+    /// アーカイブ内のエントリ一覧を取得する。
+    ///
+    /// 先頭には親ディレクトリを表すエントリが入る。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    /// * `cwd` - 基準となるディレクトリ
+    /// * `filters` - フィルタ用正規表現の配列
+    ///   Mac のリソースフォークなど、除外したいパスがある場合に指定する。
+    pub fn entries(
+        &self,
+        kind: &ArchiveKind,
+        archive: &str,
+        cwd: &str,
+        filters: &Option<Vec<Regex>>,
+    ) -> Result<Vec<Entry>> {
+        let mut parent_ent = parent_entry(archive, &self.time_style, true)?;
+        let mut cwd = cwd.to_owned();
+        if !cwd.is_empty() {
+            // 以下のように整形する。
+            // /foo/bar -> foo/bar/
+            cwd = cwd[1..].to_string() + "/";
+        }
+        let parent_p = self.parent_path(&cwd);
+        let mut archive =
+            self.archive_index_manager
+                .open(kind, archive, &self.time_style)?;
+
+        // 現在のディレクトリに存在するエントリ一覧。
+        //
+        // 例えばアーカイブの内容が以下だとすると、
+        // game/action/image.jpg
+        // game/text.txt
+        // movies/action/image.jpg
+        // movies/text.txt
+        // text.txt
+        //
+        // 格納されるエントリ一覧は以下となる。
+        // game
+        // movies
+        // text.txt
+        let mut entries: Vec<Entry> = vec![];
+
+        // スラッシュを含まない 1 セグメント (末尾スラッシュは OK) を表す。
+        // OK: foo.txt, bar.jpg, foo/, bar/
+        // NG: foo/bar/, foo/bar.txt, foo/bar/baz/foobar.jpg
+        //
+        // 現在のディレクトリにあるエントリか否かを判別するために使用する。
+        // アーカイブには全エントリのパスが入っており、下層や上層のエントリも含まれる。
+        // 欲しいのは現在のディレクトリにあるエントリ一覧なため、この判別が必要にある。
+        //
+        // foo.txt, bar.jpg は通常エントリ、foo/, bar/ はディレクトリエントリである。
+        // 一方 foo/bar/, foo/bar.txt は現在より下層にあるエントリである。
+        let re_entry = Regex::new(r"^[^/]+/?$").unwrap();
+
+        // 現在のディレクトリにあるディレクトリを取得するために使用される。
+        // 最低でもひとつのスラッシュが含まれている必要がある。
+        // OK: foo/, foo/bar/, foo/bar/baz.txt
+        // NG: foo.txt, bar.jpg, foo, bar
+        //
+        // foo/bar/baz.txt の場合、foo がキャプチャされる。
+        // bar/ の場合、bar がキャプチャされる。
+        let re_dir = Regex::new(r"^([^/]+)/").unwrap();
+
+        // アーカイブに含まれる全エントリをひとつずつ処理する。
+        // アーカイブの作成方法によって、ディレクトリエントリが含まれる場合と
+        // 含まれない場合があることに注意。
+        // ディレクトリエントリがある場合、タイムスタンプ等のリアルなメタ情報を持った、
+        // ディレクトリのエントリインスタンスを作成できる。ない場合はダミーとなる。
+        //
+        // ディレクトリエントリあり：
+        // game/
+        // game/action/
+        // game/action/image.jpg
+        // game/text.txt
+        // movies/
+        // movies/action/
+        // movies/action/image.jpg
+        // movies/text.txt
+        // text.txt
+        //
+        // ディレクトリエントリなし：
+        // game/action/image.jpg
+        // game/text.txt
+        // movies/action/image.jpg
+        // movies/text.txt
+        // text.txt
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = entry.path();
+            if !self.validate_path(&path, &cwd, filters) {
+                continue;
+            }
+            if path == parent_p {
+                parent_ent = entry.entry("..");
+                continue;
+            }
+
+            // 通常エントリ：
+            // cwd: rust/util/
+            // path: rust/util/ascii/src/main.rs
+            // -> name: ascii/src/main.rs
+            //
+            // ディレクトリエントリ：
+            // cwd: rust/util/
+            // path: rust/util/ascii/src/
+            // -> name: ascii/src/
+            let mut name = path.strip_prefix(&cwd).unwrap_or(&path);
+
+            // re_entry にマッチするということは、アーカイブ内にエントリ情報が
+            // あったということだから、entry メソッドを使ってエントリを作成できる。
+            // タイムスタンプ等のメタ情報はリアルなものが設定される。
+            if re_entry.is_match(name) {
+                name = name.strip_suffix("/").unwrap_or(name);
+                let ent = entry.entry(name);
+                // 7z のように、ディレクトリエントリが配下のエントリより
+                // 後ろに格納されている場合は、作成済みのダミーを置き換える。
+                match entries.iter_mut().find(|e| e.name == name) {
+                    Some(e) => *e = ent,
+                    None => entries.push(ent),
+                }
+                continue;
+            }
+
+            // 現在のディレクトリにあるディレクトリの取得を試みる。
+            let Some(caps) = re_dir.captures(name) else {
+                continue;
+            };
+
+            // re_entry にマッチしなかったのでアーカイブ内にエントリ情報はない。
+            // つまりディレクトリエントリが含まれないタイプのアーカイブファイルである。
+            // この場合、自前でディレクトリエントリを作成する必要がある。
+            let dir = &caps[1];
+
+            // すでに同じ名前のディレクトリエントリが作成済みならスキップする。
+            // 例えば foo/bar/ -> foo/bar/a.txt -> foo/bar/baz/b.jpg という
+            // ループ処理の時、foo が重複作成されないようにする。
+            let exists = entries.iter().any(|e| e.name == dir);
+            if exists {
+                continue;
+            }
+
+            // ディレクトリエントリを作成して格納する。
+            // 自前なのでタイムスタンプ等のメタ情報はダミーである。
+            let ent = self.dir_entry(dir);
+            entries.push(ent);
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries.insert(0, parent_ent);
+        Ok(entries)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{misc::Ls, models::Entry};
+use crate::{
+    misc::Ls,
+    models::{DirDiff, Entry},
+};
 
 use anyhow::Result;
 use serde_json::{Value, json};
@@ -11,11 +14,15 @@ use std::sync::Arc;
 /// * `signature` - ディレクトリの変更検出用の署名
 /// * `path` - 監視中のディレクトリパス
 /// * `entries` - ディレクトリ内のエントリ一覧
+/// * `revision` - エントリ一覧のリビジョン番号
+///   変更を検出するたびにインクリメントされる。
+///   クライアントが差分の取りこぼしを検出するために使用する。
 pub struct Watch {
     ls: Arc<Ls>,
     signature: String,
     pub path: String,
     pub entries: Vec<Entry>,
+    pub revision: u64,
 }
 
 impl Watch {
@@ -29,6 +36,7 @@ impl Watch {
             signature: ls.signature(path)?,
             path: path.to_owned(),
             entries: ls.entries(path)?,
+            revision: 0,
             ls,
         })
     }
@@ -36,15 +44,46 @@ impl Watch {
     /// ディレクトリの変更をチェックする。
     ///
     /// # Returns
-    /// 変更あり：true、なし：false
-    pub fn check_updates(&mut self) -> Result<bool> {
+    /// 変更あり：前回のエントリ一覧との差分、なし：None
+    pub fn check_updates(&mut self) -> Result<Option<DirDiff>> {
         let sig = self.ls.signature(&self.path)?;
         if self.signature != sig {
             self.signature = sig;
-            self.entries = self.ls.entries(&self.path)?;
-            return Ok(true);
+            let entries = self.ls.entries(&self.path)?;
+            return Ok(self.update(entries));
         }
-        Ok(false)
+        Ok(None)
+    }
+
+    /// ディレクトリ情報を再取得し、前回のエントリ一覧と比較する。
+    ///
+    /// 変更通知を受け取った際に使用する。
+    /// 署名を介さず直接比較するため、ctime が変化しない変更も検出できる。
+    ///
+    /// # Returns
+    /// 変更あり：前回のエントリ一覧との差分、なし：None
+    pub fn reload(&mut self) -> Result<Option<DirDiff>> {
+        let entries = self.ls.entries(&self.path)?;
+        Ok(self.update(entries))
+    }
+
+    /// エントリ一覧を更新する。
+    ///
+    /// 差分がある場合のみ更新し、リビジョン番号をインクリメントする。
+    ///
+    /// # Arguments
+    /// * `entries` - 最新のエントリ一覧
+    ///
+    /// # Returns
+    /// 変更あり：前回のエントリ一覧との差分、なし：None
+    fn update(&mut self, entries: Vec<Entry>) -> Option<DirDiff> {
+        let diff = DirDiff::new(&self.entries, &entries);
+        if diff.is_empty() {
+            return None;
+        }
+        self.entries = entries;
+        self.revision += 1;
+        Some(diff)
     }
 
     /// ディレクトリ情報を JSON 形式で取得する。
@@ -52,7 +91,28 @@ impl Watch {
     /// # Returns
     /// ディレクトリ情報
     pub fn data(&self) -> Value {
-        json!({ "path": self.path, "entries": self.entries })
+        json!({
+            "path": self.path,
+            "revision": self.revision,
+            "entries": self.entries,
+        })
+    }
+
+    /// ディレクトリ情報の差分を JSON 形式で取得する。
+    ///
+    /// # Arguments
+    /// * `diff` - 前回のエントリ一覧との差分
+    ///
+    /// # Returns
+    /// ディレクトリ情報の差分
+    pub fn diff_data(&self, diff: &DirDiff) -> Value {
+        json!({
+            "path": self.path,
+            "revision": self.revision,
+            "added": diff.added,
+            "removed": diff.removed,
+            "modified": diff.modified,
+        })
     }
 }
 
@@ -83,8 +143,9 @@ mod tests {
     #[tokio::test]
     async fn test_watch_check_updates_no_change() -> Result<()> {
         let (path, mut watch) = setup().await?;
-        let updated = watch.check_updates()?;
-        assert!(!updated);
+        let diff = watch.check_updates()?;
+        assert!(diff.is_none());
+        assert_eq!(watch.revision, 0);
         assert_eq!(watch.path, path);
         assert_eq!(watch.entries.len(), 6);
         teardown_resources(&path).await?;
@@ -96,8 +157,10 @@ mod tests {
         let (path, mut watch) = setup().await?;
         let sig = watch.signature.clone();
         fs::write(format!("{path}/new.txt"), "").await?;
-        let updated = watch.check_updates()?;
-        assert!(updated);
+        let diff = watch.check_updates()?.unwrap();
+        assert_eq!(diff.added[0].name, "new.txt");
+        assert!(diff.removed.is_empty());
+        assert_eq!(watch.revision, 1);
         assert_eq!(watch.path, path);
         assert_eq!(watch.entries.len(), 7);
         assert_eq!(watch.entries[1].name, "new.txt");
@@ -106,6 +169,37 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_watch_reload() -> Result<()> {
+        let (path, mut watch) = setup().await?;
+        assert!(watch.reload()?.is_none());
+        fs::write(format!("{path}/new.txt"), "").await?;
+        fs::remove_file(format!("{path}/test.txt")).await?;
+        let diff = watch.reload()?.unwrap();
+        assert_eq!(diff.added[0].name, "new.txt");
+        assert_eq!(diff.removed, ["test.txt"]);
+        assert_eq!(watch.revision, 1);
+        assert_eq!(watch.entries.len(), 6);
+        assert_eq!(watch.entries[1].name, "new.txt");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch_diff_data() -> Result<()> {
+        let (path, mut watch) = setup().await?;
+        fs::write(format!("{path}/new.txt"), "").await?;
+        let diff = watch.reload()?.unwrap();
+        let data = watch.diff_data(&diff);
+        assert_eq!(data["path"], path);
+        assert_eq!(data["revision"], 1);
+        assert_eq!(data["added"][0]["name"], "new.txt");
+        assert_eq!(data["removed"], json!([]));
+        assert_eq!(data["modified"], json!([]));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_watch_data() -> Result<()> {
         let (path, watch) = setup().await?;
@@ -1,7 +1,7 @@
 use crate::{
     errors::WatchError,
-    misc::{Ls, SenderTrait, Watch},
-    models::{WatchControl, WatchStatus},
+    misc::{Ls, Notifier, SenderTrait, Watch},
+    models::{DirDiff, WatchControl, WatchStatus},
 };
 
 use anyhow::Result;
@@ -56,29 +56,43 @@ impl WatchInfo {
 
     /// ディレクトリの変更をバックグラウンドで監視するタスクを spawn する。
     ///
-    /// 変更のチェックは 500 ミリ秒ごとに行われる。
+    /// OS のファイルシステム通知を受け取って変更をチェックする。
+    /// 通知機能が利用できない場合は、500 ミリ秒ごとのポーリングで代替する。
     ///
     /// # Arguments
     /// * `ins` - このインスタンス
     async fn spawn(&self, ins: &Arc<Mutex<Self>>) -> JoinHandle<()> {
         let ins_ = ins.clone();
+        let notifier = Notifier::new(&self.watch.path);
         tokio::spawn(async move {
+            if let Ok(mut notifier) = notifier {
+                while notifier.changed().await {
+                    let mut raw = ins_.lock().await;
+                    let res = raw.watch.reload();
+                    raw.process(res).await;
+                }
+            }
             let duration = Duration::from_millis(500);
             loop {
                 sleep(duration).await;
-                ins_.lock().await.process().await;
+                let mut raw = ins_.lock().await;
+                let res = raw.watch.check_updates();
+                raw.process(res).await;
             }
         })
     }
 
-    /// ディレクトリの変更をチェックして購読者に通知する。
-    async fn process(&mut self) {
-        match self.watch.check_updates() {
-            // 変更があった場合、最新のディレクトリ情報を取得して全購読者に通知する。
-            Ok(true) => {
-                let data = self.data();
+    /// ディレクトリの変更チェック結果を購読者に通知する。
+    ///
+    /// # Arguments
+    /// * `res` - 変更チェックの結果
+    async fn process(&mut self, res: Result<Option<DirDiff>>) {
+        match res {
+            // 変更があった場合、前回との差分を全購読者に通知する。
+            Ok(Some(diff)) => {
+                let data = self.watch.diff_data(&diff);
                 for sub in self.subs.iter() {
-                    let _ = sub.dir_update(&data).await;
+                    let _ = sub.dir_diff(&data).await;
                 }
             }
             // エラーが発生した場合、監視を中止して全購読者にエラーを通知する。
@@ -174,9 +188,9 @@ mod tests {
     #[tokio::test]
     async fn test_process_success() -> Result<()> {
         let mut sender = setup_sender();
-        sender.expect_dir_update().times(1).returning(|data| {
-            let dir_info = serde_json::from_value::<DirInfo>(data.clone())?;
-            assert_eq!(dir_info.entries[1].name, "new.txt");
+        sender.expect_dir_diff().times(1).returning(|data| {
+            assert_eq!(data["added"][0]["name"], "new.txt");
+            assert_eq!(data["revision"], 1);
             Ok(())
         });
         let sender = Arc::new(sender);
@@ -184,7 +198,8 @@ mod tests {
         let mut info = info.lock().await;
         info.add_subscriber(sender.clone());
         fs::write(format!("{path}/new.txt"), "").await?;
-        info.process().await;
+        let res = info.watch.check_updates();
+        info.process(res).await;
         teardown_resources(&path).await?;
         Ok(())
     }
@@ -201,7 +216,8 @@ mod tests {
         let mut info = info.lock().await;
         info.add_subscriber(sender.clone());
         teardown_resources(&path).await?;
-        info.process().await;
+        let res = info.watch.check_updates();
+        info.process(res).await;
         let WatchControl { path: p, status } = rx.recv().await.unwrap();
         assert_eq!(p, path);
         assert_eq!(status, WatchStatus::Abort);
@@ -210,6 +226,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_spawn_notified() -> Result<()> {
+        let mut sender = setup_sender();
+        sender.expect_dir_diff().times(1).returning(|data| {
+            assert_eq!(data["added"][0]["name"], "new.txt");
+            assert_eq!(data["revision"], 1);
+            Ok(())
+        });
+        let sender = Arc::new(sender);
+        let (path, _rx, info) = setup().await?;
+        info.lock().await.add_subscriber(sender.clone());
+        fs::write(format!("{path}/new.txt"), "").await?;
+        // ポーリング間隔を待たずに通知される。
+        sleep(300).await;
+        info.lock().await.handle.as_ref().unwrap().abort();
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_data() -> Result<()> {
         let (path, _rx, info) = setup().await?;
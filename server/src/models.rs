@@ -1,27 +1,53 @@
 //! データモデルを提供するモジュール。
 
 mod archive_kind;
+mod archive_location;
 mod bookmark;
+mod cache_lease;
 mod client_config;
+mod conflict_policy;
+mod delete_failure;
+mod dir_diff;
 mod entry;
+mod entry_kind;
+mod grep_match;
+mod journal_entry;
 mod mime_type;
+mod operation;
 mod progress_task_arg;
 mod progress_task_config;
+mod rename_item;
 mod sh_task_config;
 mod task_arg;
 mod task_control;
 mod task_result;
+mod thumbnail_format;
+mod thumbnail_query;
+mod trash_item;
 mod watch_control;
 
 pub use archive_kind::ArchiveKind;
+pub use archive_location::ArchiveLocation;
 pub use bookmark::Bookmark;
+pub use cache_lease::CacheLease;
 pub use client_config::ClientConfig;
+pub use conflict_policy::ConflictPolicy;
+pub use delete_failure::DeleteFailure;
+pub use dir_diff::DirDiff;
 pub use entry::Entry;
+pub use entry_kind::EntryKind;
+pub use grep_match::GrepMatch;
+pub use journal_entry::JournalEntry;
 pub use mime_type::MimeType;
+pub use operation::Operation;
 pub use progress_task_arg::ProgressTaskArg;
 pub use progress_task_config::ProgressTaskConfig;
+pub use rename_item::RenameItem;
 pub use sh_task_config::ShTaskConfig;
 pub use task_arg::TaskArg;
 pub use task_control::{TaskControl, TaskStatus};
 pub use task_result::{DisposeType, TaskResult};
+pub use thumbnail_format::ThumbnailFormat;
+pub use thumbnail_query::ThumbnailQuery;
+pub use trash_item::TrashItem;
 pub use watch_control::{WatchControl, WatchStatus};
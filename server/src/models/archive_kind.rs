@@ -1,6 +1,25 @@
 use crate::errors::VirtualDirError;
 
 use anyhow::Result;
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+};
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// ISO 9660 の識別子 `CD001` が格納されている位置。
+///
+/// 最初のボリューム記述子 (セクタ 16) の 1 バイト目。
+const ISO_MAGIC_OFFSET: usize = 16 * 2048 + 1;
+
+/// 形式の判別のために読み込むファイル先頭のバイト数。
+const SNIFF_SIZE: u64 = ISO_MAGIC_OFFSET as u64 + 5;
+
+/// tar のヘッダーブロックのサイズ。
+const TAR_BLOCK_SIZE: u64 = 512;
 
 /// アーカイブの種類を表す列挙型。
 ///
@@ -8,11 +27,21 @@ use anyhow::Result;
 /// * `Zip` - zip アーカイブ (.zip)
 /// * `Tar` - tar アーカイブ (.tar)
 /// * `Tgz` - tgz アーカイブ (.tgz, .tar.gz)
-#[derive(PartialEq)]
+/// * `Txz` - txz アーカイブ (.txz, .tar.xz)
+/// * `Tbz` - tbz アーカイブ (.tbz, .tbz2, .tar.bz2)
+/// * `Tzst` - tzst アーカイブ (.tzst, .tar.zst)
+/// * `SevenZip` - 7z アーカイブ (.7z)
+/// * `Iso` - ISO 9660 のディスクイメージ (.iso)
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ArchiveKind {
     Zip,
     Tar,
     Tgz,
+    Txz,
+    Tbz,
+    Tzst,
+    SevenZip,
+    Iso,
 }
 
 impl ArchiveKind {
@@ -29,7 +58,189 @@ impl ArchiveKind {
             "zip" => Ok(Self::Zip),
             "tar" => Ok(Self::Tar),
             "tgz" => Ok(Self::Tgz),
+            "txz" => Ok(Self::Txz),
+            "tbz" => Ok(Self::Tbz),
+            "tzst" => Ok(Self::Tzst),
+            "7z" => Ok(Self::SevenZip),
+            "iso" => Ok(Self::Iso),
             _ => Err(VirtualDirError::UnsupportedArchive.into()),
         }
     }
+
+    /// ファイル名の拡張子からアーカイブの種類を判別する。
+    ///
+    /// 大文字・小文字は区別しない。
+    ///
+    /// # Arguments
+    /// * `name` - ファイル名またはパス
+    ///
+    /// # Returns
+    /// サポートされていない拡張子の場合は None
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        let exts = [
+            (".zip", Self::Zip),
+            (".tar", Self::Tar),
+            (".tgz", Self::Tgz),
+            (".tar.gz", Self::Tgz),
+            (".txz", Self::Txz),
+            (".tar.xz", Self::Txz),
+            (".tbz", Self::Tbz),
+            (".tbz2", Self::Tbz),
+            (".tar.bz2", Self::Tbz),
+            (".tzst", Self::Tzst),
+            (".tar.zst", Self::Tzst),
+            (".7z", Self::SevenZip),
+            (".iso", Self::Iso),
+        ];
+        exts.into_iter()
+            .find(|(ext, _)| name.ends_with(ext))
+            .map(|(_, kind)| kind)
+    }
+
+    /// ファイルの内容 (マジックバイト) からアーカイブの種類を判別する。
+    ///
+    /// 圧縮ファイルの場合は先頭を展開し、tar が格納されているかを確認する。
+    /// 拡張子は考慮しない。
+    ///
+    /// # Arguments
+    /// * `path` - アーカイブファイルのパス
+    ///
+    /// # Errors
+    /// - `VirtualDirError::UnknownFormat`:
+    ///   アーカイブ形式を判別できない。
+    pub fn detect(path: &str) -> Result<Self> {
+        let mut buf = Vec::new();
+        File::open(path)?.take(SNIFF_SIZE).read_to_end(&mut buf)?;
+        Self::sniff(path, &buf)?.ok_or_else(|| {
+            VirtualDirError::UnknownFormat(path.to_owned()).into()
+        })
+    }
+
+    /// ファイル先頭のバイト列からアーカイブの種類を判別する。
+    ///
+    /// # Arguments
+    /// * `path` - アーカイブファイルのパス
+    /// * `buf` - ファイル先頭のバイト列
+    ///
+    /// # Returns
+    /// 判別できない場合は None
+    fn sniff(path: &str, buf: &[u8]) -> Result<Option<Self>> {
+        if buf.get(ISO_MAGIC_OFFSET..) == Some(b"CD001") {
+            return Ok(Some(Self::Iso));
+        }
+        let Some(mime) = infer::get(buf).map(|t| t.mime_type()) else {
+            return Ok(None);
+        };
+        let reader = || -> Result<BufReader<File>> {
+            Ok(BufReader::new(File::open(path)?))
+        };
+        let kind = match mime {
+            "application/zip" => Some(Self::Zip),
+            "application/x-tar" => Some(Self::Tar),
+            "application/x-7z-compressed" => Some(Self::SevenZip),
+            "application/gzip" => {
+                Self::is_tar(GzDecoder::new(reader()?)).then_some(Self::Tgz)
+            }
+            "application/x-xz" => {
+                Self::is_tar(XzDecoder::new(reader()?)).then_some(Self::Txz)
+            }
+            "application/x-bzip2" => {
+                Self::is_tar(BzDecoder::new(reader()?)).then_some(Self::Tbz)
+            }
+            "application/zstd" => {
+                let decoder = ZstdDecoder::with_buffer(reader()?)?;
+                Self::is_tar(decoder).then_some(Self::Tzst)
+            }
+            _ => None,
+        };
+        Ok(kind)
+    }
+
+    /// 展開したデータの先頭が tar のヘッダーか否かを判定する。
+    ///
+    /// # Arguments
+    /// * `reader` - 展開済みデータのリーダー
+    fn is_tar<R: Read>(reader: R) -> bool {
+        let mut header = Vec::new();
+        if reader
+            .take(TAR_BLOCK_SIZE)
+            .read_to_end(&mut header)
+            .is_err()
+        {
+            return false;
+        }
+        infer::archive::is_tar(&header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::assert_err;
+
+    use std::{fs, io::Write as _};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(ArchiveKind::from_name("a.zip"), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::from_name("a.TAR.GZ"), Some(ArchiveKind::Tgz));
+        assert_eq!(ArchiveKind::from_name("a.tbz2"), Some(ArchiveKind::Tbz));
+        assert!(
+            ArchiveKind::from_name("/a/b.7z") == Some(ArchiveKind::SevenZip)
+        );
+        assert!(ArchiveKind::from_name("a.gz").is_none());
+        assert!(ArchiveKind::from_name("zip").is_none());
+    }
+
+    #[test]
+    fn test_detect() -> Result<()> {
+        let list = [
+            ("zip", ArchiveKind::Zip),
+            ("tar", ArchiveKind::Tar),
+            ("tgz", ArchiveKind::Tgz),
+            ("txz", ArchiveKind::Txz),
+            ("tbz", ArchiveKind::Tbz),
+            ("tzst", ArchiveKind::Tzst),
+            ("7z", ArchiveKind::SevenZip),
+            ("iso", ArchiveKind::Iso),
+        ];
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        for (ext, kind) in list {
+            let path = format!("./tests/archives/dir-entries.{ext}");
+            assert_eq!(ArchiveKind::detect(&path)?, kind);
+            // 拡張子が誤っていても、内容から判別できる。
+            let misnamed = format!("{root}/archive.{ext}.txt");
+            fs::copy(&path, &misnamed)?;
+            assert_eq!(ArchiveKind::detect(&misnamed)?, kind);
+        }
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_error() -> Result<()> {
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        let text = format!("{root}/text.zip.txt");
+        fs::write(&text, "text")?;
+        let err = ArchiveKind::detect(&text).unwrap_err();
+        assert_err(&err, &VirtualDirError::UnknownFormat(text.clone()));
+
+        // tar を含まない gzip は tgz として扱わない。
+        let gz = format!("{root}/text.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&gz)?,
+            flate2::Compression::default(),
+        );
+        encoder.write_all(b"text")?;
+        encoder.finish()?;
+        let err = ArchiveKind::detect(&gz).unwrap_err();
+        assert_err(&err, &VirtualDirError::UnknownFormat(gz.clone()));
+
+        assert!(ArchiveKind::detect(&format!("{root}/nonexistent")).is_err());
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
 }
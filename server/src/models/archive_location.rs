@@ -0,0 +1,58 @@
+use crate::models::{ArchiveKind, CacheLease};
+
+/// 仮想ディレクトリのパスが指すアーカイブの所在を表す構造体。
+///
+/// アーカイブ内のアーカイブに入っている場合、`path` は
+/// キャッシュに展開された一時ファイルを指し、
+/// `root` はそのアーカイブの仮想ディレクトリとしてのパスとなる。
+///
+/// # Fields
+/// * `kind` - アーカイブの種類
+/// * `path` - アーカイブファイルの実際のパス
+/// * `root` - 仮想ディレクトリとしてのアーカイブのパス
+///   例： /Users/xxxx/Desktop/release.tgz/dist/app.zip
+/// * `lease` - キャッシュに展開された一時ファイルの利用権
+///   保持している間、一時ファイルはキャッシュから削除されない。
+///   入れ子になっていない場合は None
+pub struct ArchiveLocation {
+    pub kind: ArchiveKind,
+    pub path: String,
+    pub root: String,
+    pub lease: Option<CacheLease>,
+}
+
+impl ArchiveLocation {
+    /// 新しい ArchiveLocation インスタンスを作成する。
+    ///
+    /// 入れ子になっていない、実ファイルのアーカイブを表す。
+    ///
+    /// # Arguments
+    /// * `kind` - アーカイブの種類
+    /// * `archive` - アーカイブファイルのパス
+    pub fn new(kind: ArchiveKind, archive: &str) -> Self {
+        Self {
+            kind,
+            path: archive.to_owned(),
+            root: archive.to_owned(),
+            lease: None,
+        }
+    }
+
+    /// 仮想ディレクトリのパスを、実際のアーカイブファイル基準のパスに変換する。
+    ///
+    /// 例： root が /a/release.tgz/app.zip、path が /tmp/xxxx/app.zip の場合、
+    /// /a/release.tgz/app.zip/src -> /tmp/xxxx/app.zip/src
+    ///
+    /// # Arguments
+    /// * `path` - 変換する仮想ディレクトリのパス
+    ///
+    /// # Returns
+    /// アーカイブ外のパスの場合は None
+    pub fn real_path(&self, path: &str) -> Option<String> {
+        let rest = path.strip_prefix(&self.root)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        Some(format!("{}{rest}", self.path))
+    }
+}
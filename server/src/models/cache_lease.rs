@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+/// キャッシュされたファイルの利用権を表す構造体。
+///
+/// 保持されている間、キャッシュされたファイルは削除されない。
+/// 複製でき、すべての複製が破棄された時点で再び削除の対象となる。
+#[derive(Clone, Debug, Default)]
+pub struct CacheLease(Arc<()>);
+
+impl CacheLease {
+    /// 新しい CacheLease インスタンスを作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 自身の他に利用者がいるか否かを返す。
+    pub fn is_held(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+/// コピー先に同名のエントリが存在する場合の処理方針を表す列挙型。
+///
+/// # Variants
+/// * `Skip` - コピーしない
+/// * `Overwrite` - 上書きする
+/// * `Rename` - 名前を変えてコピーする
+///   例： `foo.txt` -> `foo (1).txt`
+/// * `Newer` - コピー元の方が新しい場合のみ上書きする
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    Rename,
+    Newer,
+}
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+/// 削除に失敗したエントリを表す構造体。
+///
+/// # Fields
+/// * `path` - エントリのパス
+/// * `msg` - 失敗した理由
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DeleteFailure {
+    pub path: String,
+    pub msg: String,
+}
@@ -0,0 +1,88 @@
+use crate::models::Entry;
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// ディレクトリの前回の状態との差分を表す構造体。
+///
+/// # Fields
+/// * `added` - 追加されたエントリ一覧
+/// * `removed` - 削除されたエントリ名の一覧
+/// * `modified` - 変更されたエントリ一覧
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct DirDiff {
+    pub added: Vec<Entry>,
+    pub removed: Vec<String>,
+    pub modified: Vec<Entry>,
+}
+
+impl DirDiff {
+    /// 新旧のエントリ一覧から差分を作成する。
+    ///
+    /// エントリ名をキーとして比較する。
+    ///
+    /// # Arguments
+    /// * `old` - 前回のエントリ一覧
+    /// * `new` - 最新のエントリ一覧
+    pub fn new(old: &[Entry], new: &[Entry]) -> Self {
+        let olds: HashMap<&str, &Entry> =
+            old.iter().map(|e| (e.name.as_str(), e)).collect();
+        let news: HashMap<&str, &Entry> =
+            new.iter().map(|e| (e.name.as_str(), e)).collect();
+        let mut diff = Self::default();
+        for entry in new {
+            match olds.get(entry.name.as_str()) {
+                None => diff.added.push(entry.clone()),
+                Some(&e) if e != entry => diff.modified.push(entry.clone()),
+                _ => {}
+            }
+        }
+        for entry in old {
+            if !news.contains_key(entry.name.as_str()) {
+                diff.removed.push(entry.name.clone());
+            }
+        }
+        diff
+    }
+
+    /// 差分がないか否かを返す。
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: &str) -> Entry {
+        Entry {
+            perm: "-rw-r--r--".to_owned(),
+            size: size.to_owned(),
+            time: "25/01/01 00:00:00".to_owned(),
+            name: name.to_owned(),
+            link: "".to_owned(),
+            is_virtual: false,
+        }
+    }
+
+    #[test]
+    fn test_dir_diff_new() {
+        let old = [entry("a", "0"), entry("b", "0"), entry("c", "0")];
+        let new = [entry("a", "0"), entry("c", "9"), entry("d", "0")];
+        let diff = DirDiff::new(&old, &new);
+        assert_eq!(diff.added, [entry("d", "0")]);
+        assert_eq!(diff.removed, ["b"]);
+        assert_eq!(diff.modified, [entry("c", "9")]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_dir_diff_new_no_change() {
+        let old = [entry("a", "0"), entry("b", "0")];
+        let diff = DirDiff::new(&old, &old);
+        assert!(diff.is_empty());
+    }
+}
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 /// * `link` - シンボリックリンクの実体パス
 ///   リンクでない場合は空文字列。
 /// * `is_virtual` - 仮想ディレクトリ内のエントリか否か
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Entry {
     pub perm: String,
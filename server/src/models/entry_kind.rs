@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+/// エントリの種類を表す列挙型。
+///
+/// # Variants
+/// * `File` - 通常のファイル
+/// * `Dir` - ディレクトリ
+/// * `Symlink` - シンボリックリンク
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+/// ファイル内容の検索にマッチした行を表す構造体。
+///
+/// # Fields
+/// * `path` - ファイルのパス (検索の起点からの相対パス)
+/// * `line` - 行番号 (1 始まり)
+/// * `text` - 行の内容
+///   長い行は、マッチした位置の周辺だけに切り詰められる。
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line: usize,
+    pub text: String,
+}
@@ -0,0 +1,17 @@
+use crate::models::Operation;
+
+use serde::{Deserialize, Serialize};
+
+/// 操作履歴のひとつの記録を表す構造体。
+///
+/// # Fields
+/// * `id` - 記録の識別子
+/// * `time` - 操作日時文字列 (`%Y-%m-%dT%H:%M:%S`)
+/// * `op` - 操作内容
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub time: String,
+    #[serde(flatten)]
+    pub op: Operation,
+}
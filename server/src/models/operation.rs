@@ -0,0 +1,38 @@
+use crate::models::RenameItem;
+
+use serde::{Deserialize, Serialize};
+
+/// 取り消し可能なファイル操作を表す列挙型。
+///
+/// 取り消し・やり直しに必要な情報を保持する。
+/// パスはすべて絶対パス。
+///
+/// # Variants
+/// * `Rename` - リネーム (`from` から `to` へ)
+/// * `Move` - 移動 (`from` から `to` へ)
+/// * `Mkdir` - ディレクトリの作成
+/// * `Extract` - アーカイブからの展開
+///   `paths` は展開によって作成されたエントリ、
+///   `trashed` は取り消し時にゴミ箱へ移動したアイテム (`from` は元のパス、`to` は識別子)。
+/// * `Trash` - ゴミ箱への移動 (`from` は元のパス、`to` はゴミ箱内の識別子)
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Operation {
+    Rename {
+        items: Vec<RenameItem>,
+    },
+    Move {
+        items: Vec<RenameItem>,
+    },
+    Mkdir {
+        path: String,
+    },
+    Extract {
+        paths: Vec<String>,
+        #[serde(default)]
+        trashed: Vec<RenameItem>,
+    },
+    Trash {
+        items: Vec<RenameItem>,
+    },
+}
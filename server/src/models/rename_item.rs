@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// 一括リネームにおける、ひとつのエントリのリネーム内容を表す構造体。
+///
+/// # Fields
+/// * `from` - 変更前の絶対パス
+/// * `to` - 変更後の絶対パス
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RenameItem {
+    pub from: String,
+    pub to: String,
+}
@@ -1,6 +1,7 @@
 use anyhow::Error;
 use serde_json::Value;
 use std::{future::Future, pin::Pin};
+use tokio::sync::oneshot;
 
 /// タスク中止時に実行される中断処理関数の型。
 pub type DisposeType =
@@ -48,8 +49,17 @@ impl TaskResult {
     /// # Arguments
     /// * `pid` - プロセス ID
     /// * `dispose` - 中断処理関数
-    pub fn progress(pid: String, dispose: DisposeType) -> Self {
-        Self::Progress(ProgressTaskResult { pid, dispose })
+    /// * `start` - 処理本体の開始を通知する送信チャネル
+    pub fn progress(
+        pid: String,
+        dispose: DisposeType,
+        start: Option<oneshot::Sender<()>>,
+    ) -> Self {
+        Self::Progress(ProgressTaskResult {
+            pid,
+            dispose,
+            start,
+        })
     }
 }
 
@@ -79,7 +89,11 @@ pub struct DataTaskResult {
 /// # Fields
 /// * `pid` - プロセス ID
 /// * `dispose` - 中断処理関数
+/// * `start` - 処理本体の開始を通知する送信チャネル
+///   TaskManager への登録が済んだ時点で通知される。
+///   None の場合、処理本体は登録を待たずに開始されている。
 pub struct ProgressTaskResult {
     pub pid: String,
     pub dispose: DisposeType,
+    pub start: Option<oneshot::Sender<()>>,
 }
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+/// サムネイル画像の形式を表す列挙型。
+///
+/// # Variants
+/// * `Jpeg` - JPEG (品質は固定)
+/// * `Webp` - WebP (ロスレス)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    /// キャッシュファイルの拡張子を返す。
+    ///
+    /// 配信時の Content-Type は拡張子から決まる。
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+}
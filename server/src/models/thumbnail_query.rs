@@ -0,0 +1,19 @@
+use crate::models::ThumbnailFormat;
+
+use serde::Deserialize;
+
+/// サムネイル画像のリクエストのクエリパラメータを表す構造体。
+///
+/// 例： `/thumb/foo/bar.jpg?size=320&format=webp`
+///
+/// # Fields
+/// * `size` - 長辺の最大ピクセル数
+///   省略した場合は ThumbnailManager の既定値となる。
+/// * `format` - 画像の形式
+///   省略した場合は JPEG となる。
+#[derive(Deserialize)]
+pub struct ThumbnailQuery {
+    pub size: Option<u32>,
+    #[serde(default)]
+    pub format: ThumbnailFormat,
+}
@@ -0,0 +1,22 @@
+use crate::models::Entry;
+
+use serde::Serialize;
+
+/// ゴミ箱内のアイテムを表す構造体。
+///
+/// エントリ名は、ゴミ箱内の名前ではなく元の名前になる。
+///
+/// # Fields
+/// * `entry` - ゴミ箱内のエントリ
+/// * `id` - アイテムの識別子 (ゴミ箱内のエントリの絶対パス)
+/// * `origin` - 元のパス
+/// * `deleted_at` - 削除日時文字列
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    #[serde(flatten)]
+    pub entry: Entry,
+    pub id: String,
+    pub origin: String,
+    pub deleted_at: String,
+}
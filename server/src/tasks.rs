@@ -1,21 +1,53 @@
 //! タスクの実装を提供するモジュール。
 
 mod abort_progress_task;
+mod add_entries_task;
 mod bookmark_task;
 mod change_dir_task;
 mod change_virtual_dir_task;
+mod copy_task;
+mod delete_entries_task;
+mod delete_task;
 mod extract_entries_task;
+mod grep_task;
+mod history_task;
+mod mkdir_task;
+mod move_task;
 mod open_task;
+mod pack_task;
 mod progress_task;
+mod redo_task;
 mod remove_client_task;
+mod rename_entry_task;
+mod rename_task;
+mod resync_task;
+mod search_task;
 mod sh_task;
+mod trash_task;
+mod undo_task;
 
 pub use abort_progress_task::AbortProgressTask;
+pub use add_entries_task::AddEntriesTask;
 pub use bookmark_task::BookmarkTask;
 pub use change_dir_task::ChangeDirTask;
 pub use change_virtual_dir_task::ChangeVirtualDirTask;
+pub use copy_task::CopyTask;
+pub use delete_entries_task::DeleteEntriesTask;
+pub use delete_task::DeleteTask;
 pub use extract_entries_task::ExtractEntriesTask;
+pub use grep_task::GrepTask;
+pub use history_task::HistoryTask;
+pub use mkdir_task::MkdirTask;
+pub use move_task::MoveTask;
 pub use open_task::OpenTask;
+pub use pack_task::PackTask;
 pub use progress_task::ProgressTask;
+pub use redo_task::RedoTask;
 pub use remove_client_task::RemoveClientTask;
+pub use rename_entry_task::RenameEntryTask;
+pub use rename_task::RenameTask;
+pub use resync_task::ResyncTask;
+pub use search_task::SearchTask;
 pub use sh_task::ShTask;
+pub use trash_task::TrashTask;
+pub use undo_task::UndoTask;
@@ -0,0 +1,242 @@
+use crate::{
+    errors::VirtualDirError,
+    managers::ArchiveIndexManager,
+    misc::{ArchiveWriter, Command, VirtualDir},
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{fs, sync::Arc};
+use tokio::{sync::mpsc, task::spawn_blocking};
+
+/// 実ファイルをアーカイブ内のカレントディレクトリに追加するタスク。
+///
+/// 追加後の仮想ディレクトリの一覧を cvd コマンドと同じ形式で返す。
+/// 同名のエントリがあるためスキップされたファイルは `skipped` に入る。
+///
+/// # Fields
+/// * `virtual_dir` - VirtualDir インスタンス
+pub struct AddEntriesTask {
+    virtual_dir: VirtualDir,
+}
+
+impl AddEntriesTask {
+    /// 新しい AddEntriesTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `archive_index_manager` - ArchiveIndexManager インスタンス
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn new(
+        archive_index_manager: Arc<ArchiveIndexManager>,
+        time_style: &str,
+    ) -> Self {
+        Self {
+            virtual_dir: VirtualDir::new(archive_index_manager, time_style),
+        }
+    }
+}
+
+#[async_trait]
+impl TaskBase for AddEntriesTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path("archive", &cmd.cwd).is_some()
+            && cmd.arg_as_path_array("sources", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {
+                    "type": "string",
+                    "enum": [
+                        "zip", "tar", "tgz", "txz", "tbz", "tzst", "7z", "iso",
+                    ],
+                },
+                "archive": { "type": "string", "minLength": 1 },
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+                "filters": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                },
+            },
+            "required": ["archive", "sources"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let archive = cmd.arg_as_path("archive", &cmd.cwd).unwrap();
+        // kind が省略された場合は、アーカイブファイルの内容から判別する。
+        let kind = match VirtualDir::kind(cmd, &archive) {
+            Ok(kind) => kind,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+        let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
+        let filters = VirtualDir::filters(cmd);
+
+        // カレントディレクトリ (追加先) は仮想ディレクトリでなければならない。
+        let Some(dir) = VirtualDir::inner_path(&archive, &cmd.cwd) else {
+            let err = VirtualDirError::OutsideRoot;
+            return Ok(TaskResult::error(err.into()));
+        };
+
+        // ソースは実在するアーカイブ外のパスでなければならない。
+        let is_valid = |s: &String| {
+            VirtualDir::inner_path(&archive, s).is_none()
+                && fs::symlink_metadata(s).is_ok()
+        };
+        if !srcs.iter().all(is_valid) {
+            let err = VirtualDirError::Args;
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        // アーカイブ全体を書き直すため、ブロッキングスレッドで実行する。
+        let writer = ArchiveWriter::new(kind, &archive);
+        let res = spawn_blocking(move || writer.add(&srcs, &dir)).await?;
+        let skipped = match res {
+            Ok(skipped) => skipped,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+
+        let res = match self
+            .virtual_dir
+            .listing(&kind, &archive, &cmd.cwd, &filters)
+        {
+            Ok(mut data) => {
+                data["skipped"] = skipped.into();
+                TaskResult::data(data, None)
+            }
+            Err(err) => TaskResult::error(err),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        DirInfo, assert_by_schema, assert_err, create_command,
+        setup_archive_index_manager, setup_resources, setup_sender,
+        setup_task_arg, teardown_resources,
+    };
+
+    use unicode_normalization::UnicodeNormalization as _;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    async fn setup() -> Result<(
+        String,
+        Arc<TaskArg>,
+        AddEntriesTask,
+        mpsc::Sender<TaskControl>,
+    )> {
+        let path = setup_resources("test").await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let task = AddEntriesTask::new(
+            setup_archive_index_manager(),
+            "%y/%m/%d %H:%M:%S",
+        );
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, task_arg, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, _task_arg, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/add_entries_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        for kind in ["zip", "tar", "tgz", "txz", "tbz", "tzst"] {
+            let (path, task_arg, task, tx) = setup().await?;
+            let name = format!("no-dir-entries.{kind}");
+            let archive = format!("{path}/{name}");
+            fs::copy(format!("./tests/archives/{name}"), &archive)?;
+            let cwd = nfc(&format!("{archive}/1 ゲーム"));
+            let args = json!({
+                "kind": kind,
+                "archive": &archive,
+                "sources": [format!("{path}/test.txt"), format!("{path}/test1")],
+            });
+            let cmd = create_command(&cwd, "_", args.clone())?;
+            let TaskResult::Data(res) =
+                task.run(&cmd, &task_arg, tx.clone()).await
+            else {
+                unreachable!();
+            };
+            assert_eq!(res.data["skipped"], json!([]));
+            let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+            assert_eq!(dir_info.path, cwd);
+            let names = dir_info
+                .entries
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>();
+            assert!(names.contains(&"test.txt"));
+            assert!(names.contains(&"test1"));
+
+            let cmd = create_command(&cwd, "_", args)?;
+            let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await
+            else {
+                unreachable!();
+            };
+            assert_eq!(res.data["skipped"].as_array().unwrap().len(), 2);
+            teardown_resources(&path).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task_arg, task, tx) = setup().await?;
+        let archive = format!("{path}/dir-entries.zip");
+        fs::copy("./tests/archives/dir-entries.zip", &archive)?;
+        let args = json!({
+            "kind": "zip",
+            "archive": &archive,
+            "sources": [format!("{path}/test.txt")],
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) =
+            task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        assert_err(&res.err, &VirtualDirError::OutsideRoot);
+
+        let args = json!({
+            "kind": "zip",
+            "archive": &archive,
+            "sources": [format!("{path}/nonexistent")],
+        });
+        let cmd = create_command(&archive, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &VirtualDirError::Args);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
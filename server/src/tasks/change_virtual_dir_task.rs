@@ -1,28 +1,32 @@
 use crate::{
-    archives::{Tar, Tgz, Zip},
-    errors::VirtualDirError,
-    helpers::{ls_style_size, parent_entry},
-    managers::WatchManager,
-    misc::Command,
-    models::{ArchiveKind, Entry, TaskArg, TaskControl, TaskResult},
-    traits::{Archive, TaskBase},
+    managers::{ArchiveCacheManager, ArchiveIndexManager, WatchManager},
+    misc::{Command, VirtualDir},
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
 };
 
 use anyhow::Result;
 use async_trait::async_trait;
-use regex::Regex;
 use serde_json::{Value, json};
-use std::{path::Path, sync::Arc};
-use tokio::sync::{Mutex, mpsc};
+use std::sync::Arc;
+use tokio::{
+    sync::{Mutex, mpsc},
+    task::spawn_blocking,
+};
 
 /// 仮想ディレクトリを変更するタスク。
 ///
+/// パスにアーカイブ内のアーカイブが含まれる場合は、
+/// それを展開してさらに中に入る。
+///
 /// # Fields
 /// * `watch_manager` - WatchManager インスタンス
-/// * `time_style` - 日時のフォーマット文字列
+/// * `archive_cache_manager` - ArchiveCacheManager インスタンス
+/// * `virtual_dir` - VirtualDir インスタンス
 pub struct ChangeVirtualDirTask {
     watch_manager: Arc<Mutex<WatchManager>>,
-    time_style: String,
+    archive_cache_manager: Arc<ArchiveCacheManager>,
+    virtual_dir: Arc<VirtualDir>,
 }
 
 impl ChangeVirtualDirTask {
@@ -30,224 +34,23 @@ impl ChangeVirtualDirTask {
     ///
     /// # Arguments
     /// * `watch_manager` - WatchManager インスタンス
+    /// * `archive_cache_manager` - ArchiveCacheManager インスタンス
+    /// * `archive_index_manager` - ArchiveIndexManager インスタンス
     /// * `time_style` - 日時のフォーマット文字列
     pub fn new(
         watch_manager: Arc<Mutex<WatchManager>>,
+        archive_cache_manager: Arc<ArchiveCacheManager>,
+        archive_index_manager: Arc<ArchiveIndexManager>,
         time_style: &str,
     ) -> Self {
         Self {
             watch_manager,
-            time_style: time_style.to_owned(),
-        }
-    }
-
-    /// 指定したパスの親ディレクトリのパスを取得する。
-    ///
-    /// パスがルートの場合、`/` を返す。
-    ///
-    /// # Arguments
-    /// * `path` - パス文字列
-    fn parent_path(&self, path: &str) -> String {
-        let mut p = Path::new(path)
-            .parent()
-            .and_then(|p| p.to_str())
-            .unwrap_or("")
-            .to_owned();
-        if p.is_empty() {
-            p += "/";
-        }
-        p
-    }
-
-    /// パスが有効か否かを検証する。
-    ///
-    /// # Arguments
-    /// * `path` - 検証するパス
-    /// * `cwd` - 基準となるディレクトリ
-    /// * `filters` - フィルタ用正規表現の配列
-    ///   Mac のリソースフォークなど、除外したいパスがある場合に指定する。
-    fn validate_path(
-        &self,
-        path: &str,
-        cwd: &str,
-        filters: &Option<Vec<Regex>>,
-    ) -> bool {
-        // カレントディレクトリ外のパスは無効とする。
-        if !path.starts_with(cwd) {
-            return false;
-        }
-        // フィルタが指定されていない場合は無条件で有効とする。
-        if filters.is_none() {
-            return true;
-        }
-        let regexes = filters.as_ref().unwrap();
-        let path = path.strip_prefix(cwd).unwrap();
-        // フィルタにマッチ「しない」場合に有効とする。
-        !regexes.iter().any(|r| r.is_match(path))
-    }
-
-    /// 空のディレクトリエントリを生成する。
-    ///
-    /// # Arguments
-    /// * `name` - ディレクトリ名
-    fn dir_entry(&self, name: &str) -> Entry {
-        Entry {
-            perm: "d---------".to_owned(),
-            name: name.to_owned(),
-            size: ls_style_size(0),
-            time: "--/--/-- --:--:--".to_owned(),
-            link: String::new(),
-            is_virtual: true,
-        }
-    }
-
-    /// アーカイブ内のエントリ一覧を取得する。
-    ///
-    /// # Arguments
-    /// * `kind` - アーカイブの種類
-    /// * `archive` - アーカイブファイルのパス
-    /// * `cwd` - 基準となるディレクトリ
-    /// * `filters` - フィルタ用正規表現の配列
-    ///   Mac のリソースフォークなど、除外したいパスがある場合に指定する。
-    fn get_entries(
-        &self,
-        kind: &ArchiveKind,
-        archive: &str,
-        cwd: &str,
-        filters: &Option<Vec<Regex>>,
-    ) -> Result<Vec<Entry>> {
-        let mut parent_ent = parent_entry(archive, &self.time_style, true)?;
-        let mut cwd = cwd.to_owned();
-        if !cwd.is_empty() {
-            // 以下のように整形する。
-            // /foo/bar -> foo/bar/
-            cwd = cwd[1..].to_string() + "/";
-        }
-        let parent_p = self.parent_path(&cwd);
-        let mut archive: Box<dyn Archive> = match kind {
-            ArchiveKind::Zip => Box::new(Zip::new(archive, &self.time_style)?),
-            ArchiveKind::Tar => Box::new(Tar::new(archive, &self.time_style)?),
-            ArchiveKind::Tgz => Box::new(Tgz::new(archive, &self.time_style)?),
-        };
-
-        // 現在のディレクトリに存在するエントリ一覧。
-        //
-        // 例えばアーカイブの内容が以下だとすると、
-        // game/action/image.jpg
-        // game/text.txt
-        // movies/action/image.jpg
-        // movies/text.txt
-        // text.txt
-        //
-        // 格納されるエントリ一覧は以下となる。
-        // game
-        // movies
-        // text.txt
-        let mut entries: Vec<Entry> = vec![];
-
-        // スラッシュを含まない 1 セグメント (末尾スラッシュは OK) を表す。
-        // OK: foo.txt, bar.jpg, foo/, bar/
-        // NG: foo/bar/, foo/bar.txt, foo/bar/baz/foobar.jpg
-        //
-        // 現在のディレクトリにあるエントリか否かを判別するために使用する。
-        // アーカイブには全エントリのパスが入っており、下層や上層のエントリも含まれる。
-        // 欲しいのは現在のディレクトリにあるエントリ一覧なため、この判別が必要にある。
-        //
-        // foo.txt, bar.jpg は通常エントリ、foo/, bar/ はディレクトリエントリである。
-        // 一方 foo/bar/, foo/bar.txt は現在より下層にあるエントリである。
-        let re_entry = Regex::new(r"^[^/]+/?$").unwrap();
-
-        // 現在のディレクトリにあるディレクトリを取得するために使用される。
-        // 最低でもひとつのスラッシュが含まれている必要がある。
-        // OK: foo/, foo/bar/, foo/bar/baz.txt
-        // NG: foo.txt, bar.jpg, foo, bar
-        //
-        // foo/bar/baz.txt の場合、foo がキャプチャされる。
-        // bar/ の場合、bar がキャプチャされる。
-        let re_dir = Regex::new(r"^([^/]+)/").unwrap();
-
-        // アーカイブに含まれる全エントリをひとつずつ処理する。
-        // アーカイブの作成方法によって、ディレクトリエントリが含まれる場合と
-        // 含まれない場合があることに注意。
-        // ディレクトリエントリがある場合、タイムスタンプ等のリアルなメタ情報を持った、
-        // ディレクトリのエントリインスタンスを作成できる。ない場合はダミーとなる。
-        //
-        // ディレクトリエントリあり：
-        // game/
-        // game/action/
-        // game/action/image.jpg
-        // game/text.txt
-        // movies/
-        // movies/action/
-        // movies/action/image.jpg
-        // movies/text.txt
-        // text.txt
-        //
-        // ディレクトリエントリなし：
-        // game/action/image.jpg
-        // game/text.txt
-        // movies/action/image.jpg
-        // movies/text.txt
-        // text.txt
-        for entry in archive.entries()? {
-            let entry = entry?;
-            let path = entry.path();
-            if !self.validate_path(&path, &cwd, filters) {
-                continue;
-            }
-            if path == parent_p {
-                parent_ent = entry.entry("..");
-                continue;
-            }
-
-            // 通常エントリ：
-            // cwd: rust/util/
-            // path: rust/util/ascii/src/main.rs
-            // -> name: ascii/src/main.rs
-            //
-            // ディレクトリエントリ：
-            // cwd: rust/util/
-            // path: rust/util/ascii/src/
-            // -> name: ascii/src/
-            let mut name = path.strip_prefix(&cwd).unwrap_or(&path);
-
-            // re_entry にマッチするということは、アーカイブ内にエントリ情報が
-            // あったということだから、entry メソッドを使ってエントリを作成できる。
-            // タイムスタンプ等のメタ情報はリアルなものが設定される。
-            if re_entry.is_match(name) {
-                name = name.strip_suffix("/").unwrap_or(name);
-                let ent = entry.entry(name);
-                entries.push(ent);
-                continue;
-            }
-
-            // 現在のディレクトリにあるディレクトリの取得を試みる。
-            let Some(caps) = re_dir.captures(name) else {
-                continue;
-            };
-
-            // re_entry にマッチしなかったのでアーカイブ内にエントリ情報はない。
-            // つまりディレクトリエントリが含まれないタイプのアーカイブファイルである。
-            // この場合、自前でディレクトリエントリを作成する必要がある。
-            let dir = &caps[1];
-
-            // すでに同じ名前のディレクトリエントリが作成済みならスキップする。
-            // 例えば foo/bar/ -> foo/bar/a.txt -> foo/bar/baz/b.jpg という
-            // ループ処理の時、foo が重複作成されないようにする。
-            let exists = entries.iter().any(|e| e.name == dir);
-            if exists {
-                continue;
-            }
-
-            // ディレクトリエントリを作成して格納する。
-            // 自前なのでタイムスタンプ等のメタ情報はダミーである。
-            let ent = self.dir_entry(dir);
-            entries.push(ent);
+            archive_cache_manager,
+            virtual_dir: Arc::new(VirtualDir::new(
+                archive_index_manager,
+                time_style,
+            )),
         }
-
-        entries.sort_by(|a, b| a.name.cmp(&b.name));
-        entries.insert(0, parent_ent);
-        Ok(entries)
     }
 }
 
@@ -265,7 +68,9 @@ impl TaskBase for ChangeVirtualDirTask {
             "properties": {
                 "kind": {
                     "type": "string",
-                    "enum": ["zip", "tar", "tgz"],
+                    "enum": [
+                        "zip", "tar", "tgz", "txz", "tbz", "tzst", "7z", "iso",
+                    ],
                 },
                 "archive": { "type": "string", "minLength": 1 },
                 "path": { "type": "string", "minLength": 1 },
@@ -273,8 +78,9 @@ impl TaskBase for ChangeVirtualDirTask {
                     "type": "array",
                     "items": { "type": "string", "minLength": 1 },
                 },
+                "password": { "type": "string", "minLength": 1 },
             },
-            "required": ["kind", "archive", "path"],
+            "required": ["archive", "path"],
             "additionalProperties": false,
         })
     }
@@ -285,43 +91,38 @@ impl TaskBase for ChangeVirtualDirTask {
         arg: &Arc<TaskArg>,
         _: mpsc::Sender<TaskControl>,
     ) -> Result<TaskResult> {
-        let kind = cmd.arg_as_str("kind").unwrap();
-        let kind = ArchiveKind::from_str(kind)?;
         let archive = cmd.arg_as_path("archive", &cmd.cwd).unwrap();
-        let path = cmd.arg_as_path("path", &cmd.cwd).unwrap();
-        let filters = cmd.arg_as_str_array("filters").and_then(|arr| {
-            let regexes: Vec<_> =
-                arr.into_iter().filter_map(|s| Regex::new(s).ok()).collect();
-            if regexes.is_empty() {
-                None
-            } else {
-                Some(regexes)
-            }
-        });
-
-        // `path` には
-        // アーカイブのファイルシステムパス＋仮想ディレクトリのパス
-        // が格納されている。
-        //
-        // 例： /Users/xxxx/Desktop/archive.zip/movies/action/
-        // ここからアーカイブのパスを取り除いて、仮想ディレクトリのパスを取得する。
-        // -> /movies/action/
-        //
-        // None の場合、アーカイブ外のパスを指定していることになるため
-        // VirtualDirError::OutsideRoot を返す。
-        let Some(cwd) = path.strip_prefix(&archive) else {
-            let err = VirtualDirError::OutsideRoot;
-            return Ok(TaskResult::error(err.into()));
+        // kind が省略された場合は、アーカイブファイルの内容から判別する。
+        let kind = match VirtualDir::kind(cmd, &archive) {
+            Ok(kind) => kind,
+            Err(err) => return Ok(TaskResult::error(err)),
         };
-
-        let res = match self.get_entries(&kind, &archive, cwd, &filters) {
+        let path = cmd.arg_as_path("path", &cmd.cwd).unwrap();
+        let filters = VirtualDir::filters(cmd);
+        let password = cmd.arg_as_str("password").map(str::to_owned);
+
+        // 入れ子のアーカイブに入る場合は、最も内側のアーカイブの一覧を取得する。
+        // 暗号化された zip 内のアーカイブは、パスワードを使って展開する。
+        // アーカイブ外のパスを指定している場合は
+        // VirtualDirError::OutsideRoot が返る。
+        // 展開を伴うことがあるため、ブロッキングスレッドで実行する。
+        let manager = self.archive_cache_manager.clone();
+        let virtual_dir = self.virtual_dir.clone();
+        let res = spawn_blocking(move || {
+            manager
+                .resolve(&kind, &archive, &path, password.as_deref())
+                .and_then(|loc| {
+                    virtual_dir.nested_listing(&loc, &path, &filters)
+                })
+        })
+        .await?;
+        let res = match res {
             Ok(data) => {
                 let manager = self.watch_manager.lock().await;
                 // 無事仮想ディレクトリ内に入れたため、現在の監視パスは解除しておく。
                 // 空文字を指定することで確実に解除できる。
                 // ちなみに仮想ディレクトリ内は監視対象外である。
                 manager.unwatch(&cmd.frame, "", arg).await;
-                let data = json!({ "path": path, "entries": data });
                 TaskResult::data(data, None)
             }
             Err(err) => TaskResult::error(err),
@@ -333,14 +134,16 @@ impl TaskBase for ChangeVirtualDirTask {
 #[cfg(test)]
 mod tests {
     use crate::{
+        errors::VirtualDirError,
         managers::WatchManager,
         test_helpers::{
             DirInfo, assert_by_schema, assert_err, create_command,
+            setup_archive_cache_manager, setup_archive_index_manager,
             setup_sender, setup_task_arg, teardown_resources,
         },
     };
 
-    use std::fs::copy;
+    use std::{fs::copy, path::Path};
     use tempfile::tempdir;
     use unicode_normalization::UnicodeNormalization as _;
 
@@ -367,7 +170,10 @@ mod tests {
         let sender = setup_sender();
         let task_arg = setup_task_arg(sender);
         let manager = WatchManager::new("%y/%m/%d %H:%M:%S");
-        let task = ChangeVirtualDirTask::new(manager, "%y/%m/%d %H:%M:%S");
+        let cache = setup_archive_cache_manager(&root);
+        let index = setup_archive_index_manager();
+        let time_style = "%y/%m/%d %H:%M:%S";
+        let task = ChangeVirtualDirTask::new(manager, cache, index, time_style);
         let (tx, _) = mpsc::channel::<TaskControl>(10);
         Ok((root, task_arg, task, tx))
     }
@@ -427,6 +233,15 @@ mod tests {
             ("tar", "no-dir-entries"),
             ("tgz", "dir-entries"),
             ("tgz", "no-dir-entries"),
+            ("txz", "dir-entries"),
+            ("txz", "no-dir-entries"),
+            ("tbz", "dir-entries"),
+            ("tbz", "no-dir-entries"),
+            ("tzst", "dir-entries"),
+            ("tzst", "no-dir-entries"),
+            ("7z", "dir-entries"),
+            ("7z", "no-dir-entries"),
+            ("iso", "dir-entries"),
         ];
         for (kind, archive) in list {
             let name = format!("{archive}.{kind}");
@@ -485,6 +300,125 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_run_nested() -> Result<()> {
+        let name = "nested.tgz";
+        let a = format!("./tests/archives/{name}");
+        let (path, task_arg, task, tx) = setup(&a).await?;
+        let archive = format!("{path}/{name}");
+
+        // tgz 内の zip に入る。
+        let cwd = format!("{archive}/dist");
+        let args = json!({
+            "kind": "tgz",
+            "archive": &archive,
+            "path": "dir-entries.zip",
+            "filters": ["^(__MACOSX/|\\._.+)"]
+        });
+        let cmd = create_command(&cwd, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+        assert_eq!(dir_info.path, format!("{cwd}/dir-entries.zip"));
+        assert!(dir_info.entries.len() == 7);
+        assert_eq!(dir_info.entries[1].name, ".hidden_file");
+        assert_eq!(dir_info.entries[3].name, nfc("1 ゲーム"));
+
+        // tgz 内の tar 内の 7z のディレクトリに入る。
+        let cwd = format!("{archive}/dist/inner.tar/dir-entries.7z");
+        let args = json!({
+            "kind": "tgz",
+            "archive": &archive,
+            "path": nfc("1 ゲーム/1 オープンワールド"),
+            "filters": ["^(__MACOSX/|\\._.+)"]
+        });
+        let cmd = create_command(&cwd, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+        assert!(dir_info.entries.len() == 4);
+        assert_eq!(dir_info.entries[1].name, "1 テキスト.txt");
+
+        // 入れ子のアーカイブから外側のアーカイブに戻る。
+        let args = json!({
+            "kind": "tgz",
+            "archive": &archive,
+            "path": "..",
+        });
+        let cmd = create_command(&cwd, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+        assert_eq!(dir_info.path, format!("{archive}/dist/inner.tar"));
+        assert!(dir_info.entries.len() == 2);
+        assert_eq!(dir_info.entries[1].name, "dir-entries.7z");
+
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_detect() -> Result<()> {
+        let (path, task_arg, task, tx) = setup("").await?;
+
+        // kind を省略した場合は、内容から判別される。
+        let archive = format!("{path}/archive.dat");
+        copy("./tests/archives/dir-entries.tzst", &archive)?;
+        let args = json!({
+            "archive": &archive,
+            "path": &archive,
+            "filters": ["^(__MACOSX/|\\._.+)"]
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+        assert!(dir_info.entries.len() == 7);
+
+        // 判別できない場合はエラーとなる。
+        let text = format!("{path}/text.zip");
+        copy("./tests/files/1 text.txt", &text)?;
+        let args = json!({ "archive": &text, "path": &text });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &VirtualDirError::UnknownFormat(text));
+
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_encrypted() -> Result<()> {
+        let name = "encrypted-aes.zip";
+        let a = format!("./tests/archives/{name}");
+        let (path, task_arg, task, tx) = setup(&a).await?;
+        let archive = format!("{path}/{name}");
+
+        // エントリ名は暗号化されないため、パスワードなしで一覧を取得できる。
+        let args = json!({ "archive": &archive, "path": &archive });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+        assert_eq!(dir_info.entries.len(), 3);
+        assert_eq!(dir_info.entries[1].name, "1 text.txt");
+        assert_eq!(dir_info.entries[2].name, "2 movies");
+
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_run_error() -> Result<()> {
         let name = "dir-entries.zip";
@@ -0,0 +1,208 @@
+use crate::{
+    errors::FsError,
+    misc::{Command, Copier, ProgressJob},
+    models::{ConflictPolicy, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// エントリをコピーするタスク。
+///
+/// シェルコマンドを使わずに Rust でコピーし、
+/// 進捗をバイト単位で報告する ProgressTask として動作する。
+pub struct CopyTask;
+
+impl CopyTask {
+    /// 新しい CopyTask インスタンスを生成する。
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl TaskBase for CopyTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path_array("sources", &cmd.cwd).is_some()
+            && cmd.arg_as_path("destination", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+                "destination": { "type": "string", "minLength": 1 },
+                "conflict": {
+                    "type": "string",
+                    "enum": ["skip", "overwrite", "rename", "newer"],
+                },
+            },
+            "required": ["sources", "destination"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
+        let dest = cmd.arg_as_path("destination", &cmd.cwd).unwrap();
+        // 未指定の場合はスキップとする。
+        let policy = match cmd.arg("conflict") {
+            Some(v) => serde_json::from_value::<ConflictPolicy>(v.clone())?,
+            None => ConflictPolicy::default(),
+        };
+
+        // コピー先ディレクトリが存在しない場合はエラーを返す。
+        if !Path::new(&dest).is_dir() {
+            let err = FsError::NotDir(dest);
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                let (bytes, files) = Copier::measure(&srcs)?;
+                job.set_total(bytes, files);
+                let mut copier = Copier::new(policy, job);
+                for src in srcs {
+                    copier.copy(Path::new(&src), Path::new(&dest))?;
+                }
+                Ok(Value::Null)
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_by_schema, assert_err, count_entries, create_command,
+        setup_resources, setup_sender, setup_task_arg, sleep,
+        teardown_resources,
+    };
+
+    use std::fs;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, CopyTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("test").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, CopyTask::new(), tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/copy_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1", "test.txt"],
+            "destination": "👟/test3",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        assert_eq!(count_entries(&[format!("{path}/test3")])?, 4);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_conflict() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        fs::write(format!("{path}/test3/test.txt"), "old")?;
+        let args = json!({
+            "sources": ["test.txt"],
+            "destination": "👟/test3",
+            "conflict": "overwrite",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        let content = fs::read_to_string(format!("{path}/test3/test.txt"))?;
+        assert_eq!(content, "test");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_error()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test1",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_not_dir() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test.txt",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &FsError::NotDir("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
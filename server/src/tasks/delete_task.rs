@@ -0,0 +1,200 @@
+use crate::{
+    misc::{Command, Deleter, ProgressJob},
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// エントリを削除するタスク。
+///
+/// シェルコマンドを使わずに Rust で削除する ProgressTask として動作する。
+/// 削除に失敗したエントリがあっても処理を続け、
+/// 完了時にその一覧を処理結果として送信する。
+pub struct DeleteTask;
+
+impl DeleteTask {
+    /// 新しい DeleteTask インスタンスを生成する。
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl TaskBase for DeleteTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path_array("sources", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+            },
+            "required": ["sources"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
+
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                // 削除はバイト数に依存しないため、エントリ数で進捗を算出する。
+                // 読み込めないエントリは、削除の失敗として後で記録される。
+                job.set_total(0, Deleter::count(&srcs));
+                let mut deleter = Deleter::new(job);
+                for src in &srcs {
+                    deleter.delete(Path::new(src))?;
+                }
+                Ok(json!({ "failures": deleter.failures() }))
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_by_schema, create_command, setup_resources, setup_sender,
+        setup_task_arg, sleep, teardown_resources,
+    };
+    use std::{fs, os::unix::fs::PermissionsExt as _};
+
+    use super::*;
+
+    async fn setup() -> Result<(String, DeleteTask, mpsc::Sender<TaskControl>)>
+    {
+        let path = setup_resources("test").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, DeleteTask::new(), tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/delete_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, result| {
+                assert_eq!(result, &json!({ "failures": [] }));
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let args = json!({ "sources": ["test1", "test.txt"] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        assert!(!Path::new(&format!("{path}/test1")).exists());
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_failures() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        let received = Arc::new(std::sync::Mutex::new(Value::Null));
+        let received_ = received.clone();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(move |_, result| {
+                *received_.lock().unwrap() = result.clone();
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let args = json!({ "sources": ["nowhere", "test2"] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        assert!(!Path::new(&format!("{path}/test2")).exists());
+        let result = received.lock().unwrap().clone();
+        let failures = result["failures"].as_array().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0]["path"], format!("{path}/nowhere"));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_unreadable() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let locked = format!("{path}/test1/locked");
+        fs::create_dir(&locked)?;
+        fs::write(format!("{locked}/file.txt"), "")?;
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+
+        let mut sender = setup_sender();
+        let received = Arc::new(std::sync::Mutex::new(Value::Null));
+        let received_ = received.clone();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(move |_, result| {
+                *received_.lock().unwrap() = result.clone();
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let args = json!({ "sources": ["test1", "test2", "test.txt"] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+
+        // 読み込めないディレクトリがあっても、他のエントリは削除される。
+        assert!(!Path::new(&format!("{path}/test2")).exists());
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        assert!(!Path::new(&format!("{path}/test1/test1.txt")).exists());
+
+        // root はパーミッションを無視して削除できるため、失敗は起きない。
+        let result = received.lock().unwrap().clone();
+        let failures = result["failures"].as_array().unwrap();
+        if unsafe { libc::geteuid() } != 0 {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0]["path"], locked);
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755))?;
+        } else {
+            assert!(failures.is_empty());
+        }
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
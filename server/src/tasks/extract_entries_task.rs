@@ -1,276 +1,152 @@
 use crate::{
     errors::VirtualDirError,
-    helpers::decode_string,
-    misc::Command,
-    models::{ArchiveKind, TaskArg, TaskControl, TaskResult},
+    managers::{ArchiveCacheManager, JournalManager},
+    misc::{Command, Extractor, ProgressJob},
+    models::{
+        ArchiveKind, ArchiveLocation, Operation, TaskArg, TaskControl,
+        TaskResult,
+    },
     traits::TaskBase,
 };
 
 use anyhow::{Result, bail};
 use async_trait::async_trait;
-use flate2::read::GzDecoder;
 use serde_json::{Value, json};
-use std::{
-    fs::{File, create_dir_all},
-    io::{BufReader, Read, copy},
-    os::unix::ffi::OsStringExt as _,
-    path::Path,
-    sync::Arc,
-};
-use tokio::sync::mpsc;
+use std::{path::Path, sync::Arc};
+use tokio::{sync::mpsc, task::spawn_blocking};
 
 /// アーカイブからエントリをコピーするタスク。
-pub struct ExtractEntriesTask;
+///
+/// 展開によって新たに作成されたエントリは操作履歴に記録され、
+/// 取り消すことができる。
+/// アーカイブ内のアーカイブからもコピーできる。
+/// 暗号化された zip のエントリは、引数 `password` で復号する。
+/// アーカイブの判別やパスワードの検証を含め、
+/// アーカイブの読み込みはすべてブロッキングスレッドで行い、
+/// 進捗をバイト単位で報告する ProgressTask として動作する。
+/// 中止した場合、書きかけのファイルは残らない。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+/// * `archive_cache_manager` - ArchiveCacheManager インスタンス
+pub struct ExtractEntriesTask {
+    journal_manager: Arc<JournalManager>,
+    archive_cache_manager: Arc<ArchiveCacheManager>,
+}
 
 impl ExtractEntriesTask {
     /// 新しい ExtractEntriesTask インスタンスを生成する。
-    pub fn new() -> Self {
-        Self {}
-    }
-
-    /// バイト列をパス文字列にデコードする。
-    ///
-    /// 記述を統一するため、相対パスの場合は `./` は削除する。
     ///
     /// # Arguments
-    /// * `raw` - デコードするバイト列
-    ///
-    /// # Returns
-    /// デコードされたパス文字列
-    fn decode_path(&self, raw: &[u8]) -> String {
-        let s = decode_string(raw);
-        if s.starts_with("./") {
-            return s.strip_prefix("./").unwrap().to_owned();
+    /// * `journal_manager` - JournalManager インスタンス
+    /// * `archive_cache_manager` - ArchiveCacheManager インスタンス
+    pub fn new(
+        journal_manager: Arc<JournalManager>,
+        archive_cache_manager: Arc<ArchiveCacheManager>,
+    ) -> Self {
+        Self {
+            journal_manager,
+            archive_cache_manager,
         }
-        s
-    }
-
-    /// 指定したパスがソースパスにマッチするか否かを確認する。
-    ///
-    /// 要するに、そのパスがコピーの対象かどうかを判断する。
-    ///
-    /// # Arguments
-    /// * `raw` - パスのバイト列
-    /// * `srcs` - コピーするパスの配列
-    fn is_match(&self, raw: &[u8], srcs: &[String]) -> bool {
-        // ソースパスはスラッシュ始まりなため、それに合わせる。
-        let path = format!("/{}", self.decode_path(raw));
-        // 以下の場合、マッチしているとみなす。
-        // - ソースパスと完全一致している
-        // - ソースパスから始まっている (＝ソースパスの下層にある)
-        srcs.iter()
-            .any(|s| s == &path || path.starts_with(&format!("{s}/")))
     }
 
-    /// アーカイブ内のパスをカレントディレクトリからの相対パスに変換する。
-    ///
-    /// 得られたパスはコピー先のパスとして使用される。
+    /// 展開元のアーカイブを解決し、パスワードを検証する。
     ///
-    /// # Arguments
-    /// * `archive` - アーカイブファイルのパス
-    /// * `path` - アーカイブ内のパス
-    /// * `cwd` - 基準となるディレクトリ
-    fn relative_path(
-        &self,
-        archive: &str,
-        path: &str,
-        cwd: &str,
-    ) -> Option<String> {
-        // アーカイブ内基準のカレントディレクトリを取得する。
-        // archive: /Users/xxxx/Desktop/archive.zip
-        // cwd: /Users/xxxx/Desktop/archive.zip/rust/util/ascii
-        // -> rust/util/ascii
-        let root = cwd
-            .strip_prefix(archive)
-            .map(|r| r.strip_prefix("/").unwrap_or(r))?;
-
-        // 対象エントリの相対パスを取得する。
-        // root: rust/util/ascii
-        // path: rust/util/ascii/tests/expected/ascii.txt
-        // -> tests/expected/ascii.txt
-        let path = path
-            .strip_prefix(root)
-            .map(|r| r.strip_prefix("/").unwrap_or(r))?;
-
-        Some(path.to_owned())
-    }
-
-    /// 仮想ディレクトリ内のエントリを、実ディレクトリにコピーする。
-    ///
-    /// # Arguments
-    /// * `entry` - コピーするエントリのリーダー
-    /// * `archive` - アーカイブファイルのパス
-    /// * `raw` - コピーするエントリのパスのバイト列
-    /// * `dest` - 展開先ディレクトリ
-    /// * `cwd` - 基準となるディレクトリ
-    ///
-    /// # Returns
-    /// スキップされた場合はそのパス、それ以外は空文字列
-    fn extract_entry<R: Read>(
-        &self,
-        entry: &mut R,
-        archive: &str,
-        raw: &[u8],
-        dest: &str,
-        cwd: &str,
-    ) -> Result<String> {
-        let path = self.decode_path(raw);
-        let Some(path) = self.relative_path(archive, &path, cwd) else {
-            bail!("Invalid path: {path}");
-        };
-        let dst = Path::new(dest).join(&path);
-        // ディレクトリパスなら、すべての階層のディレクトリを作成して抜ける。
-        if path.ends_with('/') {
-            create_dir_all(&dst)?;
-            return Ok("".to_owned());
-        }
-        // ファイルパスなら、親ディレクトリをすべて作成しておく。
-        // dest: /Users/xxxx/Desktop
-        // dst: /Users/xxxx/Desktop/tests/expected/ascii.txt
-        // -> /Users/xxxx/Desktop/tests/expected まで作成。
-        if let Some(p) = dst.parent() {
-            create_dir_all(p)?;
-        }
-        // すでに存在する場合は上書きせずにそのパスを返す。
-        // これはスキップされたパスを意味する。
-        if dst.exists() {
-            return Ok(dst.to_string_lossy().to_string());
-        }
-        // ファイルを書き出す。(＝コピー)
-        let mut out = File::create(dst)?;
-        copy(entry, &mut out)?;
-        Ok("".to_owned())
-    }
-
-    /// zip アーカイブからエントリをコピーする。
+    /// アーカイブの読み込みを伴うため、ブロッキングスレッドで実行すること。
+    /// 入れ子のアーカイブ内にいる場合は、最も内側のアーカイブを展開元とし、
+    /// コピーするパスと基準となるディレクトリはそのアーカイブ基準に読み替える。
     ///
     /// # Arguments
+    /// * `manager` - ArchiveCacheManager インスタンス
+    /// * `kind` - アーカイブの種類
+    ///   None の場合は、アーカイブファイルの内容から判別する。
     /// * `archive` - アーカイブファイルのパス
     /// * `srcs` - コピーするパスの配列
     /// * `dest` - 展開先ディレクトリ
     /// * `cwd` - 基準となるディレクトリ
+    /// * `password` - 暗号化された zip のパスワード
     ///
     /// # Returns
-    /// スキップされたパスの配列
-    fn copy_zip_entries(
-        &self,
+    /// 展開元のアーカイブ、アーカイブ内のコピーするパスの配列、
+    /// 読み替えた基準となるディレクトリのタプル
+    ///
+    /// # Errors
+    /// - `VirtualDirError::Args`:
+    ///   展開先ディレクトリが存在しない、またはアーカイブ外のパスが含まれる。
+    /// - `VirtualDirError::PasswordRequired`:
+    ///   パスワードが必要である。
+    /// - `VirtualDirError::WrongPassword`:
+    ///   パスワードが間違っている。
+    fn prepare(
+        manager: &ArchiveCacheManager,
+        kind: Option<ArchiveKind>,
         archive: &str,
-        srcs: &[String],
+        srcs: Vec<String>,
         dest: &str,
         cwd: &str,
-    ) -> Result<Vec<String>> {
-        let mut skipped = Vec::new();
-        let file = File::open(archive)?;
-        let mut zip = zip::ZipArchive::new(file)?;
-        for i in 0..zip.len() {
-            let raw = {
-                let entry = zip.by_index_raw(i)?;
-                entry.name_raw().to_owned()
-            };
-            if !self.is_match(&raw, srcs) {
-                continue;
-            }
-            let mut entry = zip.by_index(i)?;
-            let res =
-                self.extract_entry(&mut entry, archive, &raw, dest, cwd)?;
-            if !res.is_empty() {
-                skipped.push(res);
-            }
+        password: Option<&str>,
+    ) -> Result<(ArchiveLocation, Vec<String>, String)> {
+        // 展開先ディレクトリが存在しない場合はエラーを返す。
+        if !Path::new(dest).is_dir() {
+            bail!(VirtualDirError::Args);
         }
-        Ok(skipped)
+        let kind = match kind {
+            Some(kind) => kind,
+            None => ArchiveKind::detect(archive)?,
+        };
+        let loc = manager.resolve(&kind, archive, cwd, password)?;
+        let cwd = loc.real_path(cwd).unwrap_or(cwd.to_owned());
+
+        // srcs を以下のように整形する。
+        // ["/Users/xxxx/Desktop/archive.zip/rust/util/main.rs"]
+        // -> ["/rust/util/main.rs"]
+        let Some(srcs) = srcs
+            .into_iter()
+            .map(|s| s.strip_prefix(&loc.root).map(str::to_owned))
+            .collect::<Option<Vec<_>>>()
+        else {
+            bail!(VirtualDirError::Args);
+        };
+
+        // パスワードが必要な場合は、フロントエンドで入力を求められるよう、
+        // 展開を始める前にエラーを返す。
+        let job = ProgressJob::new();
+        Extractor::new(&job)
+            .verify_password(&loc.kind, &loc.path, &srcs, password)?;
+        Ok((loc, srcs, cwd))
     }
 
-    /// tar/tgz アーカイブからエントリをコピーする処理の共通ロジック部分。
+    /// コピー先の直下に新たに作成されるエントリのパスを取得する。
+    ///
+    /// すでに存在するエントリは、取り消しの対象にならないため除外する。
     ///
     /// # Arguments
     /// * `archive` - アーカイブファイルのパス
-    /// * `tar` - tar インスタンス
     /// * `srcs` - コピーするパスの配列
     /// * `dest` - 展開先ディレクトリ
     /// * `cwd` - 基準となるディレクトリ
-    ///
-    /// # Returns
-    /// スキップされたパスの配列
-    fn copy_tarball_entries<R: Read>(
-        &self,
+    fn new_paths(
         archive: &str,
-        tar: &mut tar::Archive<R>,
         srcs: &[String],
         dest: &str,
         cwd: &str,
-    ) -> Result<Vec<String>> {
-        let mut skipped = Vec::new();
-        for entry in tar.entries()? {
-            let mut entry = entry?;
-            let mut raw = entry.header().path_bytes().into_owned();
-            let str_p = String::from_utf8_lossy(&raw);
-            // USTAR ではファイル名が 100 バイトに制限されている。
-            // 途中で切れていそうなら path() にフォールバックする。
-            if (raw.len() >= 100 || str_p.contains('\0'))
-                && let Ok(path) = entry.path()
-            {
-                raw = path.into_owned().into_os_string().into_vec();
-            }
-            if !self.is_match(&raw, srcs) {
+    ) -> Vec<String> {
+        let mut paths = Vec::new();
+        for src in srcs {
+            let src = src.strip_prefix('/').unwrap_or(src);
+            let Some(path) = Extractor::relative_path(archive, src, cwd) else {
                 continue;
-            }
-            let res =
-                self.extract_entry(&mut entry, archive, &raw, dest, cwd)?;
-            if !res.is_empty() {
-                skipped.push(res);
+            };
+            let Some(name) = path.split('/').find(|s| !s.is_empty()) else {
+                continue;
+            };
+            let dst = Path::new(dest).join(name);
+            let dst = dst.to_string_lossy().to_string();
+            if !Path::new(&dst).exists() && !paths.contains(&dst) {
+                paths.push(dst);
             }
         }
-        Ok(skipped)
-    }
-
-    /// tar アーカイブからエントリをコピーする。
-    ///
-    /// # Arguments
-    /// * `archive` - アーカイブファイルのパス
-    /// * `srcs` - コピーするパスの配列
-    /// * `dest` - 展開先ディレクトリ
-    /// * `cwd` - 基準となるディレクトリ
-    ///
-    /// # Returns
-    /// スキップされたパスの配列
-    fn copy_tar_entries(
-        &self,
-        archive: &str,
-        srcs: &[String],
-        dest: &str,
-        cwd: &str,
-    ) -> Result<Vec<String>> {
-        let file = File::open(archive)?;
-        let mut tar = tar::Archive::new(file);
-        let skipped =
-            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
-        Ok(skipped)
-    }
-
-    /// tgz アーカイブからエントリをコピーする。
-    ///
-    /// # Arguments
-    /// * `archive` - アーカイブファイルのパス
-    /// * `srcs` - コピーするパスの配列
-    /// * `dest` - 展開先ディレクトリ
-    /// * `cwd` - 基準となるディレクトリ
-    ///
-    /// # Returns
-    /// スキップされたパスの配列
-    fn copy_tgz_entries(
-        &self,
-        archive: &str,
-        srcs: &[String],
-        dest: &str,
-        cwd: &str,
-    ) -> Result<Vec<String>> {
-        let file = File::open(archive)?;
-        let reader = BufReader::new(file);
-        let decoder = GzDecoder::new(reader);
-        let mut tar = tar::Archive::new(decoder);
-        let skipped =
-            self.copy_tarball_entries(archive, &mut tar, srcs, dest, cwd)?;
-        Ok(skipped)
+        paths
     }
 }
 
@@ -293,7 +169,9 @@ impl TaskBase for ExtractEntriesTask {
             "properties": {
                 "kind": {
                     "type": "string",
-                    "enum": ["zip", "tar", "tgz"],
+                    "enum": [
+                        "zip", "tar", "tgz", "txz", "tbz", "tzst", "7z", "iso",
+                    ],
                 },
                 "archive": { "type": "string", "minLength": 1 },
                 "sources": {
@@ -302,8 +180,9 @@ impl TaskBase for ExtractEntriesTask {
                     "minItems": 1,
                 },
                 "destination": { "type": "string", "minLength": 1 },
+                "password": { "type": "string", "minLength": 1 },
             },
-            "required": ["kind", "archive", "sources", "destination"],
+            "required": ["archive", "sources", "destination"],
             "additionalProperties": false,
         })
     }
@@ -311,67 +190,89 @@ impl TaskBase for ExtractEntriesTask {
     async fn try_run(
         &self,
         cmd: &Command,
-        _: &Arc<TaskArg>,
-        _: mpsc::Sender<TaskControl>,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
     ) -> Result<TaskResult> {
-        let kind = cmd.arg_as_str("kind").unwrap();
-        let kind = ArchiveKind::from_str(kind)?;
         let archive = cmd.arg_as_path("archive", &cmd.cwd).unwrap();
+        // kind が省略された場合は、アーカイブファイルの内容から判別する。
+        let kind = cmd.arg_as_str("kind").map(ArchiveKind::from_str);
+        let kind = match kind.transpose() {
+            Ok(kind) => kind,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
         let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
         let dest = cmd.arg_as_path("destination", &cmd.cwd).unwrap();
-
-        // 展開先ディレクトリが存在しない場合はエラーを返す。
-        if !Path::new(&dest).is_dir() {
-            let err = VirtualDirError::Args;
-            return Ok(TaskResult::error(err.into()));
-        }
-
-        // srcs を以下のように整形する。
-        // ["/Users/xxxx/Desktop/archive.zip/rust/util/main.rs"]
-        // -> ["/rust/util/main.rs"]
-        let Some(srcs) = srcs
-            .into_iter()
-            .map(|s| s.strip_prefix(&archive).map(str::to_owned))
-            .collect::<Option<Vec<_>>>()
-        else {
-            let err = VirtualDirError::Args;
-            return Ok(TaskResult::error(err.into()));
+        let password = cmd.arg_as_str("password").map(str::to_owned);
+
+        // アーカイブの読み込みを伴うため、ブロッキングスレッドで行う。
+        // パスワードのエラーは、展開を始める前にエラーレスポンスとして返す。
+        let manager = self.archive_cache_manager.clone();
+        let (dest_, cwd, password_) =
+            (dest.clone(), cmd.cwd.clone(), password.clone());
+        let res = spawn_blocking(move || {
+            Self::prepare(
+                &manager,
+                kind,
+                &archive,
+                srcs,
+                &dest_,
+                &cwd,
+                password_.as_deref(),
+            )
+        })
+        .await?;
+        let (loc, srcs, cwd) = match res {
+            Ok(res) => res,
+            Err(err) => return Ok(TaskResult::error(err)),
         };
 
-        let res = match kind {
-            ArchiveKind::Zip => {
-                self.copy_zip_entries(&archive, &srcs, &dest, &cmd.cwd)
-            }
-            ArchiveKind::Tar => {
-                self.copy_tar_entries(&archive, &srcs, &dest, &cmd.cwd)
-            }
-            ArchiveKind::Tgz => {
-                self.copy_tgz_entries(&archive, &srcs, &dest, &cmd.cwd)
-            }
-        };
+        let job = ProgressJob::new();
+        let journal = self.journal_manager.clone();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                // 入れ子のアーカイブの場合、展開が終わるまで
+                // キャッシュから削除されないよう利用権を保持しておく。
+                let _lease = loc.lease;
+                let paths = Self::new_paths(&loc.path, &srcs, &dest, &cwd);
+                let res = Extractor::new(job).extract(
+                    &loc.kind,
+                    &loc.path,
+                    &srcs,
+                    &dest,
+                    &cwd,
+                    password.as_deref(),
+                );
+
+                // 失敗または中止した場合も、作成済みのエントリは記録する。
+                let paths = paths
+                    .into_iter()
+                    .filter(|p| Path::new(p).exists())
+                    .collect::<Vec<_>>();
+                if !paths.is_empty() {
+                    let trashed = Vec::new();
+                    journal.record(Operation::Extract { paths, trashed });
+                }
+
+                // スキップされたエントリがある場合はそれを結果として返す。
+                Ok(json!({ "skipped": res? }))
+            });
 
-        // 処理結果に応じて TaskResult を返す。
-        // スキップされたエントリがある場合はそれを data として返す。
-        let res = match res {
-            Ok(skipped) if skipped.is_empty() => TaskResult::success(),
-            Ok(skipped) => {
-                let status = "SKIPPED".to_owned();
-                TaskResult::data(skipped.into(), Some(status))
-            }
-            Err(err) => TaskResult::error(err),
-        };
-        Ok(res)
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::test_helpers::{
-        assert_by_schema, assert_err, create_command, setup_sender,
+        assert_by_schema, assert_err, create_command,
+        setup_archive_cache_manager, setup_journal_manager, setup_sender,
         setup_task_arg, teardown_resources,
     };
 
-    use std::fs::copy;
+    use std::{
+        fs::{copy, read},
+        sync::Mutex,
+    };
     use tempfile::tempdir;
     use unicode_normalization::UnicodeNormalization as _;
 
@@ -397,11 +298,39 @@ mod tests {
         }
         let sender = setup_sender();
         let task_arg = setup_task_arg(sender);
-        let task = ExtractEntriesTask::new();
+        let task = ExtractEntriesTask::new(
+            setup_journal_manager(&root),
+            setup_archive_cache_manager(&root),
+        );
         let (tx, _) = mpsc::channel::<TaskControl>(10);
         Ok((root, task_arg, task, tx))
     }
 
+    /// タスクを実行し、ProgressTask の完了を待ってその結果を返す。
+    async fn run_progress(
+        task: &ExtractEntriesTask,
+        cmd: &Command,
+    ) -> Result<Value> {
+        let result = Arc::new(Mutex::new(Value::Null));
+        let result_ = result.clone();
+        let mut sender = setup_sender();
+        sender.expect_progress().returning(|_, _, _| Ok(()));
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(move |_, res| {
+                *result_.lock().unwrap() = res.clone();
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let (tx, mut rx) = mpsc::channel::<TaskControl>(10);
+        let TaskResult::Progress(_) = task.run(cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        rx.recv().await;
+        Ok(result.lock().unwrap().clone())
+    }
+
     #[tokio::test]
     async fn test_validate_by_schema() -> Result<()> {
         let (path, _task_arg, task, _) = setup("").await?;
@@ -420,11 +349,20 @@ mod tests {
             ("tar", "no-dir-entries"),
             ("tgz", "dir-entries"),
             ("tgz", "no-dir-entries"),
+            ("txz", "dir-entries"),
+            ("txz", "no-dir-entries"),
+            ("tbz", "dir-entries"),
+            ("tbz", "no-dir-entries"),
+            ("tzst", "dir-entries"),
+            ("tzst", "no-dir-entries"),
+            ("7z", "dir-entries"),
+            ("7z", "no-dir-entries"),
+            ("iso", "dir-entries"),
         ];
         for (kind, archive) in list {
             let name = format!("{archive}.{kind}");
             let a = format!("./tests/archives/{name}");
-            let (path, task_arg, task, tx) = setup(&a).await?;
+            let (path, _, task, _) = setup(&a).await?;
             let archive = format!("{path}/{name}");
 
             let args = json!({
@@ -434,8 +372,8 @@ mod tests {
                 "destination": &path,
             });
             let cmd = create_command(&archive, "_", args)?;
-            let res = task.run(&cmd, &task_arg, tx.clone()).await;
-            assert!(matches!(res, TaskResult::Success(_)));
+            let res = run_progress(&task, &cmd).await?;
+            assert_eq!(res, json!({ "skipped": [] }));
             let p = path.clone();
             let fmt = move |s: &str| nfc(&format!("{p}/1 ゲーム/{s}"));
             let p1 = fmt("1 テキスト.txt");
@@ -444,6 +382,9 @@ mod tests {
             assert!(Path::new(&p1).is_file());
             assert!(Path::new(&p2).is_dir());
             assert!(Path::new(&p3).is_file());
+            let history = task.journal_manager.history();
+            let paths = history["undo"][0]["paths"].as_array().unwrap();
+            assert_eq!(paths.len(), 3);
 
             let args = json!({
                 "kind": kind,
@@ -452,14 +393,12 @@ mod tests {
                 "destination": &path,
             });
             let cmd = create_command(&archive, "_", args)?;
-            let TaskResult::Data(res) =
-                task.run(&cmd, &task_arg, tx.clone()).await
-            else {
-                unreachable!();
-            };
-            let skipped: Vec<String> = serde_json::from_value(res.data)?;
+            let res = run_progress(&task, &cmd).await?;
+            let skipped: Vec<String> =
+                serde_json::from_value(res["skipped"].clone())?;
             assert!(skipped[0].ends_with("/1 text.txt"));
-            assert!(res.status.is_some());
+            let history = task.journal_manager.history();
+            assert_eq!(history["undo"].as_array().unwrap().len(), 1);
 
             let mut cwd = format!("{archive}/1 ゲーム/1 オープンワールド");
             cwd = nfc(&cwd);
@@ -470,8 +409,8 @@ mod tests {
                 "destination": &path,
             });
             let cmd = create_command(&cwd, "_", args)?;
-            let res = task.run(&cmd, &task_arg, tx.clone()).await;
-            assert!(matches!(res, TaskResult::Success(_)));
+            let res = run_progress(&task, &cmd).await?;
+            assert_eq!(res, json!({ "skipped": [] }));
             let p = path.clone();
             let fmt = move |s: &str| {
                 nfc(&format!("{p}/1 ゲーム/1 オープンワールド/{s}"))
@@ -488,6 +427,99 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_run_nested() -> Result<()> {
+        let name = "nested.tgz";
+        let a = format!("./tests/archives/{name}");
+        let (path, _, task, _) = setup(&a).await?;
+        let archive = format!("{path}/{name}");
+
+        let cwd = nfc(&format!("{archive}/dist/dir-entries.zip/1 ゲーム"));
+        let args = json!({
+            "kind": "tgz",
+            "archive": &archive,
+            "sources": ["1 テキスト.txt", nfc("1 オープンワールド")],
+            "destination": &path,
+        });
+        let cmd = create_command(&cwd, "_", args)?;
+        let res = run_progress(&task, &cmd).await?;
+        assert_eq!(res, json!({ "skipped": [] }));
+        let p1 = nfc(&format!("{path}/1 テキスト.txt"));
+        let p2 = nfc(&format!("{path}/1 オープンワールド/2 画像.jpg"));
+        assert!(Path::new(&p1).is_file());
+        assert!(Path::new(&p2).is_file());
+        let history = task.journal_manager.history();
+        let paths = history["undo"][0]["paths"].as_array().unwrap();
+        assert_eq!(paths.len(), 2);
+
+        // 入れ子のアーカイブそのものはファイルとしてコピーされる。
+        // kind を省略した場合は、内容から判別される。
+        let cwd = format!("{archive}/dist/inner.tar");
+        let args = json!({
+            "archive": &archive,
+            "sources": ["dir-entries.7z"],
+            "destination": &path,
+        });
+        let cmd = create_command(&cwd, "_", args)?;
+        let res = run_progress(&task, &cmd).await?;
+        assert_eq!(res, json!({ "skipped": [] }));
+        let p = format!("{path}/dir-entries.7z");
+        let expected = read("./tests/archives/dir-entries.7z")?;
+        assert_eq!(read(&p)?, expected);
+
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_encrypted() -> Result<()> {
+        for name in ["encrypted-zipcrypto.zip", "encrypted-aes.zip"] {
+            let a = format!("./tests/archives/{name}");
+            let (path, task_arg, task, tx) = setup(&a).await?;
+            let archive = format!("{path}/{name}");
+            let mut args = json!({
+                "archive": &archive,
+                "sources": ["1 text.txt", "2 movies"],
+                "destination": &path,
+            });
+
+            // パスワードなし。
+            let cmd = create_command(&archive, "_", args.clone())?;
+            let TaskResult::Error(res) =
+                task.run(&cmd, &task_arg, tx.clone()).await
+            else {
+                unreachable!();
+            };
+            let err = VirtualDirError::PasswordRequired("".to_owned());
+            assert_err(&res.err, &err);
+
+            // パスワード間違い。書きかけのファイルは残らない。
+            args["password"] = json!("wrong");
+            let cmd = create_command(&archive, "_", args.clone())?;
+            let TaskResult::Error(res) =
+                task.run(&cmd, &task_arg, tx.clone()).await
+            else {
+                unreachable!();
+            };
+            let err = VirtualDirError::WrongPassword("".to_owned());
+            assert_err(&res.err, &err);
+            assert!(!Path::new(&format!("{path}/1 text.txt")).exists());
+
+            // 正しいパスワード。
+            args["password"] = json!("footloose");
+            let cmd = create_command(&archive, "_", args)?;
+            let res = run_progress(&task, &cmd).await?;
+            assert_eq!(res, json!({ "skipped": [] }));
+            let expected = read("./tests/files/1 text.txt")?;
+            assert_eq!(read(format!("{path}/1 text.txt"))?, expected);
+            let expected = read("./tests/files/2 movies/1 text.txt")?;
+            assert_eq!(read(format!("{path}/2 movies/1 text.txt"))?, expected);
+
+            teardown_resources(&path).await?;
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_run_error() -> Result<()> {
         let name = "dir-entries.zip";
@@ -0,0 +1,201 @@
+use crate::{
+    errors::FsError,
+    misc::{Command, Grepper, ProgressJob, SearchQuery},
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// カレントディレクトリの配下のファイルから、文字列を含む行を検索するタスク。
+///
+/// ProgressTask として動作し、`kill` で中止できる。
+/// マッチした行は、ファイルのパス、行番号、行の内容からなる
+/// GrepMatch として PROGRESS_DATA で逐次送信される。
+pub struct GrepTask;
+
+impl GrepTask {
+    /// 新しい GrepTask インスタンスを生成する。
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// コマンド引数から、行にマッチさせる正規表現を作成する。
+    ///
+    /// `regex` が指定されていない場合、パターンは文字列として扱う。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    /// * `ignore_case` - 大文字小文字を区別しないか否か
+    fn regex(&self, cmd: &Command, ignore_case: bool) -> Result<Regex> {
+        let pattern = cmd.arg_as_str("pattern").unwrap();
+        let pattern = match cmd.arg("regex").and_then(Value::as_bool) {
+            Some(true) => pattern.to_owned(),
+            _ => regex::escape(pattern),
+        };
+        SearchQuery::regex(&pattern, ignore_case)
+    }
+
+    /// コマンド引数から、検索対象とするファイルの条件を作成する。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    fn query(&self, cmd: &Command) -> Result<SearchQuery> {
+        let glob = match cmd.arg_as_str("name") {
+            Some(p) => Some(SearchQuery::glob(p, false)?),
+            None => None,
+        };
+        let gitignore = cmd.arg("gitignore").and_then(Value::as_bool);
+        let max_depth = cmd.arg("maxDepth").and_then(Value::as_u64);
+        Ok(SearchQuery {
+            glob,
+            gitignore: gitignore == Some(true),
+            max_depth: max_depth.map(|n| n as usize),
+            ..Default::default()
+        })
+    }
+}
+
+#[async_trait]
+impl TaskBase for GrepTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": { "type": "string", "minLength": 1 },
+                "regex": { "type": "boolean" },
+                "ignoreCase": { "type": "boolean" },
+                "name": { "type": "string", "minLength": 1 },
+                "gitignore": { "type": "boolean" },
+                "maxDepth": { "type": "integer", "minimum": 1 },
+            },
+            "required": ["pattern"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let ignore_case = cmd.arg("ignoreCase").and_then(Value::as_bool);
+        let res = self
+            .regex(cmd, ignore_case == Some(true))
+            .and_then(|regex| Ok(Grepper::new(self.query(cmd)?, regex)));
+        let grepper = match res {
+            Ok(grepper) => grepper,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+
+        // 仮想ディレクトリ内では検索できない。
+        let root = cmd.cwd.clone();
+        if !Path::new(&root).is_dir() {
+            let err = FsError::NotDir(root);
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                let (count, files) = grepper.grep(Path::new(&root), job)?;
+                Ok(json!({ "count": count, "files": files }))
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::SearchError,
+        test_helpers::{
+            assert_by_schema, assert_err, create_command, setup_resources,
+            setup_sender, setup_task_arg, sleep, teardown_resources,
+        },
+    };
+
+    use std::sync::Mutex;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, GrepTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("foo.bar\nfooxbar").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, GrepTask::new(), tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/grep_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let data = Arc::new(Mutex::new(Value::Null));
+        let result = Arc::new(Mutex::new(Value::Null));
+        let (data_, result_) = (data.clone(), result.clone());
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_data()
+            .times(1)
+            .returning(move |_, v| {
+                *data_.lock().unwrap() = v.clone();
+                Ok(())
+            });
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(move |_, v| {
+                *result_.lock().unwrap() = v.clone();
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let args = json!({ "pattern": "FOO.BAR", "ignoreCase": true });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        let data = data.lock().unwrap().clone();
+        assert_eq!(
+            data,
+            json!([{ "path": "test.txt", "line": 1, "text": "foo.bar" }])
+        );
+        let result = result.lock().unwrap().clone();
+        assert_eq!(result, json!({ "count": 1, "files": 1 }));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({ "pattern": "(", "regex": true });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &SearchError::InvalidPattern("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    managers::JournalManager,
+    misc::Command,
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// ファイル操作の履歴を取得するタスク。
+///
+/// 取り消し可能な操作 (`undo`) とやり直し可能な操作 (`redo`) を、
+/// それぞれ新しいものから順に返す。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+pub struct HistoryTask {
+    journal_manager: Arc<JournalManager>,
+}
+
+impl HistoryTask {
+    /// 新しい HistoryTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(journal_manager: Arc<JournalManager>) -> Self {
+        Self { journal_manager }
+    }
+}
+
+#[async_trait]
+impl TaskBase for HistoryTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        _: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let data = self.journal_manager.history();
+        Ok(TaskResult::data(data, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::Operation,
+        test_helpers::{
+            assert_by_schema, create_command, setup_journal_manager,
+            setup_resources, setup_sender, setup_task_arg, teardown_resources,
+        },
+    };
+
+    use super::*;
+
+    async fn setup() -> Result<(String, HistoryTask, mpsc::Sender<TaskControl>)>
+    {
+        let path = setup_resources("").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        let task = HistoryTask::new(setup_journal_manager(&path));
+        Ok((path, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/history_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        for name in ["a", "b"] {
+            let path = format!("{path}/{name}");
+            task.journal_manager.record(Operation::Mkdir { path });
+        }
+        let cmd = create_command(&path, "_", json!({}))?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_eq!(res.data["undo"][0]["path"], format!("{path}/b"));
+        assert_eq!(res.data["undo"][1]["kind"], "mkdir");
+        assert_eq!(res.data["redo"], json!([]));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{
+    managers::JournalManager,
+    misc::Command,
+    models::{Operation, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::{fs, sync::mpsc};
+
+/// ディレクトリを作成するタスク。
+///
+/// 作成は操作履歴に記録され、取り消すことができる。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+pub struct MkdirTask {
+    journal_manager: Arc<JournalManager>,
+}
+
+impl MkdirTask {
+    /// 新しい MkdirTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(journal_manager: Arc<JournalManager>) -> Self {
+        Self { journal_manager }
+    }
+}
+
+#[async_trait]
+impl TaskBase for MkdirTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path("path", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "minLength": 1 },
+            },
+            "required": ["path"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let path = cmd.arg_as_path("path", &cmd.cwd).unwrap();
+        if let Err(err) = fs::create_dir(&path).await {
+            return Ok(TaskResult::error(err.into()));
+        }
+        self.journal_manager.record(Operation::Mkdir { path });
+        Ok(TaskResult::success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_by_schema, create_command, setup_journal_manager,
+        setup_resources, setup_sender, setup_task_arg, teardown_resources,
+    };
+
+    use std::path::Path;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, MkdirTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((
+            path.clone(),
+            MkdirTask::new(setup_journal_manager(&path)),
+            tx,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/mkdir_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let cmd = create_command(&path, "_", json!({ "path": "new" }))?;
+        let res = task.run(&cmd, &task_arg, tx.clone()).await;
+        assert!(matches!(res, TaskResult::Success(_)));
+        assert!(Path::new(&format!("{path}/new")).is_dir());
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"][0]["path"], format!("{path}/new"));
+        let res = task.run(&cmd, &task_arg, tx).await;
+        assert!(matches!(res, TaskResult::Error(_)));
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"].as_array().unwrap().len(), 1);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    errors::FsError,
+    managers::JournalManager,
+    misc::{Command, Copier, Mover, ProgressJob},
+    models::{
+        ConflictPolicy, Operation, RenameItem, TaskArg, TaskControl, TaskResult,
+    },
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// エントリを移動するタスク。
+///
+/// 同じデバイス上では `rename` で移動し、デバイスを跨ぐ場合は
+/// コピーした後に移動元を削除する ProgressTask として動作する。
+/// 移動は操作履歴に記録され、取り消すことができる。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+pub struct MoveTask {
+    journal_manager: Arc<JournalManager>,
+}
+
+impl MoveTask {
+    /// 新しい MoveTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(journal_manager: Arc<JournalManager>) -> Self {
+        Self { journal_manager }
+    }
+
+    /// エントリを移動し、取り消し可能な移動を記録する。
+    ///
+    /// 既存のエントリを上書き・マージした移動は元に戻せないため、記録しない。
+    ///
+    /// # Arguments
+    /// * `mover` - Mover インスタンス
+    /// * `srcs` - 移動元のパスの配列
+    /// * `dest` - 移動先のディレクトリ
+    /// * `items` - 記録する移動内容の格納先
+    ///   途中で失敗した場合も、それまでの移動内容は格納される。
+    fn move_all(
+        mover: &mut Mover,
+        srcs: &[String],
+        dest: &str,
+        items: &mut Vec<RenameItem>,
+    ) -> Result<()> {
+        for src in srcs {
+            let src = Path::new(src);
+            let dst = src.file_name().map(|n| Path::new(dest).join(n));
+            let existed = dst.as_ref().is_some_and(|p| p.exists());
+            let Some(moved) = mover.mv(src, Path::new(dest))? else {
+                continue;
+            };
+            if existed && Some(&moved) == dst.as_ref() {
+                continue;
+            }
+            items.push(RenameItem {
+                from: src.to_string_lossy().to_string(),
+                to: moved.to_string_lossy().to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TaskBase for MoveTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path_array("sources", &cmd.cwd).is_some()
+            && cmd.arg_as_path("destination", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+                "destination": { "type": "string", "minLength": 1 },
+                "conflict": {
+                    "type": "string",
+                    "enum": ["skip", "overwrite", "rename", "newer"],
+                },
+            },
+            "required": ["sources", "destination"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
+        let dest = cmd.arg_as_path("destination", &cmd.cwd).unwrap();
+        // 未指定の場合はスキップとする。
+        let policy = match cmd.arg("conflict") {
+            Some(v) => serde_json::from_value::<ConflictPolicy>(v.clone())?,
+            None => ConflictPolicy::default(),
+        };
+
+        // 移動先ディレクトリが存在しない場合はエラーを返す。
+        if !Path::new(&dest).is_dir() {
+            let err = FsError::NotDir(dest);
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        let journal = self.journal_manager.clone();
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                let (bytes, files) = Copier::measure(&srcs)?;
+                job.set_total(bytes, files);
+                let mut mover = Mover::new(policy, job);
+                let mut items = Vec::new();
+                let res = Self::move_all(&mut mover, &srcs, &dest, &mut items);
+                if !items.is_empty() {
+                    journal.record(Operation::Move { items });
+                }
+                res.map(|_| Value::Null)
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_by_schema, assert_err, count_entries, create_command,
+        setup_journal_manager, setup_resources, setup_sender, setup_task_arg,
+        sleep, teardown_resources,
+    };
+
+    use std::fs;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, MoveTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("test").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        let task = MoveTask::new(setup_journal_manager(&path));
+        Ok((path, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/move_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1", "test.txt"],
+            "destination": "👟/test3",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        assert_eq!(count_entries(&[format!("{path}/test3")])?, 4);
+        assert!(!Path::new(&format!("{path}/test1")).exists());
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        let history = task.journal_manager.history();
+        let items = &history["undo"][0]["items"];
+        assert_eq!(items[0]["from"], format!("{path}/test1"));
+        assert_eq!(items[1]["to"], format!("{path}/test3/test.txt"));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_conflict() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        fs::write(format!("{path}/test3/test.txt"), "old")?;
+        let args = json!({
+            "sources": ["test.txt"],
+            "destination": "👟/test3",
+            "conflict": "overwrite",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        let content = fs::read_to_string(format!("{path}/test3/test.txt"))?;
+        assert_eq!(content, "test");
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"], json!([]));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_error()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test1",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_not_dir() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test.txt",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &FsError::NotDir("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{
+    errors::FsError,
+    misc::{Command, Packer, ProgressJob},
+    models::{ArchiveKind, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{fs, path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// エントリをまとめてアーカイブを作成するタスク。
+///
+/// 外部コマンドを使わずに Rust で圧縮し、
+/// 進捗をバイト単位で報告する ProgressTask として動作する。
+/// 中止した場合、作成途中のアーカイブは削除される。
+pub struct PackTask;
+
+impl PackTask {
+    /// 新しい PackTask インスタンスを生成する。
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl TaskBase for PackTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path_array("sources", &cmd.cwd).is_some()
+            && cmd.arg_as_path("destination", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+                "destination": { "type": "string", "minLength": 1 },
+                "format": {
+                    "type": "string",
+                    "enum": ["zip", "tar", "tgz", "txz", "tbz", "tzst"],
+                },
+                "level": { "type": "integer", "minimum": 0, "maximum": 9 },
+            },
+            "required": ["sources", "destination", "format"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let srcs = cmd.arg_as_path_array("sources", &cmd.cwd).unwrap();
+        let dest = cmd.arg_as_path("destination", &cmd.cwd).unwrap();
+        let kind = cmd.arg_as_str("format").unwrap();
+        let kind = ArchiveKind::from_str(kind)?;
+        let level = cmd.arg("level").and_then(Value::as_u64).map(|n| n as u32);
+
+        // 作成先ディレクトリが存在しない場合はエラーを返す。
+        let dir = Path::new(&dest).parent().unwrap_or(Path::new("/"));
+        if !dir.is_dir() {
+            let err = FsError::NotDir(dir.to_string_lossy().to_string());
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        // 既存のファイルは上書きしない。
+        if fs::symlink_metadata(&dest).is_ok() {
+            let err = FsError::AlreadyExists(dest);
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                Packer::new(kind, level, job).pack(&srcs, Path::new(&dest))?;
+                Ok(Value::Null)
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{
+        assert_by_schema, assert_err, create_command, setup_resources,
+        setup_sender, setup_task_arg, sleep, teardown_resources,
+    };
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, PackTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("test").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, PackTask::new(), tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/pack_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1", "test.txt"],
+            "destination": "👟/test3/test.zip",
+            "format": "zip",
+            "level": 1,
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        let file = fs::File::open(format!("{path}/test3/test.zip"))?;
+        let mut zip = ZipArchive::new(file)?;
+        assert_eq!(zip.len(), 3);
+        assert_eq!(std::io::read_to_string(zip.by_name("test.txt")?)?, "test");
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_error()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let task_arg = setup_task_arg(sender);
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test1/test.tar",
+            "format": "tar",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) =
+            task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+
+        let args = json!({
+            "sources": ["test1"],
+            "destination": "👟/test.txt",
+            "format": "tgz",
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &FsError::AlreadyExists("".into()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
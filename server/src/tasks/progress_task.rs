@@ -188,7 +188,9 @@ impl ProgressTask {
                     biased;
                     _ = intv.tick() => {
                         let num = ((count + 1) as f32 / total as f32) * 100.0;
-                        let _ = sender_.progress(&pid_, num as usize).await;
+                        let _ = sender_
+                            .progress(&pid_, num as usize, &Value::Null)
+                            .await;
                     }
                     res = stdout.read_until(b'\n', &mut buf) => {
                         buf.clear(); // 使わないのでクリアする。
@@ -218,7 +220,7 @@ impl ProgressTask {
             match child_.lock().await.wait().await {
                 Ok(status) => {
                     if status.success() {
-                        let _ = sender_.progress_end(&pid_).await;
+                        let _ = sender_.progress_end(&pid_, &Value::Null).await;
                     } else {
                         // エラー時は stderr を読み取って送信する。
                         // exit code が 0 でありながらエラーということもあり得るが、
@@ -323,7 +325,7 @@ impl TaskBase for ProgressTask {
             tx,
         });
 
-        Ok(TaskResult::progress(pid, dispose))
+        Ok(TaskResult::progress(pid, dispose, None))
     }
 }
 
@@ -345,7 +347,10 @@ mod tests {
         let path = setup_resources("").await?;
         let (tx, _) = mpsc::channel::<TaskControl>(10);
         let mut sender = setup_sender();
-        sender.expect_progress_end().times(1).returning(|_| Ok(()));
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(|_, _| Ok(()));
         let task_arg = setup_task_arg(sender);
         let cmd = create_command(&path, "_", args)?;
         let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
@@ -0,0 +1,246 @@
+use crate::{
+    errors::VirtualDirError,
+    managers::ArchiveIndexManager,
+    misc::{ArchiveWriter, Command, VirtualDir},
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::{sync::mpsc, task::spawn_blocking};
+use unicode_normalization::UnicodeNormalization as _;
+
+/// アーカイブ内のエントリの名前を変更するタスク。
+///
+/// ディレクトリの場合、配下のエントリもまとめて変更される。
+/// 変更後の仮想ディレクトリの一覧を cvd コマンドと同じ形式で返す。
+///
+/// # Fields
+/// * `virtual_dir` - VirtualDir インスタンス
+pub struct RenameEntryTask {
+    virtual_dir: VirtualDir,
+}
+
+impl RenameEntryTask {
+    /// 新しい RenameEntryTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `archive_index_manager` - ArchiveIndexManager インスタンス
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn new(
+        archive_index_manager: Arc<ArchiveIndexManager>,
+        time_style: &str,
+    ) -> Self {
+        Self {
+            virtual_dir: VirtualDir::new(archive_index_manager, time_style),
+        }
+    }
+}
+
+#[async_trait]
+impl TaskBase for RenameEntryTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+            && cmd.arg_as_path("archive", &cmd.cwd).is_some()
+            && cmd.arg_as_path("source", &cmd.cwd).is_some()
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {
+                    "type": "string",
+                    "enum": [
+                        "zip", "tar", "tgz", "txz", "tbz", "tzst", "7z", "iso",
+                    ],
+                },
+                "archive": { "type": "string", "minLength": 1 },
+                "source": { "type": "string", "minLength": 1 },
+                "name": {
+                    "type": "string",
+                    "pattern": "^[^/]+$",
+                    "not": { "enum": [".", ".."] },
+                },
+                "filters": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                },
+            },
+            "required": ["archive", "source", "name"],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let archive = cmd.arg_as_path("archive", &cmd.cwd).unwrap();
+        // kind が省略された場合は、アーカイブファイルの内容から判別する。
+        let kind = match VirtualDir::kind(cmd, &archive) {
+            Ok(kind) => kind,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+        let src = cmd.arg_as_path("source", &cmd.cwd).unwrap();
+        let name = cmd.arg_as_str("name").unwrap().nfc().to_string();
+        let filters = VirtualDir::filters(cmd);
+
+        // ソースをアーカイブ内のパスに変換する。
+        // アーカイブのルート自体は名前を変更できない。
+        let Some(from) =
+            VirtualDir::inner_path(&archive, &src).filter(|p| !p.is_empty())
+        else {
+            let err = VirtualDirError::Args;
+            return Ok(TaskResult::error(err.into()));
+        };
+
+        // 変更後のパスはソースと同じディレクトリに置かれる。
+        // foo/bar.txt -> foo/baz.txt
+        let to = match from.rsplit_once('/') {
+            Some((dir, _)) => format!("{dir}/{name}"),
+            None => name,
+        };
+
+        // アーカイブ全体を書き直すため、ブロッキングスレッドで実行する。
+        let writer = ArchiveWriter::new(kind, &archive);
+        let res = spawn_blocking(move || writer.rename(&from, &to)).await?;
+        if let Err(err) = res {
+            return Ok(TaskResult::error(err));
+        }
+
+        let res = match self
+            .virtual_dir
+            .listing(&kind, &archive, &cmd.cwd, &filters)
+        {
+            Ok(data) => TaskResult::data(data, None),
+            Err(err) => TaskResult::error(err),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::ArchiveKind,
+        test_helpers::{
+            DirInfo, assert_by_schema, assert_err, create_command,
+            setup_archive_index_manager, setup_sender, setup_task_arg,
+            teardown_resources,
+        },
+    };
+
+    use regex::Regex;
+    use std::fs;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn nfc(s: &str) -> String {
+        s.nfc().to_string()
+    }
+
+    async fn setup() -> Result<(
+        String,
+        Arc<TaskArg>,
+        RenameEntryTask,
+        mpsc::Sender<TaskControl>,
+    )> {
+        let root = tempdir()?.keep().to_string_lossy().to_string();
+        let task_arg = setup_task_arg(setup_sender());
+        let task = RenameEntryTask::new(
+            setup_archive_index_manager(),
+            "%y/%m/%d %H:%M:%S",
+        );
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((root, task_arg, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, _task_arg, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/rename_entry_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let filters = Some(vec![Regex::new(r"^\._")?]);
+        for kind in ["zip", "tar", "tgz", "txz", "tbz", "tzst"] {
+            let (path, task_arg, task, tx) = setup().await?;
+            let name = format!("no-dir-entries.{kind}");
+            let archive = format!("{path}/{name}");
+            fs::copy(format!("./tests/archives/{name}"), &archive)?;
+            let cwd = nfc(&format!("{archive}/1 ゲーム"));
+            let args = json!({
+                "kind": kind,
+                "archive": &archive,
+                "source": nfc("1 オープンワールド"),
+                "name": "9 renamed",
+            });
+            let cmd = create_command(&cwd, "_", args)?;
+            let TaskResult::Data(res) =
+                task.run(&cmd, &task_arg, tx.clone()).await
+            else {
+                unreachable!();
+            };
+            let dir_info = serde_json::from_value::<DirInfo>(res.data)?;
+            assert_eq!(dir_info.path, cwd);
+            let last = dir_info.entries.last().unwrap();
+            assert_eq!(last.name, "9 renamed");
+
+            // 配下のエントリも一緒に移動している。
+            let vd = VirtualDir::new(setup_archive_index_manager(), "%y");
+            let kind = ArchiveKind::from_str(kind)?;
+            let dir = format!("{cwd}/9 renamed");
+            let data = vd.listing(&kind, &archive, &dir, &filters)?;
+            let dir_info = serde_json::from_value::<DirInfo>(data)?;
+            assert_eq!(dir_info.entries.len(), 4);
+            teardown_resources(&path).await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task_arg, task, tx) = setup().await?;
+        let archive = format!("{path}/dir-entries.zip");
+        fs::copy("./tests/archives/dir-entries.zip", &archive)?;
+        let args = json!({
+            "kind": "zip",
+            "archive": &archive,
+            "source": "1 text.txt",
+            "name": "2 image.jpg",
+        });
+        let cmd = create_command(&archive, "_", args)?;
+        let TaskResult::Error(res) =
+            task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        let err = VirtualDirError::AlreadyExists("".into());
+        assert_err(&res.err, &err);
+
+        let args = json!({
+            "kind": "zip",
+            "archive": &archive,
+            "source": "nonexistent",
+            "name": "foo",
+        });
+        let cmd = create_command(&archive, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &VirtualDirError::NotFound("".into()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
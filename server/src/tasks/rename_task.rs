@@ -0,0 +1,261 @@
+use crate::{
+    helpers::{absolutize_path, normalize_path},
+    managers::JournalManager,
+    misc::{Command, RenameRule, Renamer},
+    models::{Operation, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::mpsc;
+
+/// 複数のエントリを一括でリネームするタスク。
+///
+/// 変更後の名前は、正規表現による置換、テンプレート、
+/// または変更前のパスと変更後の名前の対応表のいずれかで指定する。
+/// ドライランの場合はリネームせず、計画だけを返す。
+/// リネームは操作履歴に記録され、取り消すことができる。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+pub struct RenameTask {
+    journal_manager: Arc<JournalManager>,
+}
+
+impl RenameTask {
+    /// 新しい RenameTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(journal_manager: Arc<JournalManager>) -> Self {
+        Self { journal_manager }
+    }
+
+    /// コマンド引数から、変更後の名前を決定する規則を作成する。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    fn rule(&self, cmd: &Command) -> Result<RenameRule> {
+        if let Some(regex) = cmd.arg("regex") {
+            let pattern = regex["pattern"].as_str().unwrap_or_default();
+            let replacement = regex["replacement"].as_str().unwrap_or_default();
+            return RenameRule::regex(pattern, replacement);
+        }
+        if let Some(template) = cmd.arg_as_str("template") {
+            let start = cmd.arg("start").and_then(Value::as_u64).unwrap_or(1);
+            let template = template.to_owned();
+            return Ok(RenameRule::Template { template, start });
+        }
+        let mut map = HashMap::new();
+        if let Some(mapping) = cmd.arg("mapping").and_then(Value::as_object) {
+            for (path, name) in mapping {
+                let path = normalize_path(&absolutize_path(path, &cmd.cwd));
+                let name = name.as_str().unwrap_or_default().to_owned();
+                map.insert(path, name);
+            }
+        }
+        Ok(RenameRule::Mapping(map))
+    }
+}
+
+#[async_trait]
+impl TaskBase for RenameTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        if !self.is_valid_args(&cmd.args) {
+            return false;
+        }
+        match cmd.arg("mapping") {
+            Some(_) => true,
+            None => cmd.arg_as_path_array("sources", &cmd.cwd).is_some(),
+        }
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "minLength": 1 },
+                    "minItems": 1,
+                },
+                "regex": {
+                    "type": "object",
+                    "properties": {
+                        "pattern": { "type": "string", "minLength": 1 },
+                        "replacement": { "type": "string" },
+                    },
+                    "required": ["pattern", "replacement"],
+                    "additionalProperties": false,
+                },
+                "template": { "type": "string", "minLength": 1 },
+                "start": { "type": "integer", "minimum": 0 },
+                "mapping": {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "string",
+                        "minLength": 1,
+                    },
+                    "minProperties": 1,
+                },
+                "dryRun": { "type": "boolean" },
+            },
+            "oneOf": [
+                {
+                    "required": ["sources", "regex"],
+                    "not": {
+                        "anyOf": [
+                            { "required": ["template"] },
+                            { "required": ["start"] },
+                            { "required": ["mapping"] },
+                        ],
+                    },
+                },
+                {
+                    "required": ["sources", "template"],
+                    "not": {
+                        "anyOf": [
+                            { "required": ["regex"] },
+                            { "required": ["mapping"] },
+                        ],
+                    },
+                },
+                {
+                    "required": ["mapping"],
+                    "not": {
+                        "anyOf": [
+                            { "required": ["sources"] },
+                            { "required": ["regex"] },
+                            { "required": ["template"] },
+                            { "required": ["start"] },
+                        ],
+                    },
+                },
+            ],
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let srcs = cmd
+            .arg_as_path_array("sources", &cmd.cwd)
+            .unwrap_or_default();
+        let dry_run = cmd.arg("dryRun").and_then(Value::as_bool);
+        let res = self
+            .rule(cmd)
+            .and_then(|rule| Renamer::new(rule).plan(&srcs))
+            .and_then(|items| {
+                if dry_run != Some(true) && !items.is_empty() {
+                    Renamer::apply(&items)?;
+                    let items = items.clone();
+                    self.journal_manager.record(Operation::Rename { items });
+                }
+                Ok(items)
+            });
+        let res = match res {
+            Ok(items) => TaskResult::data(json!(items), None),
+            Err(err) => TaskResult::error(err),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::RenameError,
+        test_helpers::{
+            assert_by_schema, assert_err, create_command,
+            setup_journal_manager, setup_resources, setup_sender,
+            setup_task_arg, teardown_resources,
+        },
+    };
+
+    use std::path::Path;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, RenameTask, mpsc::Sender<TaskControl>)>
+    {
+        let path = setup_resources("").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        let task = RenameTask::new(setup_journal_manager(&path));
+        Ok((path, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/rename_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_dry_run() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({
+            "sources": ["test1", "test2"],
+            "template": "dir_{n:02}",
+            "dryRun": true,
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_eq!(res.data[1]["to"], format!("{path}/dir_02"));
+        assert!(Path::new(&format!("{path}/test2")).is_dir());
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"], json!([]));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_mapping() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({
+            "mapping": { "test1": "test2", "👟/test2": "test1" },
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_eq!(res.data.as_array().unwrap().len(), 2);
+        assert!(Path::new(&format!("{path}/test1/test2.txt")).is_file());
+        assert!(Path::new(&format!("{path}/test2/test1.txt")).is_file());
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"][0]["items"], res.data);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let args = json!({
+            "sources": ["test1", "test2"],
+            "regex": { "pattern": "\\d$", "replacement": "" },
+        });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &RenameError::Collision("".to_owned()));
+        assert!(Path::new(&format!("{path}/test1")).is_dir());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    managers::WatchManagerTrait,
+    misc::Command,
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc};
+
+/// 監視中のディレクトリの全エントリを再送するタスク。
+///
+/// 通常、ディレクトリの更新は差分として通知される。
+/// クライアントが差分を取りこぼした場合などに、状態を同期し直すために使用する。
+/// 最新のディレクトリ情報は DIR_UPDATE として送信される。
+///
+/// # Fields
+/// * `watch_manager` - WatchManager インスタンス
+pub struct ResyncTask<T: WatchManagerTrait> {
+    watch_manager: Arc<Mutex<T>>,
+}
+
+impl<T: WatchManagerTrait> ResyncTask<T> {
+    /// 新しい ResyncTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `watch_manager` - WatchManager インスタンス
+    pub fn new(watch_manager: Arc<Mutex<T>>) -> Self {
+        Self { watch_manager }
+    }
+}
+
+#[async_trait]
+impl<T: WatchManagerTrait> TaskBase for ResyncTask<T> {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let manager = self.watch_manager.lock().await;
+        let data = match manager.data(&cmd.cwd).await {
+            Ok(data) => data,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+        arg.sender.dir_update(&data).await?;
+        Ok(TaskResult::success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::WatchError,
+        managers::{MockWatchManagerTrait, WatchManager},
+        test_helpers::{
+            DirInfo, assert_by_schema, assert_err, create_command,
+            setup_resources, setup_sender, setup_task_arg, teardown_resources,
+        },
+    };
+
+    use anyhow::bail;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let path = setup_resources("").await?;
+        let manager = Arc::new(Mutex::new(MockWatchManagerTrait::new()));
+        let task = ResyncTask::new(manager);
+        let fx_path = "./tests/fixtures/resync_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let path = setup_resources("").await?;
+        let mut sender = setup_sender();
+        sender.expect_dir_update().times(1).returning(|data| {
+            let dir_info = serde_json::from_value::<DirInfo>(data.clone())?;
+            assert_eq!(dir_info.entries[1].name, "test.txt");
+            Ok(())
+        });
+        let task_arg = setup_task_arg(sender);
+        let manager = WatchManager::new("%y/%m/%d %H:%M:%S");
+        manager.lock().await.watch("a", &path, &task_arg).await?;
+        let task = ResyncTask::new(manager);
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        let cmd = create_command(&path, "_", json!({}))?;
+        let res = task.run(&cmd, &task_arg, tx).await;
+        assert!(matches!(res, TaskResult::Success(_)));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let path = setup_resources("").await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let mut manager = MockWatchManagerTrait::new();
+        manager
+            .expect_data()
+            .returning(|p| bail!(WatchError::NotWatched(p.to_owned())));
+        let task = ResyncTask::new(Arc::new(Mutex::new(manager)));
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        let cmd = create_command(&path, "_", json!({}))?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &WatchError::NotWatched("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
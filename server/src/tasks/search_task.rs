@@ -0,0 +1,226 @@
+use crate::{
+    errors::FsError,
+    misc::{Command, ProgressJob, SearchQuery, Searcher},
+    models::{EntryKind, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::sync::mpsc;
+
+/// カレントディレクトリの配下からエントリを検索するタスク。
+///
+/// ProgressTask として動作し、`kill` で中止できる。
+/// マッチしたエントリは PROGRESS_DATA として逐次送信される。
+/// エントリ名はカレントディレクトリからの相対パスになるため、
+/// 検索結果は仮想的なディレクトリとして表示でき、
+/// 他のタスクの対象としてそのまま指定できる。
+///
+/// # Fields
+/// * `time_style` - 日時のフォーマット文字列
+pub struct SearchTask {
+    time_style: String,
+}
+
+impl SearchTask {
+    /// 新しい SearchTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `time_style` - 日時のフォーマット文字列
+    pub fn new(time_style: &str) -> Self {
+        Self {
+            time_style: time_style.to_owned(),
+        }
+    }
+
+    /// コマンド引数から検索条件を作成する。
+    ///
+    /// # Arguments
+    /// * `cmd` - コマンド
+    fn query(&self, cmd: &Command) -> Result<SearchQuery> {
+        let ignore_case = cmd.arg("ignoreCase").and_then(Value::as_bool);
+        let ignore_case = ignore_case.unwrap_or(false);
+        let glob = match cmd.arg_as_str("name") {
+            Some(p) => Some(SearchQuery::glob(p, ignore_case)?),
+            None => None,
+        };
+        let regex = match cmd.arg_as_str("regex") {
+            Some(p) => Some(SearchQuery::regex(p, ignore_case)?),
+            None => None,
+        };
+        let kind = match cmd.arg("type") {
+            Some(v) => Some(serde_json::from_value::<EntryKind>(v.clone())?),
+            None => None,
+        };
+        let perm = cmd
+            .arg_as_str("perm")
+            .and_then(|p| u32::from_str_radix(p, 8).ok());
+        Ok(SearchQuery {
+            glob,
+            regex,
+            kind,
+            min_size: cmd.arg("minSize").and_then(Value::as_u64),
+            max_size: cmd.arg("maxSize").and_then(Value::as_u64),
+            min_mtime: cmd.arg("minMtime").and_then(Value::as_i64),
+            max_mtime: cmd.arg("maxMtime").and_then(Value::as_i64),
+            perm,
+            gitignore: cmd.arg("gitignore").and_then(Value::as_bool)
+                == Some(true),
+            max_depth: cmd
+                .arg("maxDepth")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize),
+        })
+    }
+}
+
+#[async_trait]
+impl TaskBase for SearchTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "regex": { "type": "string", "minLength": 1 },
+                "ignoreCase": { "type": "boolean" },
+                "type": {
+                    "type": "string",
+                    "enum": ["file", "dir", "symlink"],
+                },
+                "minSize": { "type": "integer", "minimum": 0 },
+                "maxSize": { "type": "integer", "minimum": 0 },
+                "minMtime": { "type": "integer" },
+                "maxMtime": { "type": "integer" },
+                "perm": { "type": "string", "pattern": "^[0-7]{1,4}$" },
+                "gitignore": { "type": "boolean" },
+                "maxDepth": { "type": "integer", "minimum": 1 },
+            },
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        arg: &Arc<TaskArg>,
+        tx: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let query = match self.query(cmd) {
+            Ok(query) => query,
+            Err(err) => return Ok(TaskResult::error(err)),
+        };
+
+        // 仮想ディレクトリ内では検索できない。
+        let root = cmd.cwd.clone();
+        if !Path::new(&root).is_dir() {
+            let err = FsError::NotDir(root);
+            return Ok(TaskResult::error(err.into()));
+        }
+
+        let searcher = Searcher::new(query, &self.time_style);
+        let job = ProgressJob::new();
+        let (pid, dispose, start) =
+            job.spawn(arg.sender.clone(), tx, move |job| {
+                let count = searcher.search(Path::new(&root), job)?;
+                Ok(json!({ "count": count }))
+            });
+
+        Ok(TaskResult::progress(pid, dispose, Some(start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::SearchError,
+        test_helpers::{
+            assert_by_schema, assert_err, create_command, setup_resources,
+            setup_sender, setup_task_arg, sleep, teardown_resources,
+        },
+    };
+
+    use std::sync::Mutex;
+
+    use super::*;
+
+    async fn setup() -> Result<(String, SearchTask, mpsc::Sender<TaskControl>)>
+    {
+        let path = setup_resources("test").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, SearchTask::new("%y/%m/%d %H:%M:%S"), tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/search_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let data = Arc::new(Mutex::new(Value::Null));
+        let result = Arc::new(Mutex::new(Value::Null));
+        let (data_, result_) = (data.clone(), result.clone());
+        let mut sender = setup_sender();
+        sender
+            .expect_progress_data()
+            .times(1)
+            .returning(move |_, v| {
+                *data_.lock().unwrap() = v.clone();
+                Ok(())
+            });
+        sender
+            .expect_progress_end()
+            .times(1)
+            .returning(move |_, v| {
+                *result_.lock().unwrap() = v.clone();
+                Ok(())
+            });
+        let task_arg = setup_task_arg(sender);
+        let args = json!({ "name": "*.txt", "type": "file", "maxDepth": 1 });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Progress(_) = task.run(&cmd, &task_arg, tx).await
+        else {
+            unreachable!();
+        };
+        sleep(100).await;
+        let data = data.lock().unwrap().clone();
+        assert_eq!(data[0]["name"], "test.txt");
+        assert_eq!(data[0]["size"], "4");
+        assert_eq!(*result.lock().unwrap(), json!({ "count": 1 }));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let cmd = create_command(&path, "_", json!({ "regex": "(" }))?;
+        let TaskResult::Error(res) =
+            task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        assert_err(&res.err, &SearchError::InvalidPattern("".to_owned()));
+        let cwd = format!("{path}/test.txt");
+        let cmd = create_command(&cwd, "_", json!({}))?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &FsError::NotDir("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
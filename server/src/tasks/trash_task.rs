@@ -0,0 +1,243 @@
+use crate::{
+    managers::{JournalManager, TrashManager},
+    misc::Command,
+    models::{Operation, RenameItem, TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::{path::Path, sync::Arc};
+use tokio::{sync::mpsc, task::spawn_blocking};
+
+/// ゴミ箱を操作するタスク。
+///
+/// ゴミ箱への移動は操作履歴に記録され、取り消すことができる。
+///
+/// # Fields
+/// * `trash_manager` - TrashManager インスタンス
+/// * `journal_manager` - JournalManager インスタンス
+pub struct TrashTask {
+    trash_manager: Arc<TrashManager>,
+    journal_manager: Arc<JournalManager>,
+}
+
+impl TrashTask {
+    /// 新しい TrashTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `trash_manager` - TrashManager インスタンス
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(
+        trash_manager: Arc<TrashManager>,
+        journal_manager: Arc<JournalManager>,
+    ) -> Self {
+        Self {
+            trash_manager,
+            journal_manager,
+        }
+    }
+
+    /// エントリをゴミ箱に移動し、操作履歴に記録する。
+    ///
+    /// 途中で失敗した場合も、移動済みのエントリは記録する。
+    ///
+    /// # Arguments
+    /// * `trash_manager` - TrashManager インスタンス
+    /// * `journal_manager` - JournalManager インスタンス
+    /// * `srcs` - エントリの絶対パスの配列
+    ///
+    /// # Returns
+    /// 操作後のアイテム一覧
+    fn put(
+        trash_manager: &TrashManager,
+        journal_manager: &JournalManager,
+        srcs: &[String],
+    ) -> Result<Value> {
+        let mut items = Vec::new();
+        let mut res = Ok(());
+        for src in srcs {
+            match trash_manager.put(Path::new(src)) {
+                Ok(id) => items.push(RenameItem {
+                    from: src.clone(),
+                    to: id,
+                }),
+                Err(err) => {
+                    res = Err(err);
+                    break;
+                }
+            }
+        }
+        if !items.is_empty() {
+            journal_manager.record(Operation::Trash { items });
+        }
+        res?;
+        trash_manager.process("list", &[])
+    }
+}
+
+#[async_trait]
+impl TaskBase for TrashTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        if !self.is_valid_args(&cmd.args) {
+            return false;
+        }
+        match cmd.arg_as_str("action") {
+            Some("put") => cmd.arg_as_path_array("sources", &cmd.cwd).is_some(),
+            _ => true,
+        }
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["put", "list", "restore", "purge"],
+                },
+            },
+            "required": ["action"],
+            "oneOf": [
+                {
+                    "properties": {
+                        "action": { "const": "put" },
+                        "sources": {
+                            "type": "array",
+                            "items": { "type": "string", "minLength": 1 },
+                            "minItems": 1,
+                        },
+                    },
+                    "required": ["action", "sources"],
+                    "additionalProperties": false,
+                },
+                {
+                    "properties": {
+                        "action": { "const": "list" },
+                    },
+                    "required": ["action"],
+                    "additionalProperties": false,
+                },
+                {
+                    "properties": {
+                        "action": { "enum": ["restore", "purge"] },
+                        "ids": {
+                            "type": "array",
+                            "items": { "type": "string", "minLength": 1 },
+                            "minItems": 1,
+                        },
+                    },
+                    "required": ["action", "ids"],
+                    "additionalProperties": false,
+                },
+            ],
+        })
+    }
+
+    async fn try_run(
+        &self,
+        cmd: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        let action = cmd.arg_as_str("action").unwrap().to_owned();
+        let targets = match action.as_str() {
+            "put" => cmd.arg_as_path_array("sources", &cmd.cwd).unwrap(),
+            _ => cmd
+                .arg_as_str_array("ids")
+                .unwrap_or_default()
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+        };
+        // デバイスを跨ぐ移動はコピーになり得るため、ブロッキングスレッドで実行する。
+        let manager = self.trash_manager.clone();
+        let journal = self.journal_manager.clone();
+        let res = spawn_blocking(move || match action.as_str() {
+            "put" => Self::put(&manager, &journal, &targets),
+            _ => manager.process(&action, &targets),
+        })
+        .await?;
+        let res = match res {
+            Ok(data) => TaskResult::data(data, None),
+            Err(err) => TaskResult::error(TrashManager::to_error(err)),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::TrashError,
+        test_helpers::{
+            assert_by_schema, assert_err, create_command, setup_resources,
+            setup_sender, setup_task_arg, teardown_resources,
+        },
+    };
+
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    async fn setup()
+    -> Result<(String, Arc<TaskArg>, TrashTask, mpsc::Sender<TaskControl>)>
+    {
+        let path = setup_resources("test").await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let data_home = PathBuf::from(format!("{path}/data"));
+        let manager = TrashManager::new(Some(data_home), "%y/%m/%d %H:%M:%S");
+        let journal = JournalManager::new(&None, manager.clone());
+        let task = TrashTask::new(manager, journal);
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((path, task_arg, task, tx))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, _task_arg, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/trash_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_put_and_restore() -> Result<()> {
+        let (path, task_arg, task, tx) = setup().await?;
+        let args = json!({ "action": "put", "sources": ["test.txt"] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx.clone()).await
+        else {
+            unreachable!();
+        };
+        assert_eq!(res.data[0]["name"], "test.txt");
+        assert!(!Path::new(&format!("{path}/test.txt")).exists());
+        let history = task.journal_manager.history();
+        assert_eq!(history["undo"][0]["kind"], "trash");
+        let id = res.data[0]["id"].as_str().unwrap();
+        let args = json!({ "action": "restore", "ids": [id] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_eq!(res.data, json!([]));
+        assert!(Path::new(&format!("{path}/test.txt")).is_file());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task_arg, task, tx) = setup().await?;
+        let args = json!({ "action": "purge", "ids": ["/path/to/nowhere"] });
+        let cmd = create_command(&path, "_", args)?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &TrashError::NotFound("".to_owned()));
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    managers::JournalManager,
+    misc::Command,
+    models::{TaskArg, TaskControl, TaskResult},
+    traits::TaskBase,
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::{sync::mpsc, task::spawn_blocking};
+
+/// 直近のファイル操作を取り消すタスク。
+///
+/// 取り消した後の操作履歴を返す。
+///
+/// # Fields
+/// * `journal_manager` - JournalManager インスタンス
+pub struct UndoTask {
+    journal_manager: Arc<JournalManager>,
+}
+
+impl UndoTask {
+    /// 新しい UndoTask インスタンスを生成する。
+    ///
+    /// # Arguments
+    /// * `journal_manager` - JournalManager インスタンス
+    pub fn new(journal_manager: Arc<JournalManager>) -> Self {
+        Self { journal_manager }
+    }
+}
+
+#[async_trait]
+impl TaskBase for UndoTask {
+    fn validate(&self, cmd: &Command) -> bool {
+        self.is_valid_args(&cmd.args)
+    }
+
+    fn schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+        })
+    }
+
+    async fn try_run(
+        &self,
+        _: &Command,
+        _: &Arc<TaskArg>,
+        _: mpsc::Sender<TaskControl>,
+    ) -> Result<TaskResult> {
+        // デバイスを跨ぐ移動はコピーになり得るため、ブロッキングスレッドで実行する。
+        let manager = self.journal_manager.clone();
+        let res = spawn_blocking(move || manager.undo()).await?;
+        let res = match res {
+            Ok(data) => TaskResult::data(data, None),
+            Err(err) => TaskResult::error(JournalManager::to_error(err)),
+        };
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::JournalError,
+        models::Operation,
+        test_helpers::{
+            assert_by_schema, assert_err, create_command,
+            setup_journal_manager, setup_resources, setup_sender,
+            setup_task_arg, teardown_resources,
+        },
+    };
+
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    async fn setup() -> Result<(String, UndoTask, mpsc::Sender<TaskControl>)> {
+        let path = setup_resources("").await?;
+        let (tx, _) = mpsc::channel::<TaskControl>(10);
+        Ok((
+            path.clone(),
+            UndoTask::new(setup_journal_manager(&path)),
+            tx,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_validate_by_schema() -> Result<()> {
+        let (path, task, _) = setup().await?;
+        let fx_path = "./tests/fixtures/undo_task.json";
+        assert_by_schema(fx_path, "_", &path, &task).await?;
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_success() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let dir = format!("{path}/new");
+        fs::create_dir(&dir)?;
+        task.journal_manager
+            .record(Operation::Mkdir { path: dir.clone() });
+        let cmd = create_command(&path, "_", json!({}))?;
+        let TaskResult::Data(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_eq!(res.data["undo"], json!([]));
+        assert_eq!(res.data["redo"][0]["path"], dir);
+        assert!(!Path::new(&dir).exists());
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_error() -> Result<()> {
+        let (path, task, tx) = setup().await?;
+        let task_arg = setup_task_arg(setup_sender());
+        let cmd = create_command(&path, "_", json!({}))?;
+        let TaskResult::Error(res) = task.run(&cmd, &task_arg, tx).await else {
+            unreachable!();
+        };
+        assert_err(&res.err, &JournalError::NothingToUndo);
+        teardown_resources(&path).await?;
+        Ok(())
+    }
+}
@@ -4,6 +4,9 @@
 
 use crate::{
     helpers::quote_paths,
+    managers::{
+        ArchiveCacheManager, ArchiveIndexManager, JournalManager, TrashManager,
+    },
     misc::{CmdArgsType, Command, FrameSet, MockSenderTrait},
     models::{Entry, TaskArg},
     traits::{ErrorCode, TaskBase},
@@ -16,6 +19,7 @@ use std::{
     fs as StdFs,
     mem::discriminant,
     os::unix::fs::{PermissionsExt as _, symlink},
+    path::PathBuf,
     process::Command as StdCommand,
     sync::Arc,
     time::Duration,
@@ -153,6 +157,44 @@ pub fn setup_task_arg(sender: MockSenderTrait) -> Arc<TaskArg> {
     Arc::new(TaskArg::new(FrameSet::new(), Arc::new(sender)))
 }
 
+/// テスト用の JournalManager をセットアップする。
+///
+/// 操作履歴はメモリ上でのみ保持し、ゴミ箱は `{path}/data/Trash` を使用する。
+///
+/// # Arguments
+/// * `path` - テスト用ディレクトリのパス
+///
+/// # Returns
+/// JournalManager インスタンス
+pub fn setup_journal_manager(path: &str) -> Arc<JournalManager> {
+    let data_home = PathBuf::from(format!("{path}/data"));
+    let trash = TrashManager::new(Some(data_home), "%y/%m/%d %H:%M:%S");
+    JournalManager::new(&None, trash)
+}
+
+/// テスト用の ArchiveIndexManager をセットアップする。
+///
+/// # Returns
+/// ArchiveIndexManager インスタンス
+pub fn setup_archive_index_manager() -> Arc<ArchiveIndexManager> {
+    ArchiveIndexManager::new(ArchiveIndexManager::DEFAULT_LIMIT)
+}
+
+/// テスト用の ArchiveCacheManager をセットアップする。
+///
+/// キャッシュは `{path}/cache` に保存する。
+///
+/// # Arguments
+/// * `path` - テスト用ディレクトリのパス
+///
+/// # Returns
+/// ArchiveCacheManager インスタンス
+pub fn setup_archive_cache_manager(path: &str) -> Arc<ArchiveCacheManager> {
+    let root = PathBuf::from(format!("{path}/cache"));
+    let limit = ArchiveCacheManager::DEFAULT_LIMIT;
+    ArchiveCacheManager::new(root, limit, setup_archive_index_manager())
+}
+
 /// コマンド文字列を生成する。
 ///
 /// # Arguments